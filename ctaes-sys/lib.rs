@@ -19,6 +19,8 @@ use typenum::{U15, U32};
 use generic_array::{GenericArray, ArrayLength};
 use libc::c_uchar;
 
+use std::ptr;
+
 #[repr(C)]
 #[derive(Debug, Copy, Clone, Default)]
 pub struct AES_state {
@@ -141,6 +143,88 @@ impl AESGCMCipher for AESGCM256 {
     }
 }
 
+/// An expanded AES256 key, kept across calls. Blocks are encrypted and decrypted
+/// in place or into caller-provided buffers, without allocating.
+#[derive(Clone)]
+pub struct AES256 {
+    ctx: AES256_ctx,
+}
+
+impl AES256 {
+    /// Expands an AES256 key.
+    pub fn new(key: &AES256GCMKey) -> AES256 {
+        let mut ctx = AES256_ctx::default();
+        unsafe {
+            AES256_init(&mut ctx, key.as_slice().as_ptr());
+        }
+        AES256 { ctx: ctx }
+    }
+
+    /// Returns the expanded key.
+    pub fn ctx(&self) -> &AES256_ctx {
+        &self.ctx
+    }
+
+    /// Encrypts in place a buffer of whole blocks.
+    pub fn encrypt_blocks(&self, buf: &mut [u8]) -> Result<(), Error> {
+        let blocks = check_blocks(buf.len())?;
+        let p = buf.as_mut_ptr();
+        unsafe {
+            AES256_encrypt(&self.ctx, blocks, p, p);
+        }
+        Ok(())
+    }
+
+    /// Decrypts in place a buffer of whole blocks.
+    pub fn decrypt_blocks(&self, buf: &mut [u8]) -> Result<(), Error> {
+        let blocks = check_blocks(buf.len())?;
+        let p = buf.as_mut_ptr();
+        unsafe {
+            AES256_decrypt(&self.ctx, blocks, p, p);
+        }
+        Ok(())
+    }
+
+    /// Encrypts whole blocks into an output buffer of the same length.
+    pub fn encrypt_blocks_to(&self, plain: &[u8], ciph: &mut [u8]) -> Result<(), Error> {
+        let blocks = check_blocks(plain.len())?;
+        if ciph.len() != plain.len() {
+            return Err(format_err!("invalid length"));
+        }
+        unsafe {
+            AES256_encrypt(&self.ctx, blocks, ciph.as_mut_ptr(), plain.as_ptr());
+        }
+        Ok(())
+    }
+
+    /// Decrypts whole blocks into an output buffer of the same length.
+    pub fn decrypt_blocks_to(&self, ciph: &[u8], plain: &mut [u8]) -> Result<(), Error> {
+        let blocks = check_blocks(ciph.len())?;
+        if plain.len() != ciph.len() {
+            return Err(format_err!("invalid length"));
+        }
+        unsafe {
+            AES256_decrypt(&self.ctx, blocks, plain.as_mut_ptr(), ciph.as_ptr());
+        }
+        Ok(())
+    }
+}
+
+impl Drop for AES256 {
+    fn drop(&mut self) {
+        unsafe {
+            ptr::write_volatile(&mut self.ctx, AES256_ctx::default());
+        }
+    }
+}
+
+fn check_blocks(len: usize) -> Result<usize, Error> {
+    if len % 16 != 0 {
+        return Err(format_err!("invalid length"));
+    }
+    Ok(len / 16)
+}
+
 #[cfg(test)]
 mod tests {
     extern crate hex;
@@ -198,4 +282,49 @@ mod tests {
             assert_eq!(res, test.as_slice())
         }
     }
+
+    #[test]
+    fn aes256_encrypt_blocks_test_vectors() {
+        for v in test_vectors() {
+            let key = *AES256GCMKey::from_slice(hex::decode(v.0).unwrap().as_slice());
+            let cipher = AES256::new(&key);
+            let mut buf = hex::decode(&v.1).unwrap();
+            cipher.encrypt_blocks(&mut buf).unwrap();
+            assert_eq!(buf, hex::decode(v.2).unwrap());
+            cipher.decrypt_blocks(&mut buf).unwrap();
+            assert_eq!(buf, hex::decode(v.1).unwrap())
+        }
+    }
+
+    #[test]
+    fn aes256_encrypt_blocks_to_test_vectors() {
+        let vectors = test_vectors();
+        let key = *AES256GCMKey::from_slice(hex::decode(&vectors[1].0).unwrap().as_slice());
+        let cipher = AES256::new(&key);
+
+        let mut plain = Vec::new();
+        let mut test = Vec::new();
+        for v in &vectors[1..] {
+            plain.extend_from_slice(&hex::decode(&v.1).unwrap());
+            test.extend_from_slice(&hex::decode(&v.2).unwrap());
+        }
+
+        let mut ciph = vec![0u8; plain.len()];
+        cipher.encrypt_blocks_to(&plain, &mut ciph).unwrap();
+        assert_eq!(ciph, test);
+
+        let mut res = vec![0u8; ciph.len()];
+        cipher.decrypt_blocks_to(&ciph, &mut res).unwrap();
+        assert_eq!(res, plain)
+    }
+
+    #[test]
+    fn aes256_encrypt_blocks_fail() {
+        let key = AES256GCMKey::default();
+        let cipher = AES256::new(&key);
+        let mut buf = vec![0u8; 17];
+        assert!(cipher.encrypt_blocks(&mut buf).is_err());
+        let mut ciph = vec![0u8; 32];
+        assert!(cipher.encrypt_blocks_to(&[0u8; 16], &mut ciph).is_err())
+    }
 }
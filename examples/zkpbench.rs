@@ -0,0 +1,58 @@
+// Copyright 2018 Yobicash Ltd. See the COPYRIGHT file at the top-level directory
+// of this distribution.
+//
+// Licensed under the MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>
+// and the Apache 2.0 license <LICENSE-APACHE or https://opensource.org/licenses/Apache-2.0>.
+// This file may not be copied, modified, or distributed except according to those
+// terms.
+
+//! Compares the single and the batch verification of `ZKPProof`s.
+//!
+//! Run with `cargo run --release --example zkpbench [proofs]`.
+
+extern crate yobicrypto;
+
+use yobicrypto::{Random, Scalar, ZKPWitness, ZKPProof};
+
+use std::env;
+use std::time::{Duration, Instant};
+
+const CONTEXT: &[u8] = b"zkpbench";
+
+fn millis(d: Duration) -> f64 {
+    d.as_secs() as f64 * 1e3 + d.subsec_nanos() as f64 / 1e6
+}
+
+fn main() {
+    let len = env::args().nth(1)
+        .map(|s| s.parse::<usize>().expect("the number of proofs"))
+        .unwrap_or(2000);
+
+    let batch: Vec<(ZKPProof, ZKPWitness, Vec<u8>)> = (0..len).map(|_| {
+        let instance = Scalar::random();
+        let witness = ZKPWitness::new(instance.clone()).unwrap();
+        let message = Random::bytes(32);
+        let proof = ZKPProof::prove(instance, &message, CONTEXT).unwrap();
+
+        (proof, witness, message)
+    }).collect();
+
+    let start = Instant::now();
+    for &(ref proof, witness, ref message) in &batch {
+        assert!(proof.verify(witness, message, CONTEXT).unwrap());
+    }
+    let single = millis(start.elapsed());
+
+    let items: Vec<(ZKPProof, ZKPWitness, &[u8])> = batch.iter()
+        .map(|&(ref proof, witness, ref message)| (proof.clone(), witness, &message[..]))
+        .collect();
+
+    let start = Instant::now();
+    assert!(ZKPProof::verify_batch(&items, CONTEXT).unwrap().is_valid());
+    let batched = millis(start.elapsed());
+
+    println!("proofs: {}", len);
+    println!("single: {:.2} ms", single);
+    println!("batch:  {:.2} ms", batched);
+    println!("speed-up: {:.2}x", single / batched);
+}
@@ -0,0 +1,282 @@
+// Copyright 2018 Yobicash Ltd.
+//
+// Licensed under the MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>
+// and the Apache 2.0 license <LICENSE-APACHE or https://opensource.org/licenses/Apache-2.0>.
+// This file may not be copied, modified, or distributed except according to those
+// terms.
+
+//! The `aes` module provides the AES256 block cipher
+//! ([FIPS-197](https://nvlpubs.nist.gov/nistpubs/FIPS/NIST.FIPS.197.pdf)) behind the
+//! `BlockCipher` trait, with two backends: the constant-time bitsliced `ctaes`, and
+//! the AES-NI instructions on x86_64. `AES256` selects AES-NI at runtime when the
+//! CPU supports it, and falls back to `ctaes` otherwise.
+
+use ctaes_sys::AES256 as CtaesAES256;
+
+use error::ErrorKind;
+use result::Result;
+use encrypt::Key;
+
+/// The size in bytes of an AES block.
+pub const AES_BLOCK_SIZE: usize = 16;
+
+/// Trait implemented by block ciphers encrypting whole blocks in place.
+pub trait BlockCipher: Sized {
+    /// The size in bytes of a block.
+    const BLOCK_SIZE: usize;
+
+    /// Creates a new block cipher from a `Key`.
+    fn new(key: &Key) -> Result<Self>;
+
+    /// Encrypts in place a buffer of whole blocks.
+    fn encrypt_blocks(&self, buf: &mut [u8]) -> Result<()>;
+
+    /// Decrypts in place a buffer of whole blocks.
+    fn decrypt_blocks(&self, buf: &mut [u8]) -> Result<()>;
+}
+
+/// The AES256 block cipher, using AES-NI if available and `ctaes` otherwise.
+#[derive(Clone)]
+pub enum AES256 {
+    /// The `ctaes` backend.
+    Ctaes(AES256Ctaes),
+    /// The AES-NI backend.
+    #[cfg(target_arch = "x86_64")]
+    NI(AES256NI),
+}
+
+impl BlockCipher for AES256 {
+    const BLOCK_SIZE: usize = AES_BLOCK_SIZE;
+
+    /// Creates a new `AES256` with the fastest backend supported by the CPU.
+    fn new(key: &Key) -> Result<AES256> {
+        #[cfg(target_arch = "x86_64")]
+        {
+            if let Ok(aes) = AES256NI::new(key) {
+                return Ok(AES256::NI(aes));
+            }
+        }
+
+        AES256Ctaes::new(key).map(AES256::Ctaes)
+    }
+
+    fn encrypt_blocks(&self, buf: &mut [u8]) -> Result<()> {
+        match *self {
+            AES256::Ctaes(ref aes) => aes.encrypt_blocks(buf),
+            #[cfg(target_arch = "x86_64")]
+            AES256::NI(ref aes) => aes.encrypt_blocks(buf),
+        }
+    }
+
+    fn decrypt_blocks(&self, buf: &mut [u8]) -> Result<()> {
+        match *self {
+            AES256::Ctaes(ref aes) => aes.decrypt_blocks(buf),
+            #[cfg(target_arch = "x86_64")]
+            AES256::NI(ref aes) => aes.decrypt_blocks(buf),
+        }
+    }
+}
+
+/// The AES256 block cipher backed by the constant-time bitsliced `ctaes`.
+#[derive(Clone)]
+pub struct AES256Ctaes(CtaesAES256);
+
+impl BlockCipher for AES256Ctaes {
+    const BLOCK_SIZE: usize = AES_BLOCK_SIZE;
+
+    fn new(key: &Key) -> Result<AES256Ctaes> {
+        Ok(AES256Ctaes(CtaesAES256::new(key.as_aes_key())))
+    }
+
+    fn encrypt_blocks(&self, buf: &mut [u8]) -> Result<()> {
+        check_blocks(buf)?;
+
+        self.0.encrypt_blocks(buf)?;
+
+        Ok(())
+    }
+
+    fn decrypt_blocks(&self, buf: &mut [u8]) -> Result<()> {
+        check_blocks(buf)?;
+
+        self.0.decrypt_blocks(buf)?;
+
+        Ok(())
+    }
+}
+
+/// Returns true if the CPU supports the AES-NI instructions.
+pub fn has_aesni() -> bool {
+    #[cfg(target_arch = "x86_64")]
+    {
+        is_x86_feature_detected!("aes")
+    }
+
+    #[cfg(not(target_arch = "x86_64"))]
+    {
+        false
+    }
+}
+
+fn check_blocks(buf: &[u8]) -> Result<()> {
+    if buf.len() % AES_BLOCK_SIZE != 0 {
+        return Err(ErrorKind::InvalidLength.into());
+    }
+
+    Ok(())
+}
+
+#[cfg(target_arch = "x86_64")]
+pub use self::ni::AES256NI;
+
+#[cfg(target_arch = "x86_64")]
+mod ni {
+    use std::arch::x86_64::*;
+    use std::mem;
+
+    use error::ErrorKind;
+    use result::Result;
+    use encrypt::Key;
+    use wipe::wipe_value;
+    use super::{BlockCipher, AES_BLOCK_SIZE, has_aesni, check_blocks};
+
+    /// The AES256 block cipher backed by the AES-NI instructions. Creating it
+    /// fails with `ErrorKind::NotSupported` if the CPU does not support them.
+    #[derive(Clone)]
+    pub struct AES256NI {
+        /// The encryption round keys.
+        enc: [__m128i; 15],
+        /// The decryption round keys, for the equivalent inverse cipher.
+        dec: [__m128i; 15],
+    }
+
+    impl BlockCipher for AES256NI {
+        const BLOCK_SIZE: usize = AES_BLOCK_SIZE;
+
+        fn new(key: &Key) -> Result<AES256NI> {
+            if !has_aesni() {
+                return Err(ErrorKind::NotSupported.into());
+            }
+
+            let (enc, dec) = unsafe { expand_key(key.expose_secret()) };
+
+            Ok(AES256NI {
+                enc: enc,
+                dec: dec,
+            })
+        }
+
+        fn encrypt_blocks(&self, buf: &mut [u8]) -> Result<()> {
+            check_blocks(buf)?;
+
+            for block in buf.chunks_mut(AES_BLOCK_SIZE) {
+                unsafe {
+                    encrypt_block(&self.enc, block);
+                }
+            }
+
+            Ok(())
+        }
+
+        fn decrypt_blocks(&self, buf: &mut [u8]) -> Result<()> {
+            check_blocks(buf)?;
+
+            for block in buf.chunks_mut(AES_BLOCK_SIZE) {
+                unsafe {
+                    decrypt_block(&self.dec, block);
+                }
+            }
+
+            Ok(())
+        }
+    }
+
+    impl Drop for AES256NI {
+        fn drop(&mut self) {
+            let zero: [__m128i; 15] = unsafe { mem::zeroed() };
+
+            wipe_value(&mut self.enc, zero);
+            wipe_value(&mut self.dec, zero);
+        }
+    }
+
+    /// One step of the AES256 key schedule, for the even round keys.
+    #[target_feature(enable = "aes")]
+    unsafe fn expand_even(k: __m128i, t: __m128i) -> __m128i {
+        let t = _mm_shuffle_epi32(t, 0xff);
+        expand_step(k, t)
+    }
+
+    /// One step of the AES256 key schedule, for the odd round keys.
+    #[target_feature(enable = "aes")]
+    unsafe fn expand_odd(k: __m128i, t: __m128i) -> __m128i {
+        let t = _mm_shuffle_epi32(t, 0xaa);
+        expand_step(k, t)
+    }
+
+    #[target_feature(enable = "aes")]
+    unsafe fn expand_step(mut k: __m128i, t: __m128i) -> __m128i {
+        k = _mm_xor_si128(k, _mm_slli_si128(k, 4));
+        k = _mm_xor_si128(k, _mm_slli_si128(k, 4));
+        k = _mm_xor_si128(k, _mm_slli_si128(k, 4));
+        _mm_xor_si128(k, t)
+    }
+
+    /// Expands a 32 bytes key in the encryption and decryption round keys.
+    #[target_feature(enable = "aes")]
+    unsafe fn expand_key(key: &[u8]) -> ([__m128i; 15], [__m128i; 15]) {
+        let mut enc: [__m128i; 15] = mem::zeroed();
+        let mut dec: [__m128i; 15] = mem::zeroed();
+
+        enc[0] = _mm_loadu_si128(key.as_ptr() as *const __m128i);
+        enc[1] = _mm_loadu_si128(key[16..].as_ptr() as *const __m128i);
+
+        enc[2] = expand_even(enc[0], _mm_aeskeygenassist_si128(enc[1], 0x01));
+        enc[3] = expand_odd(enc[1], _mm_aeskeygenassist_si128(enc[2], 0x00));
+        enc[4] = expand_even(enc[2], _mm_aeskeygenassist_si128(enc[3], 0x02));
+        enc[5] = expand_odd(enc[3], _mm_aeskeygenassist_si128(enc[4], 0x00));
+        enc[6] = expand_even(enc[4], _mm_aeskeygenassist_si128(enc[5], 0x04));
+        enc[7] = expand_odd(enc[5], _mm_aeskeygenassist_si128(enc[6], 0x00));
+        enc[8] = expand_even(enc[6], _mm_aeskeygenassist_si128(enc[7], 0x08));
+        enc[9] = expand_odd(enc[7], _mm_aeskeygenassist_si128(enc[8], 0x00));
+        enc[10] = expand_even(enc[8], _mm_aeskeygenassist_si128(enc[9], 0x10));
+        enc[11] = expand_odd(enc[9], _mm_aeskeygenassist_si128(enc[10], 0x00));
+        enc[12] = expand_even(enc[10], _mm_aeskeygenassist_si128(enc[11], 0x20));
+        enc[13] = expand_odd(enc[11], _mm_aeskeygenassist_si128(enc[12], 0x00));
+        enc[14] = expand_even(enc[12], _mm_aeskeygenassist_si128(enc[13], 0x40));
+
+        dec[0] = enc[14];
+        for i in 1..14 {
+            dec[i] = _mm_aesimc_si128(enc[14 - i]);
+        }
+        dec[14] = enc[0];
+
+        (enc, dec)
+    }
+
+    #[target_feature(enable = "aes")]
+    unsafe fn encrypt_block(rk: &[__m128i; 15], block: &mut [u8]) {
+        let mut b = _mm_loadu_si128(block.as_ptr() as *const __m128i);
+
+        b = _mm_xor_si128(b, rk[0]);
+        for k in rk[1..14].iter() {
+            b = _mm_aesenc_si128(b, *k);
+        }
+        b = _mm_aesenclast_si128(b, rk[14]);
+
+        _mm_storeu_si128(block.as_mut_ptr() as *mut __m128i, b);
+    }
+
+    #[target_feature(enable = "aes")]
+    unsafe fn decrypt_block(rk: &[__m128i; 15], block: &mut [u8]) {
+        let mut b = _mm_loadu_si128(block.as_ptr() as *const __m128i);
+
+        b = _mm_xor_si128(b, rk[0]);
+        for k in rk[1..14].iter() {
+            b = _mm_aesdec_si128(b, *k);
+        }
+        b = _mm_aesdeclast_si128(b, rk[14]);
+
+        _mm_storeu_si128(block.as_mut_ptr() as *mut __m128i, b);
+    }
+}
@@ -18,6 +18,7 @@ use traits::Validate;
 use traits::{BinarySerialize, HexSerialize};
 use hash::Digest;
 use memory::Memory;
+use wipe::Wipe;
 
 use std::fmt;
 
@@ -233,6 +234,7 @@ impl BalloonHasher {
         buf_0.extend_from_slice(&self.salt.to_bytes()?);
 
         buf[0] = Digest::hash(&buf_0);
+        buf_0.wipe();
 
         for m in 1..self.params.s_cost as usize {
 
@@ -242,6 +244,7 @@ impl BalloonHasher {
             buf_m_1.extend_from_slice(&buf[m-1].to_bytes()?);
 
             buf[m] = Digest::hash(&buf_m_1);
+            buf_m_1.wipe();
         }
 
         // TODO: fix the algo online, contact the guys (t > 0)
@@ -249,7 +252,7 @@ impl BalloonHasher {
             // TODO: fix the algo online, contact the guys
             for m in 1..(self.params.s_cost-1) as usize {
 
-                let prev = buf[(m-1 as usize) % self.params.s_cost as usize];
+                let mut prev = buf[(m-1 as usize) % self.params.s_cost as usize];
                 let mut buf_m_2 = Vec::new();
                 buf_m_2.write_u32::<BigEndian>(cnt)?;
                 cnt += 1;
@@ -257,6 +260,8 @@ impl BalloonHasher {
                 buf_m_2.extend_from_slice(&buf[m].to_bytes()?);
 
                 buf[m] = Digest::hash(&buf_m_2);
+                buf_m_2.wipe();
+                prev.0.as_mut_slice().wipe();
 
                 for i in 0..(self.params.delta-1) as usize {
                     // NB: block obtained by hashing
@@ -287,11 +292,18 @@ impl BalloonHasher {
                     buf_i_2.extend_from_slice(&buf[other as usize].to_bytes()?);
 
                     buf[m] = Digest::hash(&buf_i_2);
+                    buf_i_2.wipe();
                 }
             }
         }
 
-        Ok(buf[(self.params.s_cost-1) as usize])
+        let digest = buf[(self.params.s_cost-1) as usize];
+
+        for d in buf.iter_mut() {
+            d.0.as_mut_slice().wipe();
+        }
+
+        Ok(digest)
     }
 }
 
@@ -0,0 +1,425 @@
+// Copyright 2018 Yobicash Ltd.
+//
+// Licensed under the MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>
+// and the Apache 2.0 license <LICENSE-APACHE or https://opensource.org/licenses/Apache-2.0>.
+// This file may not be copied, modified, or distributed except according to those
+// terms.
+
+//! The `chacha` module provides the ChaCha20-Poly1305 authenticated cipher
+//! ([RFC 8439](https://tools.ietf.org/html/rfc8439)) and its extended-nonce
+//! variant XChaCha20-Poly1305
+//! ([draft-irtf-cfrg-xchacha](https://tools.ietf.org/html/draft-irtf-cfrg-xchacha-03)).
+
+use byteorder::{LittleEndian, ByteOrder};
+use subtle::slices_equal;
+
+use error::ErrorKind;
+use result::Result;
+use traits::BinarySerialize;
+use encrypt::Key;
+use cipher::AEADCipher;
+use wipe::Wipe;
+
+/// The size in bytes of a ChaCha20-Poly1305 nonce.
+pub const CHACHA_NONCE_SIZE: usize = 12;
+
+/// The size in bytes of a XChaCha20-Poly1305 nonce.
+pub const XCHACHA_NONCE_SIZE: usize = 24;
+
+/// The size in bytes of a Poly1305 authentication tag.
+pub const POLY1305_TAG_SIZE: usize = 16;
+
+/// The ChaCha20-Poly1305 authenticated cipher.
+#[derive(Clone)]
+pub struct ChaCha20Poly1305 {
+    key: [u32; 8],
+}
+
+impl AEADCipher for ChaCha20Poly1305 {
+    const NONCE_SIZE: usize = CHACHA_NONCE_SIZE;
+
+    const TAG_SIZE: usize = POLY1305_TAG_SIZE;
+
+    fn new(key: Key) -> Result<ChaCha20Poly1305> {
+        let mut _key = [0u32; 8];
+        LittleEndian::read_u32_into(key.expose_secret(), &mut _key);
+
+        Ok(ChaCha20Poly1305 {
+            key: _key,
+        })
+    }
+
+    fn encrypt(&self, nonce: &[u8], ad: &[u8], plain: &[u8]) -> Result<Vec<u8>> {
+        if nonce.len() != CHACHA_NONCE_SIZE {
+            return Err(ErrorKind::InvalidLength.into());
+        }
+
+        let mut _nonce = [0u32; 3];
+        LittleEndian::read_u32_into(nonce, &mut _nonce);
+
+        let mut cyph = plain.to_owned();
+        chacha20_xor(&self.key, &_nonce, 1, &mut cyph);
+
+        let tag = self.tag(&_nonce, ad, &cyph);
+        cyph.extend_from_slice(&tag);
+
+        Ok(cyph)
+    }
+
+    fn decrypt(&self, nonce: &[u8], ad: &[u8], cyph: &[u8]) -> Result<Vec<u8>> {
+        if nonce.len() != CHACHA_NONCE_SIZE {
+            return Err(ErrorKind::InvalidLength.into());
+        }
+
+        let len = cyph.len();
+        if len < POLY1305_TAG_SIZE {
+            return Err(ErrorKind::InvalidLength.into());
+        }
+
+        let (cyph, tag) = cyph.split_at(len - POLY1305_TAG_SIZE);
+
+        let mut _nonce = [0u32; 3];
+        LittleEndian::read_u32_into(nonce, &mut _nonce);
+
+        if slices_equal(&self.tag(&_nonce, ad, cyph), tag) != 1 {
+            return Err(ErrorKind::InvalidTag.into());
+        }
+
+        let mut plain = cyph.to_owned();
+        chacha20_xor(&self.key, &_nonce, 1, &mut plain);
+
+        Ok(plain)
+    }
+}
+
+impl Drop for ChaCha20Poly1305 {
+    fn drop(&mut self) {
+        self.key.wipe();
+    }
+}
+
+impl ChaCha20Poly1305 {
+    /// Computes the Poly1305 tag of a cyphertext and its associated data.
+    fn tag(&self, nonce: &[u32; 3], ad: &[u8], cyph: &[u8]) -> [u8; 16] {
+        let block = chacha20_block(&self.key, nonce, 0);
+
+        let mut poly_key = [0u8; 32];
+        LittleEndian::write_u32_into(&block[..8], &mut poly_key);
+
+        let padding = [0u8; 16];
+
+        let mut poly = Poly1305::new(&poly_key);
+        poly_key.wipe();
+        poly.update(ad);
+        poly.update(&padding[..(16 - ad.len() % 16) % 16]);
+        poly.update(cyph);
+        poly.update(&padding[..(16 - cyph.len() % 16) % 16]);
+
+        let mut lengths = [0u8; 16];
+        LittleEndian::write_u64(&mut lengths[..8], ad.len() as u64);
+        LittleEndian::write_u64(&mut lengths[8..], cyph.len() as u64);
+        poly.update(&lengths);
+
+        poly.finalize()
+    }
+}
+
+/// The XChaCha20-Poly1305 authenticated cipher, with 192 bits nonces that can
+/// be safely chosen at random.
+#[derive(Clone)]
+pub struct XChaCha20Poly1305 {
+    key: Key,
+}
+
+impl AEADCipher for XChaCha20Poly1305 {
+    const NONCE_SIZE: usize = XCHACHA_NONCE_SIZE;
+
+    const TAG_SIZE: usize = POLY1305_TAG_SIZE;
+
+    fn new(key: Key) -> Result<XChaCha20Poly1305> {
+        Ok(XChaCha20Poly1305 {
+            key: key,
+        })
+    }
+
+    fn encrypt(&self, nonce: &[u8], ad: &[u8], plain: &[u8]) -> Result<Vec<u8>> {
+        let (cipher, _nonce) = self.subcipher(nonce)?;
+
+        cipher.encrypt(&_nonce, ad, plain)
+    }
+
+    fn decrypt(&self, nonce: &[u8], ad: &[u8], cyph: &[u8]) -> Result<Vec<u8>> {
+        let (cipher, _nonce) = self.subcipher(nonce)?;
+
+        cipher.decrypt(&_nonce, ad, cyph)
+    }
+}
+
+impl XChaCha20Poly1305 {
+    /// Derives the ChaCha20-Poly1305 subkey and nonce from the extended nonce.
+    fn subcipher(&self, nonce: &[u8]) -> Result<(ChaCha20Poly1305, [u8; CHACHA_NONCE_SIZE])> {
+        if nonce.len() != XCHACHA_NONCE_SIZE {
+            return Err(ErrorKind::InvalidLength.into());
+        }
+
+        let mut subkey = hchacha20_subkey(self.key.expose_secret(), &nonce[..16]);
+
+        let mut _nonce = [0u8; CHACHA_NONCE_SIZE];
+        _nonce[4..].copy_from_slice(&nonce[16..]);
+
+        let key = Key::from_bytes(&subkey);
+        subkey.wipe();
+        let cipher = ChaCha20Poly1305::new(key?)?;
+
+        Ok((cipher, _nonce))
+    }
+}
+
+/// The ChaCha20 constants "expand 32-byte k".
+const SIGMA: [u32; 4] = [0x6170_7865, 0x3320_646e, 0x7962_2d32, 0x6b20_6574];
+
+fn quarter_round(s: &mut [u32; 16], a: usize, b: usize, c: usize, d: usize) {
+    s[a] = s[a].wrapping_add(s[b]); s[d] ^= s[a]; s[d] = s[d].rotate_left(16);
+    s[c] = s[c].wrapping_add(s[d]); s[b] ^= s[c]; s[b] = s[b].rotate_left(12);
+    s[a] = s[a].wrapping_add(s[b]); s[d] ^= s[a]; s[d] = s[d].rotate_left(8);
+    s[c] = s[c].wrapping_add(s[d]); s[b] ^= s[c]; s[b] = s[b].rotate_left(7);
+}
+
+fn double_rounds(s: &mut [u32; 16]) {
+    for _ in 0..10 {
+        quarter_round(s, 0, 4, 8, 12);
+        quarter_round(s, 1, 5, 9, 13);
+        quarter_round(s, 2, 6, 10, 14);
+        quarter_round(s, 3, 7, 11, 15);
+        quarter_round(s, 0, 5, 10, 15);
+        quarter_round(s, 1, 6, 11, 12);
+        quarter_round(s, 2, 7, 8, 13);
+        quarter_round(s, 3, 4, 9, 14);
+    }
+}
+
+fn initial_state(key: &[u32; 8], words: &[u32; 4]) -> [u32; 16] {
+    let mut state = [0u32; 16];
+    state[..4].copy_from_slice(&SIGMA);
+    state[4..12].copy_from_slice(key);
+    state[12..].copy_from_slice(words);
+    state
+}
+
+/// Computes the ChaCha20 block of a key, nonce and block counter.
+fn chacha20_block(key: &[u32; 8], nonce: &[u32; 3], counter: u32) -> [u32; 16] {
+    let init = initial_state(key, &[counter, nonce[0], nonce[1], nonce[2]]);
+
+    let mut state = init;
+    double_rounds(&mut state);
+
+    for i in 0..16 {
+        state[i] = state[i].wrapping_add(init[i]);
+    }
+
+    state
+}
+
+/// XORs `buf` with the ChaCha20 keystream starting at the block `counter`.
+fn chacha20_xor(key: &[u32; 8], nonce: &[u32; 3], counter: u32, buf: &mut [u8]) {
+    let mut keystream = [0u8; 64];
+
+    for (i, chunk) in buf.chunks_mut(64).enumerate() {
+        let block = chacha20_block(key, nonce, counter.wrapping_add(i as u32));
+        LittleEndian::write_u32_into(&block, &mut keystream);
+
+        for (b, k) in chunk.iter_mut().zip(keystream.iter()) {
+            *b ^= *k;
+        }
+    }
+
+    keystream.wipe();
+}
+
+/// Derives a subkey from a key and a 16 bytes nonce with HChaCha20.
+fn hchacha20_subkey(key: &[u8], nonce: &[u8]) -> [u8; 32] {
+    let mut _key = [0u32; 8];
+    LittleEndian::read_u32_into(key, &mut _key);
+
+    let mut _nonce = [0u32; 4];
+    LittleEndian::read_u32_into(nonce, &mut _nonce);
+
+    let mut state = initial_state(&_key, &_nonce);
+    double_rounds(&mut state);
+
+    let mut subkey = [0u8; 32];
+    LittleEndian::write_u32_into(&state[..4], &mut subkey[..16]);
+    LittleEndian::write_u32_into(&state[12..], &mut subkey[16..]);
+
+    _key.wipe();
+    state.wipe();
+
+    subkey
+}
+
+/// The Poly1305 one-time authenticator, with 26 bits limbs.
+struct Poly1305 {
+    r: [u32; 5],
+    h: [u32; 5],
+    pad: [u32; 4],
+    buffer: [u8; 16],
+    leftover: usize,
+}
+
+impl Poly1305 {
+    fn new(key: &[u8; 32]) -> Poly1305 {
+        let r = [
+            LittleEndian::read_u32(&key[0..]) & 0x3ff_ffff,
+            (LittleEndian::read_u32(&key[3..]) >> 2) & 0x3ff_ff03,
+            (LittleEndian::read_u32(&key[6..]) >> 4) & 0x3ff_c0ff,
+            (LittleEndian::read_u32(&key[9..]) >> 6) & 0x3f0_3fff,
+            (LittleEndian::read_u32(&key[12..]) >> 8) & 0x00f_ffff,
+        ];
+
+        let mut pad = [0u32; 4];
+        LittleEndian::read_u32_into(&key[16..], &mut pad);
+
+        Poly1305 {
+            r: r,
+            h: [0u32; 5],
+            pad: pad,
+            buffer: [0u8; 16],
+            leftover: 0,
+        }
+    }
+
+    fn update(&mut self, mut data: &[u8]) {
+        if self.leftover > 0 {
+            let want = ::std::cmp::min(16 - self.leftover, data.len());
+            self.buffer[self.leftover..self.leftover + want].copy_from_slice(&data[..want]);
+            self.leftover += want;
+            data = &data[want..];
+
+            if self.leftover < 16 {
+                return;
+            }
+
+            let block = self.buffer;
+            self.block(&block, 1 << 24);
+            self.leftover = 0;
+        }
+
+        while data.len() >= 16 {
+            self.block(&data[..16], 1 << 24);
+            data = &data[16..];
+        }
+
+        self.buffer[..data.len()].copy_from_slice(data);
+        self.leftover = data.len();
+    }
+
+    fn block(&mut self, m: &[u8], hibit: u32) {
+        let r = self.r;
+        let s1 = r[1] * 5;
+        let s2 = r[2] * 5;
+        let s3 = r[3] * 5;
+        let s4 = r[4] * 5;
+
+        let mut h = self.h;
+        h[0] += LittleEndian::read_u32(&m[0..]) & 0x3ff_ffff;
+        h[1] += (LittleEndian::read_u32(&m[3..]) >> 2) & 0x3ff_ffff;
+        h[2] += (LittleEndian::read_u32(&m[6..]) >> 4) & 0x3ff_ffff;
+        h[3] += (LittleEndian::read_u32(&m[9..]) >> 6) & 0x3ff_ffff;
+        h[4] += (LittleEndian::read_u32(&m[12..]) >> 8) | hibit;
+
+        let mul = |a: u32, b: u32| u64::from(a) * u64::from(b);
+
+        let d0 = mul(h[0], r[0]) + mul(h[1], s4) + mul(h[2], s3) + mul(h[3], s2) + mul(h[4], s1);
+        let mut d1 = mul(h[0], r[1]) + mul(h[1], r[0]) + mul(h[2], s4) + mul(h[3], s3) + mul(h[4], s2);
+        let mut d2 = mul(h[0], r[2]) + mul(h[1], r[1]) + mul(h[2], r[0]) + mul(h[3], s4) + mul(h[4], s3);
+        let mut d3 = mul(h[0], r[3]) + mul(h[1], r[2]) + mul(h[2], r[1]) + mul(h[3], r[0]) + mul(h[4], s4);
+        let mut d4 = mul(h[0], r[4]) + mul(h[1], r[3]) + mul(h[2], r[2]) + mul(h[3], r[1]) + mul(h[4], r[0]);
+
+        let mut c = d0 >> 26;
+        h[0] = d0 as u32 & 0x3ff_ffff;
+        d1 += c; c = d1 >> 26; h[1] = d1 as u32 & 0x3ff_ffff;
+        d2 += c; c = d2 >> 26; h[2] = d2 as u32 & 0x3ff_ffff;
+        d3 += c; c = d3 >> 26; h[3] = d3 as u32 & 0x3ff_ffff;
+        d4 += c; c = d4 >> 26; h[4] = d4 as u32 & 0x3ff_ffff;
+        h[0] += c as u32 * 5;
+        let c = h[0] >> 26;
+        h[0] &= 0x3ff_ffff;
+        h[1] += c;
+
+        self.h = h;
+    }
+
+    fn finalize(mut self) -> [u8; 16] {
+        if self.leftover > 0 {
+            let mut block = [0u8; 16];
+            block[..self.leftover].copy_from_slice(&self.buffer[..self.leftover]);
+            block[self.leftover] = 1;
+            self.block(&block, 0);
+        }
+
+        let mut h = self.h;
+
+        let mut c = h[1] >> 26; h[1] &= 0x3ff_ffff;
+        h[2] += c; c = h[2] >> 26; h[2] &= 0x3ff_ffff;
+        h[3] += c; c = h[3] >> 26; h[3] &= 0x3ff_ffff;
+        h[4] += c; c = h[4] >> 26; h[4] &= 0x3ff_ffff;
+        h[0] += c * 5; c = h[0] >> 26; h[0] &= 0x3ff_ffff;
+        h[1] += c;
+
+        // NB: computes h + -p, selecting it in constant time if h >= p
+        let mut g = [0u32; 5];
+        g[0] = h[0].wrapping_add(5); c = g[0] >> 26; g[0] &= 0x3ff_ffff;
+        g[1] = h[1].wrapping_add(c); c = g[1] >> 26; g[1] &= 0x3ff_ffff;
+        g[2] = h[2].wrapping_add(c); c = g[2] >> 26; g[2] &= 0x3ff_ffff;
+        g[3] = h[3].wrapping_add(c); c = g[3] >> 26; g[3] &= 0x3ff_ffff;
+        g[4] = h[4].wrapping_add(c).wrapping_sub(1 << 26);
+
+        let mask = (g[4] >> 31).wrapping_sub(1);
+        for i in 0..5 {
+            h[i] = (h[i] & !mask) | (g[i] & mask);
+        }
+
+        let h0 = h[0] | (h[1] << 26);
+        let h1 = (h[1] >> 6) | (h[2] << 20);
+        let h2 = (h[2] >> 12) | (h[3] << 14);
+        let h3 = (h[3] >> 18) | (h[4] << 8);
+
+        let mut f = u64::from(h0) + u64::from(self.pad[0]);
+        let t0 = f as u32;
+        f = u64::from(h1) + u64::from(self.pad[1]) + (f >> 32);
+        let t1 = f as u32;
+        f = u64::from(h2) + u64::from(self.pad[2]) + (f >> 32);
+        let t2 = f as u32;
+        f = u64::from(h3) + u64::from(self.pad[3]) + (f >> 32);
+        let t3 = f as u32;
+
+        let mut tag = [0u8; 16];
+        LittleEndian::write_u32_into(&[t0, t1, t2, t3], &mut tag);
+        tag
+    }
+}
+
+impl Drop for Poly1305 {
+    fn drop(&mut self) {
+        self.r.wipe();
+        self.h.wipe();
+        self.pad.wipe();
+        self.buffer.wipe();
+    }
+}
+
+/// Computes the Poly1305 tag of a message under a one-time key.
+pub fn poly1305(key: &[u8; 32], msg: &[u8]) -> [u8; 16] {
+    let mut poly = Poly1305::new(key);
+    poly.update(msg);
+    poly.finalize()
+}
+
+/// Computes the HChaCha20 subkey of a 32 bytes key and a 16 bytes nonce.
+pub fn hchacha20(key: &[u8], nonce: &[u8]) -> Result<[u8; 32]> {
+    if key.len() != 32 || nonce.len() != 16 {
+        return Err(ErrorKind::InvalidLength.into());
+    }
+
+    Ok(hchacha20_subkey(key, nonce))
+}
@@ -0,0 +1,200 @@
+// Copyright 2018 Yobicash Ltd.
+//
+// Licensed under the MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>
+// and the Apache 2.0 license <LICENSE-APACHE or https://opensource.org/licenses/Apache-2.0>.
+// This file may not be copied, modified, or distributed except according to those
+// terms.
+
+//! The `cipher` module provides the authenticated cipher trait and the selection
+//! of the cipher used throughout `yobicrypto`.
+//!
+//! The functions that do not take a `CipherAlgorithm` use `CipherAlgorithm::default`.
+//! A deployment chooses it once at start-up with `CipherAlgorithm::set_default`,
+//! without changing the call sites, and `DEFAULT_CIPHER_ALGORITHM` is used otherwise.
+
+use error::ErrorKind;
+use result::Result;
+use encrypt::Key;
+use gcm::{AES256GCM, GCM_NONCE_SIZE, GCM_TAG_SIZE};
+use siv::{AES256GCMSIV, GCM_SIV_NONCE_SIZE, GCM_SIV_TAG_SIZE};
+use chacha::{ChaCha20Poly1305, XChaCha20Poly1305};
+use chacha::{CHACHA_NONCE_SIZE, XCHACHA_NONCE_SIZE, POLY1305_TAG_SIZE};
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Trait for authenticated ciphers with associated data.
+pub trait AEADCipher: Sized {
+    /// The size in bytes of the nonce.
+    const NONCE_SIZE: usize;
+
+    /// The size in bytes of the authentication tag.
+    const TAG_SIZE: usize;
+
+    /// Creates the cipher from a `Key`.
+    fn new(key: Key) -> Result<Self>;
+
+    /// Encrypts a plaintext, authenticating it together with the associated data.
+    /// Returns the cyphertext followed by the authentication tag.
+    fn encrypt(&self, nonce: &[u8], ad: &[u8], plain: &[u8]) -> Result<Vec<u8>>;
+
+    /// Decrypts a cyphertext produced by `encrypt`, failing with `ErrorKind::InvalidTag`
+    /// if the cyphertext or the associated data have been tampered with.
+    fn decrypt(&self, nonce: &[u8], ad: &[u8], cyph: &[u8]) -> Result<Vec<u8>>;
+}
+
+/// The `CipherAlgorithm` used by the functions that do not take one, unless
+/// another one is set with `CipherAlgorithm::set_default`.
+pub const DEFAULT_CIPHER_ALGORITHM: CipherAlgorithm = CipherAlgorithm::AES256GCM;
+
+/// The identifier of the process-wide default `CipherAlgorithm`, 0 until it is
+/// set or first read.
+static DEFAULT_CIPHER: AtomicUsize = AtomicUsize::new(0);
+
+/// The authenticated ciphers available in `yobicrypto`.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Serialize, Deserialize)]
+pub enum CipherAlgorithm {
+    /// AES256-GCM.
+    AES256GCM,
+    /// ChaCha20-Poly1305.
+    ChaCha20Poly1305,
+    /// XChaCha20-Poly1305.
+    XChaCha20Poly1305,
+    /// AES256-GCM-SIV.
+    AES256GCMSIV,
+}
+
+impl CipherAlgorithm {
+    /// Creates a `CipherAlgorithm` from its identifier.
+    pub fn from_u8(id: u8) -> Result<CipherAlgorithm> {
+        match id {
+            1 => Ok(CipherAlgorithm::AES256GCM),
+            2 => Ok(CipherAlgorithm::ChaCha20Poly1305),
+            3 => Ok(CipherAlgorithm::XChaCha20Poly1305),
+            4 => Ok(CipherAlgorithm::AES256GCMSIV),
+            _ => Err(ErrorKind::NotSupported.into()),
+        }
+    }
+
+    /// Returns the identifier of the `CipherAlgorithm`.
+    pub fn to_u8(&self) -> u8 {
+        match *self {
+            CipherAlgorithm::AES256GCM => 1,
+            CipherAlgorithm::ChaCha20Poly1305 => 2,
+            CipherAlgorithm::XChaCha20Poly1305 => 3,
+            CipherAlgorithm::AES256GCMSIV => 4,
+        }
+    }
+
+    /// Returns the nonce size of the `CipherAlgorithm`.
+    pub fn nonce_size(&self) -> usize {
+        match *self {
+            CipherAlgorithm::AES256GCM => GCM_NONCE_SIZE,
+            CipherAlgorithm::ChaCha20Poly1305 => CHACHA_NONCE_SIZE,
+            CipherAlgorithm::XChaCha20Poly1305 => XCHACHA_NONCE_SIZE,
+            CipherAlgorithm::AES256GCMSIV => GCM_SIV_NONCE_SIZE,
+        }
+    }
+
+    /// Returns the tag size of the `CipherAlgorithm`.
+    pub fn tag_size(&self) -> usize {
+        match *self {
+            CipherAlgorithm::AES256GCM => GCM_TAG_SIZE,
+            CipherAlgorithm::ChaCha20Poly1305 => POLY1305_TAG_SIZE,
+            CipherAlgorithm::XChaCha20Poly1305 => POLY1305_TAG_SIZE,
+            CipherAlgorithm::AES256GCMSIV => GCM_SIV_TAG_SIZE,
+        }
+    }
+
+    /// Sets the process-wide `CipherAlgorithm` returned by `CipherAlgorithm::default`,
+    /// and so used by the functions that do not take one.
+    ///
+    /// The default can be set only once, before it is first read: afterwards it
+    /// fails with `ErrorKind::AlreadyFound`, unless it sets the same algorithm.
+    pub fn set_default(algorithm: CipherAlgorithm) -> Result<()> {
+        let id = algorithm.to_u8() as usize;
+
+        match DEFAULT_CIPHER.compare_exchange(0, id, Ordering::SeqCst, Ordering::SeqCst) {
+            Ok(_) => Ok(()),
+            Err(current) if current == id => Ok(()),
+            Err(_) => Err(ErrorKind::AlreadyFound.into()),
+        }
+    }
+}
+
+impl Default for CipherAlgorithm {
+    fn default() -> CipherAlgorithm {
+        // NB: the first read fixes the default, so it cannot change while in use
+        let default_id = DEFAULT_CIPHER_ALGORITHM.to_u8() as usize;
+        let id = match DEFAULT_CIPHER.compare_exchange(0, default_id, Ordering::SeqCst, Ordering::SeqCst) {
+            Ok(_) => default_id,
+            Err(current) => current,
+        };
+
+        CipherAlgorithm::from_u8(id as u8).unwrap_or(DEFAULT_CIPHER_ALGORITHM)
+    }
+}
+
+/// An authenticated cipher chosen at runtime.
+#[derive(Clone)]
+pub enum Cipher {
+    /// AES256-GCM.
+    AES256GCM(AES256GCM),
+    /// ChaCha20-Poly1305.
+    ChaCha20Poly1305(ChaCha20Poly1305),
+    /// XChaCha20-Poly1305.
+    XChaCha20Poly1305(XChaCha20Poly1305),
+    /// AES256-GCM-SIV.
+    AES256GCMSIV(AES256GCMSIV),
+}
+
+impl Cipher {
+    /// Creates a new `Cipher`.
+    pub fn new(algorithm: CipherAlgorithm, key: Key) -> Result<Cipher> {
+        let cipher = match algorithm {
+            CipherAlgorithm::AES256GCM => {
+                Cipher::AES256GCM(AES256GCM::new(key)?)
+            },
+            CipherAlgorithm::ChaCha20Poly1305 => {
+                Cipher::ChaCha20Poly1305(ChaCha20Poly1305::new(key)?)
+            },
+            CipherAlgorithm::XChaCha20Poly1305 => {
+                Cipher::XChaCha20Poly1305(XChaCha20Poly1305::new(key)?)
+            },
+            CipherAlgorithm::AES256GCMSIV => {
+                Cipher::AES256GCMSIV(AES256GCMSIV::new(key)?)
+            },
+        };
+
+        Ok(cipher)
+    }
+
+    /// Returns the `CipherAlgorithm` of the `Cipher`.
+    pub fn algorithm(&self) -> CipherAlgorithm {
+        match *self {
+            Cipher::AES256GCM(_) => CipherAlgorithm::AES256GCM,
+            Cipher::ChaCha20Poly1305(_) => CipherAlgorithm::ChaCha20Poly1305,
+            Cipher::XChaCha20Poly1305(_) => CipherAlgorithm::XChaCha20Poly1305,
+            Cipher::AES256GCMSIV(_) => CipherAlgorithm::AES256GCMSIV,
+        }
+    }
+
+    /// Encrypts a plaintext, authenticating it together with the associated data.
+    pub fn encrypt(&self, nonce: &[u8], ad: &[u8], plain: &[u8]) -> Result<Vec<u8>> {
+        match *self {
+            Cipher::AES256GCM(ref c) => c.encrypt(nonce, ad, plain),
+            Cipher::ChaCha20Poly1305(ref c) => c.encrypt(nonce, ad, plain),
+            Cipher::XChaCha20Poly1305(ref c) => c.encrypt(nonce, ad, plain),
+            Cipher::AES256GCMSIV(ref c) => c.encrypt(nonce, ad, plain),
+        }
+    }
+
+    /// Decrypts a cyphertext produced by `encrypt`.
+    pub fn decrypt(&self, nonce: &[u8], ad: &[u8], cyph: &[u8]) -> Result<Vec<u8>> {
+        match *self {
+            Cipher::AES256GCM(ref c) => c.decrypt(nonce, ad, cyph),
+            Cipher::ChaCha20Poly1305(ref c) => c.decrypt(nonce, ad, cyph),
+            Cipher::XChaCha20Poly1305(ref c) => c.decrypt(nonce, ad, cyph),
+            Cipher::AES256GCMSIV(ref c) => c.decrypt(nonce, ad, cyph),
+        }
+    }
+}
@@ -0,0 +1,145 @@
+// Copyright 2018 Yobicash Ltd.
+//
+// Licensed under the MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>
+// and the Apache 2.0 license <LICENSE-APACHE or https://opensource.org/licenses/Apache-2.0>.
+// This file may not be copied, modified, or distributed except according to those
+// terms.
+
+//! The `cmac` module provides the AES256-CMAC message authentication code
+//! ([NIST SP 800-38B](https://nvlpubs.nist.gov/nistpubs/SpecialPublications/NIST.SP.800-38B.pdf),
+//! [RFC 4493](https://tools.ietf.org/html/rfc4493) with a 256 bits key).
+
+use subtle::slices_equal;
+
+use error::ErrorKind;
+use result::Result;
+use wipe::Wipe;
+use aes::{BlockCipher, AES256, AES_BLOCK_SIZE};
+use encrypt::Key;
+
+/// The size in bytes of an AES256-CMAC tag.
+pub const CMAC_SIZE: usize = 16;
+
+/// The constant of the subkey generation for a 128 bits block size.
+const CMAC_RB: u8 = 0x87;
+
+/// The AES256-CMAC of a message, computed incrementally.
+#[derive(Clone)]
+pub struct AES256CMAC {
+    aes: AES256,
+    k1: [u8; 16],
+    k2: [u8; 16],
+    state: [u8; 16],
+    buf: [u8; 16],
+    buf_len: usize,
+}
+
+impl AES256CMAC {
+    /// Creates a new `AES256CMAC` from a `Key`.
+    pub fn new(key: &Key) -> Result<AES256CMAC> {
+        let aes = AES256::new(key)?;
+
+        let mut l = [0u8; 16];
+        aes.encrypt_blocks(&mut l)?;
+
+        let k1 = double(&l);
+        let k2 = double(&k1);
+        l.wipe();
+
+        Ok(AES256CMAC {
+            aes: aes,
+            k1: k1,
+            k2: k2,
+            state: [0u8; 16],
+            buf: [0u8; 16],
+            buf_len: 0,
+        })
+    }
+
+    /// Processes a part of the message.
+    pub fn update(&mut self, data: &[u8]) -> Result<()> {
+        let mut data = data;
+
+        while !data.is_empty() {
+            // NB: a full block is processed only when more data follows, as the
+            // last block is processed differently by `finalize`
+            if self.buf_len == AES_BLOCK_SIZE {
+                for i in 0..AES_BLOCK_SIZE {
+                    self.state[i] ^= self.buf[i];
+                }
+                self.aes.encrypt_blocks(&mut self.state)?;
+                self.buf_len = 0;
+            }
+
+            let len = ::std::cmp::min(AES_BLOCK_SIZE - self.buf_len, data.len());
+            self.buf[self.buf_len..self.buf_len + len].copy_from_slice(&data[..len]);
+            self.buf_len += len;
+            data = &data[len..];
+        }
+
+        Ok(())
+    }
+
+    /// Returns the tag of the message.
+    pub fn finalize(mut self) -> Result<[u8; 16]> {
+        let subkey = if self.buf_len == AES_BLOCK_SIZE {
+            self.k1
+        } else {
+            self.buf[self.buf_len] = 0x80;
+            for b in self.buf[self.buf_len + 1..].iter_mut() {
+                *b = 0;
+            }
+
+            self.k2
+        };
+
+        for i in 0..AES_BLOCK_SIZE {
+            self.state[i] ^= self.buf[i] ^ subkey[i];
+        }
+        self.aes.encrypt_blocks(&mut self.state)?;
+
+        Ok(self.state)
+    }
+
+    /// Verifies in constant time the tag of the message.
+    pub fn verify(self, tag: &[u8]) -> Result<()> {
+        let expected = self.finalize()?;
+
+        if tag.len() != CMAC_SIZE || slices_equal(&expected, tag) != 1 {
+            return Err(ErrorKind::InvalidTag.into());
+        }
+
+        Ok(())
+    }
+}
+
+impl Drop for AES256CMAC {
+    fn drop(&mut self) {
+        self.k1.wipe();
+        self.k2.wipe();
+        self.state.wipe();
+        self.buf.wipe();
+    }
+}
+
+/// Computes the AES256-CMAC of a message.
+pub fn cmac_aes256(key: &Key, msg: &[u8]) -> Result<[u8; 16]> {
+    let mut cmac = AES256CMAC::new(key)?;
+    cmac.update(msg)?;
+    cmac.finalize()
+}
+
+/// Doubles a block in GF(2^128), for the subkey generation.
+fn double(block: &[u8; 16]) -> [u8; 16] {
+    let mut res = [0u8; 16];
+
+    for i in 0..15 {
+        res[i] = (block[i] << 1) | (block[i + 1] >> 7);
+    }
+    res[15] = block[15] << 1;
+
+    // NB: constant-time conditional reduction
+    res[15] ^= CMAC_RB & (0u8.wrapping_sub(block[0] >> 7));
+
+    res
+}
@@ -12,7 +12,8 @@ use generic_array::GenericArray;
 use digest::Digest;
 use curve25519::scalar::Scalar as CurveScalar;
 use sha2::Sha512Trunc256;
-use ctaes_sys::*;
+use ctaes_sys::AES256GCMKey;
+use subtle::slices_equal;
 use hex;
 
 use error::ErrorKind;
@@ -20,32 +21,59 @@ use result::Result;
 use traits::Validate;
 use traits::{BinarySerialize, HexSerialize};
 use random::Random;
-use scalar::Scalar;
+use wipe::{Wipe, wipe_value};
+use hash::Digest as HashDigest;
+use balloon::{BalloonParams, BalloonHasher};
+use passphrase::check_passphrase_params;
+use scalar::{Scalar, SecretScalar};
 use point::Point;
+use x25519::{X25519SecretKey, X25519PublicKey};
+use aes::{BlockCipher, AES256};
+use cipher::CipherAlgorithm;
+use envelope::Envelope;
 
 use std::fmt;
 
 /// A secret key is a secret field scalar used for ECIES encryption.
-#[derive(Copy, Clone, Eq, PartialEq, Default, Debug, Serialize, Deserialize)]
-pub struct SecretKey(Scalar);
+///
+/// The `SecretKey` is redacted when formatted, and its bytes are only accessible
+/// through `SecretKey::expose_secret` and the `BinarySerialize` and `HexSerialize`
+/// conversions. It does not implement serde's traits: a field holding it opts in
+/// with `#[serde(with = "secret_serde")]`.
+#[derive(Clone, Default)]
+pub struct SecretKey(SecretScalar);
 
 impl SecretKey {
     /// Creates a `SecretKey` from a byte array.
     pub fn new(b: [u8; 32]) -> Result<SecretKey> {
-        Ok(SecretKey(Scalar::new(b)?))
+        Ok(SecretKey(SecretScalar::new(Scalar::new(b)?)))
     }
 
     /// Creates a random `SecretKey`.
     pub fn random() -> SecretKey {
-        SecretKey(Scalar::random())
+        SecretKey(SecretScalar::random())
     }
 
     /// Converts the `SecretKey` to a `PublicKey`.
     pub fn to_public(&self) -> PublicKey {
-        PublicKey::new(*self)
+        PublicKey(&Point::default() * self.0.expose_secret())
+    }
+
+    /// Returns the underlying bytes. The caller is responsible for not
+    /// leaking them.
+    pub fn expose_secret(&self) -> &[u8] {
+        &self.0.expose_secret().0.as_bytes()[..]
+    }
+}
+
+impl PartialEq for SecretKey {
+    fn eq(&self, other: &SecretKey) -> bool {
+        slices_equal(self.expose_secret(), other.expose_secret()) == 1
     }
 }
 
+impl Eq for SecretKey {}
+
 impl Validate for SecretKey {
     fn validate(&self) -> Result<()> {
         self.0.validate()
@@ -54,27 +82,37 @@ impl Validate for SecretKey {
 
 impl BinarySerialize for SecretKey {
     fn to_bytes(&self) -> Result<Vec<u8>> {
-        self.0.to_bytes()
+        Ok(self.expose_secret().to_owned())
     }
 
     fn from_bytes(b: &[u8]) -> Result<SecretKey> {
-        Ok(SecretKey(Scalar::from_bytes(b)?))
+        Ok(SecretKey(SecretScalar::from_bytes(b)?))
     }
 }
 
 impl HexSerialize for SecretKey {
     fn to_hex(&self) -> Result<String> {
-        Ok(hex::encode(&self.to_bytes()?))
+        Ok(hex::encode(self.expose_secret()))
     }
 
     fn from_hex(s: &str) -> Result<SecretKey> {
-        Self::from_bytes(&hex::decode(s)?)
+        let mut b = hex::decode(s)?;
+        let sk = Self::from_bytes(&b);
+        b.wipe();
+
+        sk
+    }
+}
+
+impl fmt::Debug for SecretKey {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "SecretKey(<redacted>)")
     }
 }
 
 impl fmt::Display for SecretKey {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "{:?}", self.to_hex().unwrap())
+        write!(f, "SecretKey(<redacted>)")
     }
 }
 
@@ -85,10 +123,15 @@ pub struct PublicKey(Point);
 impl PublicKey {
     /// Creates a `PublicKey` from a `SecretKey`.
     pub fn new(sk: SecretKey) -> PublicKey {
-        let _pk = &Point::default() * &sk.0;
+        let _pk = &Point::default() * sk.0.expose_secret();
 
         PublicKey(_pk)
     }
+
+    /// Returns the fingerprint of the `PublicKey`.
+    pub fn fingerprint(&self) -> Result<HashDigest> {
+        Ok(HashDigest::hash(&self.to_bytes()?))
+    }
 }
 
 impl Validate for PublicKey {
@@ -124,7 +167,12 @@ impl fmt::Display for PublicKey {
 }
 
 /// An encryption key is a 32 bytes byte array used for encryption.
-#[derive(Copy, Clone, Eq, PartialEq, Default, Debug, Serialize, Deserialize)]
+///
+/// The `Key` is redacted when formatted, and its bytes are only accessible
+/// through `Key::expose_secret` and the `BinarySerialize` and `HexSerialize`
+/// conversions. It does not implement serde's traits: a field holding it opts in
+/// with `#[serde(with = "secret_serde")]`.
+#[derive(Clone, Default)]
 pub struct Key(GenericArray<u8, U32>);
 
 impl Key {
@@ -133,8 +181,23 @@ impl Key {
         Self::from_bytes(&Random::bytes(32)).unwrap()
     }
 
-    /// Creates a new shared `Key` with x25519 Diffie-Hellman.
+    /// Creates a new shared `Key` with x25519 Diffie-Hellman. The `Key` is not
+    /// bound to the public keys or to a purpose: use `SharedSecret` to derive
+    /// context-bound subkeys.
     pub fn shared(sk: SecretKey, pk: PublicKey) -> Result<Key> {
+        let mut _shared = Key::diffie_hellman(sk, pk)?;
+
+        let mut hasher = Sha512Trunc256::new();
+        hasher.input(&_shared[..]);
+        let _key = hasher.result();
+
+        _shared.wipe();
+
+        Ok(Key(_key))
+    }
+
+    /// Returns the raw output of the x25519 Diffie-Hellman.
+    pub(crate) fn diffie_hellman(sk: SecretKey, pk: PublicKey) -> Result<[u8; 32]> {
         sk.validate()?;
         pk.validate()?;
 
@@ -143,28 +206,81 @@ impl Key {
         }
 
         let _point = (pk.0).0.to_montgomery();
-        let mut _sk = ((sk.0).0).to_bytes();
+        let mut _sk = sk.0.expose_secret().0.to_bytes();
+
+        // NB: the scalar is not clamped as in RFC 7748, as the `PublicKey` is
+        // an Edwards point computed from the unclamped scalar, and clamping
+        // would break the symmetry of the exchange. For X25519 peers, use
+        // `Key::shared_x25519`.
+        let mut s = CurveScalar::from_bits(_sk);
+        _sk.wipe();
+
+        let _shared = (&_point * &s).compress().to_bytes();
+        wipe_value(&mut s, CurveScalar::zero());
+
+        if _shared == [0u8; 32] {
+            return Err(ErrorKind::InvalidSharedSecret.into());
+        }
 
-        // scrabbling?
+        Ok(_shared)
+    }
 
-        let s = CurveScalar::from_bits(_sk);
+    /// Creates a new shared `Key` with the RFC 7748 X25519 Diffie-Hellman.
+    pub fn shared_x25519(sk: X25519SecretKey, pk: X25519PublicKey) -> Result<Key> {
+        let mut _shared = sk.diffie_hellman(&pk)?;
 
         let mut hasher = Sha512Trunc256::new();
-        hasher.input(&(&_point * &s).compress().to_bytes()[..]);
+        hasher.input(&_shared[..]);
         let _key = hasher.result();
 
+        _shared.wipe();
+
         Ok(Key(_key))
     }
 
-    /// Converts to AES256GCMKey
-    fn to_aes_key(&self) -> AES256GCMKey {
-        self.0
+    /// Creates a new `Key` from a passphrase, stretching it with Balloon hashing.
+    /// The `BalloonParams` are checked with `check_passphrase_params`.
+    pub fn from_passphrase(passphrase: &[u8], salt: HashDigest, params: BalloonParams) -> Result<Key> {
+        check_passphrase_params(params)?;
+
+        let hasher = BalloonHasher::new(salt, params)?;
+        let mut _digest = hasher.hash(passphrase)?.to_bytes()?;
+
+        let key = Key::from_bytes(&_digest[..32]);
+        _digest.wipe();
+
+        key
+    }
+
+    /// Returns the underlying `AES256GCMKey`.
+    pub(crate) fn as_aes_key(&self) -> &AES256GCMKey {
+        &self.0
+    }
+
+    /// Returns the underlying bytes. The caller is responsible for not
+    /// leaking them.
+    pub fn expose_secret(&self) -> &[u8] {
+        self.0.as_slice()
+    }
+}
+
+impl PartialEq for Key {
+    fn eq(&self, other: &Key) -> bool {
+        slices_equal(self.expose_secret(), other.expose_secret()) == 1
+    }
+}
+
+impl Eq for Key {}
+
+impl Drop for Key {
+    fn drop(&mut self) {
+        self.0.as_mut_slice().wipe();
     }
 }
 
 impl BinarySerialize for Key {
     fn to_bytes(&self) -> Result<Vec<u8>> {
-        Ok(self.0.as_slice().to_owned())
+        Ok(self.expose_secret().to_owned())
     }
 
     fn from_bytes(b: &[u8]) -> Result<Key> {
@@ -179,93 +295,161 @@ impl BinarySerialize for Key {
 
 impl HexSerialize for Key {
     fn to_hex(&self) -> Result<String> {
-        Ok(hex::encode(&self.to_bytes()?))
+        Ok(hex::encode(self.expose_secret()))
     }
 
     fn from_hex(s: &str) -> Result<Key> {
-        Self::from_bytes(&hex::decode(s)?)
+        let mut b = hex::decode(s)?;
+        let key = Self::from_bytes(&b);
+        b.wipe();
+
+        key
+    }
+}
+
+impl fmt::Debug for Key {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Key(<redacted>)")
     }
 }
 
 impl fmt::Display for Key {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "{:?}", self.to_hex().unwrap())
+        write!(f, "Key(<redacted>)")
     }
 }
 
 
-/// Decrypts a plaintext with AESGMC256.
+/// Encrypts a plaintext with the default `CipherAlgorithm` in a self-describing `Envelope`.
 pub fn sym_encrypt(key: Key, plaintext: &[u8]) -> Result<Vec<u8>> {
-    let mut plain = Vec::new();
-    plain.extend_from_slice(plaintext);
+    sym_encrypt_with_ad(key, &[], plaintext)
+}
 
-    let len = plaintext.len();
-    
-    let (blocks_rem, blocks_len) = if len % 16 != 0 {
-        let rem = 16 - (len % 16);
-        let len = (len / 16) + 1;
-        (rem, len)
-    } else {
-        (0, len / 16)
-    };
+/// Encrypts a plaintext with the default `CipherAlgorithm` in a self-describing `Envelope`,
+/// authenticating also the associated data.
+pub fn sym_encrypt_with_ad(key: Key, ad: &[u8], plaintext: &[u8]) -> Result<Vec<u8>> {
+    sym_encrypt_with_cipher(CipherAlgorithm::default(), key, ad, plaintext)
+}
 
-    for _ in 0..blocks_rem {
-        plain.push(0);
-    }
+/// Encrypts a plaintext with a given `CipherAlgorithm` in a self-describing `Envelope`,
+/// authenticating also the associated data.
+pub fn sym_encrypt_with_cipher(algorithm: CipherAlgorithm, key: Key, ad: &[u8], plaintext: &[u8]) -> Result<Vec<u8>> {
+    Envelope::encrypt_with(algorithm, key, ad, plaintext)?.to_bytes()
+}
 
-    let mut cyph = Vec::new();
+/// Encrypts a plaintext with the default `CipherAlgorithm` and generates the key with x25519.
+pub fn assym_encrypt(sk: SecretKey, pk: PublicKey, plain: &[u8]) -> Result<Vec<u8>> {
+    assym_encrypt_with_cipher(CipherAlgorithm::default(), sk, pk, plain)
+}
 
-    // TODO: permute key each time, to increase safety
+/// Encrypts a plaintext with a given `CipherAlgorithm` and generates the key with x25519.
+pub fn assym_encrypt_with_cipher(algorithm: CipherAlgorithm, sk: SecretKey, pk: PublicKey, plain: &[u8]) -> Result<Vec<u8>> {
+    let key = Key::shared(sk, pk)?;
 
-    for i in 0..blocks_len {
-        let start = 16*i;
-        let stop = 16*(i+1);
-        let mut encryptor = AESGCM256::new(key.to_aes_key());
-        let cyphertext = encryptor.encrypt(&plain[start..stop])?;
-        cyph.extend_from_slice(&cyphertext);
-    }
+    sym_encrypt_with_cipher(algorithm, key, &[], plain)
+}
 
+/// Decrypts an `Envelope` produced by `sym_encrypt`.
+pub fn sym_decrypt(key: Key, cyph: &[u8]) -> Result<Vec<u8>> {
+    sym_decrypt_with_ad(key, &[], cyph)
+}
 
-    Ok(cyph)
+/// Decrypts an `Envelope` produced by `sym_encrypt_with_ad`.
+pub fn sym_decrypt_with_ad(key: Key, ad: &[u8], cyph: &[u8]) -> Result<Vec<u8>> {
+    Envelope::from_bytes(cyph)?.decrypt(key, ad)
 }
 
-/// Encrypts a plaintext with [STREAM](https://eprint.iacr.org/2015/189.pdf)
-/// but generates the key with x25519.
-pub fn assym_encrypt(sk: SecretKey, pk: PublicKey, plain: &[u8]) -> Result<Vec<u8>> {
+/// Decrypts an `Envelope` produced by `assym_encrypt`.
+pub fn assym_decrypt(sk: SecretKey, pk: PublicKey, cyph: &[u8]) -> Result<Vec<u8>> {
     let key = Key::shared(sk, pk)?;
 
-    sym_encrypt(key, plain)
+    sym_decrypt(key, cyph)
 }
 
-/// Decrypts a cyphertext encrypted with AESGMC256.
-pub fn sym_decrypt(key: Key, cyph: &[u8], size: u32) -> Result<Vec<u8>> {
+/// Decrypts a cyphertext produced by `sym_encrypt` before the introduction of
+/// the `Envelope`, where the blocks were encrypted one by one with AES256
+/// and zero-padded. The cyphertext is not authenticated.
+pub fn sym_decrypt_legacy(key: Key, cyph: &[u8], size: u32) -> Result<Vec<u8>> {
     if size > cyph.len() as u32 {
         return Err(ErrorKind::InvalidLength.into());
     }
-    
-    let mut plain = Vec::new();
-
-    let blocks_len = cyph.len() / 16;
 
-    // TODO: permute key each time, to increase safety
-
-    for i in 0..blocks_len {
-        let start = 16*i;
-        let stop = 16*(i+1);
-        let mut decryptor = AESGCM256::new(key.to_aes_key());
-        let plaintext = decryptor.decrypt(&cyph[start..stop])?;
-        plain.extend_from_slice(&plaintext);
+    if cyph.len() % 16 != 0 {
+        return Err(ErrorKind::InvalidLength.into());
     }
 
-    plain = Vec::from(&plain[0..size as usize]);
+    let mut plain = cyph.to_owned();
+    AES256::new(&key)?.decrypt_blocks(&mut plain)?;
+
+    plain.truncate(size as usize);
 
     Ok(plain)
 }
 
-/// Decrypts a cyphertext encrypted with AESGMC256 and generates
-/// the key with x25519.
-pub fn assym_decrypt(sk: SecretKey, pk: PublicKey, cyph: &[u8], size: u32) -> Result<Vec<u8>> {
+/// Decrypts a cyphertext produced by `assym_encrypt` before the introduction of
+/// the `Envelope`.
+pub fn assym_decrypt_legacy(sk: SecretKey, pk: PublicKey, cyph: &[u8], size: u32) -> Result<Vec<u8>> {
     let key = Key::shared(sk, pk)?;
 
-    sym_decrypt(key, cyph, size)
+    sym_decrypt_legacy(key, cyph, size)
+}
+
+/// Derives the `Key` of a sealed box from the x25519 shared `Key`, the ephemeral
+/// `PublicKey` and the recipient `PublicKey`.
+pub(crate) fn sealed_key(shared: Key, epk: &[u8], pk: &[u8]) -> Key {
+    let mut hasher = Sha512Trunc256::new();
+    hasher.input(&shared.0);
+    hasher.input(epk);
+    hasher.input(pk);
+
+    Key(hasher.result())
+}
+
+/// Encrypts a plaintext to a `PublicKey` with a fresh ephemeral `SecretKey` and the
+/// default `CipherAlgorithm`, so that the sender stays anonymous and every message
+/// has its own `Key`. The ephemeral `PublicKey` is prepended to the `Envelope`.
+pub fn seal(pk: PublicKey, plaintext: &[u8]) -> Result<Vec<u8>> {
+    seal_with_cipher(CipherAlgorithm::default(), pk, plaintext)
+}
+
+/// Encrypts a plaintext to a `PublicKey` with a fresh ephemeral `SecretKey` and a
+/// given `CipherAlgorithm`.
+pub fn seal_with_cipher(algorithm: CipherAlgorithm, pk: PublicKey, plaintext: &[u8]) -> Result<Vec<u8>> {
+    pk.validate()?;
+
+    let esk = SecretKey::random();
+    let epk = esk.to_public().to_bytes()?;
+    let pk_buf = pk.to_bytes()?;
+
+    let key = sealed_key(Key::shared(esk, pk)?, &epk, &pk_buf);
+
+    let mut ad = Vec::new();
+    ad.extend_from_slice(&epk);
+    ad.extend_from_slice(&pk_buf);
+
+    let mut cyph = Vec::new();
+    cyph.extend_from_slice(&epk);
+    cyph.extend_from_slice(&sym_encrypt_with_cipher(algorithm, key, &ad, plaintext)?);
+
+    Ok(cyph)
+}
+
+/// Decrypts a cyphertext produced by `seal` with the recipient `SecretKey`.
+pub fn open(sk: SecretKey, cyph: &[u8]) -> Result<Vec<u8>> {
+    sk.validate()?;
+
+    if cyph.len() < 32 {
+        return Err(ErrorKind::InvalidLength.into());
+    }
+
+    let (epk, cyph) = cyph.split_at(32);
+    let pk_buf = sk.to_public().to_bytes()?;
+
+    let key = sealed_key(Key::shared(sk, PublicKey::from_bytes(epk)?)?, epk, &pk_buf);
+
+    let mut ad = Vec::new();
+    ad.extend_from_slice(epk);
+    ad.extend_from_slice(&pk_buf);
+
+    sym_decrypt_with_ad(key, &ad, cyph)
 }
@@ -0,0 +1,192 @@
+// Copyright 2018 Yobicash Ltd.
+//
+// Licensed under the MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>
+// and the Apache 2.0 license <LICENSE-APACHE or https://opensource.org/licenses/Apache-2.0>.
+// This file may not be copied, modified, or distributed except according to those
+// terms.
+
+//! The `envelope` module provides the self-describing cyphertext envelope.
+//!
+//! An envelope is laid out as `magic || version || algorithm || nonce || length || cyphertext`,
+//! where `length` is the big-endian `u32` length of the plaintext, and the cyphertext
+//! carries the authentication tag. The header is authenticated as associated data.
+
+use byteorder::{BigEndian, ByteOrder, WriteBytesExt};
+use hex;
+
+use error::ErrorKind;
+use result::Result;
+use traits::Validate;
+use traits::{BinarySerialize, HexSerialize};
+use random::Random;
+use encrypt::Key;
+use cipher::{CipherAlgorithm, Cipher};
+
+use std::fmt;
+
+/// The magic byte starting every envelope.
+pub const ENVELOPE_MAGIC: u8 = 0x59;
+
+/// The current envelope version.
+pub const ENVELOPE_VERSION: u8 = 1;
+
+/// A self-describing authenticated cyphertext.
+#[derive(Clone, Eq, PartialEq, Debug, Serialize, Deserialize)]
+pub struct Envelope {
+    /// The envelope version.
+    pub version: u8,
+    /// The cipher used to encrypt the plaintext.
+    pub algorithm: CipherAlgorithm,
+    /// The nonce used to encrypt the plaintext.
+    pub nonce: Vec<u8>,
+    /// The length of the plaintext.
+    pub length: u32,
+    /// The cyphertext, followed by the authentication tag.
+    pub cyphertext: Vec<u8>,
+}
+
+impl Envelope {
+    /// Encrypts a plaintext in a new `Envelope` with the default `CipherAlgorithm`,
+    /// authenticating also the associated data.
+    pub fn encrypt(key: Key, ad: &[u8], plaintext: &[u8]) -> Result<Envelope> {
+        Envelope::encrypt_with(CipherAlgorithm::default(), key, ad, plaintext)
+    }
+
+    /// Encrypts a plaintext in a new `Envelope` with a given `CipherAlgorithm`,
+    /// authenticating also the associated data.
+    pub fn encrypt_with(algorithm: CipherAlgorithm, key: Key, ad: &[u8], plaintext: &[u8]) -> Result<Envelope> {
+        if plaintext.len() > u32::max_value() as usize {
+            return Err(ErrorKind::InvalidLength.into());
+        }
+
+        let mut envelope = Envelope {
+            version: ENVELOPE_VERSION,
+            algorithm: algorithm,
+            nonce: Random::bytes(algorithm.nonce_size() as u32),
+            length: plaintext.len() as u32,
+            cyphertext: Vec::new(),
+        };
+
+        let mut _ad = envelope.header()?;
+        _ad.extend_from_slice(ad);
+
+        envelope.cyphertext = Cipher::new(algorithm, key)?
+            .encrypt(&envelope.nonce, &_ad, plaintext)?;
+
+        Ok(envelope)
+    }
+
+    /// Decrypts the `Envelope`, recovering the exact plaintext.
+    pub fn decrypt(&self, key: Key, ad: &[u8]) -> Result<Vec<u8>> {
+        self.validate()?;
+
+        let mut _ad = self.header()?;
+        _ad.extend_from_slice(ad);
+
+        let plaintext = Cipher::new(self.algorithm, key)?
+            .decrypt(&self.nonce, &_ad, &self.cyphertext)?;
+
+        if plaintext.len() != self.length as usize {
+            return Err(ErrorKind::InvalidLength.into());
+        }
+
+        Ok(plaintext)
+    }
+
+    /// Returns the header of the `Envelope`, which is authenticated with the cyphertext.
+    fn header(&self) -> Result<Vec<u8>> {
+        let mut buf = Vec::new();
+        buf.push(ENVELOPE_MAGIC);
+        buf.push(self.version);
+        buf.push(self.algorithm.to_u8());
+        buf.extend_from_slice(&self.nonce);
+        buf.write_u32::<BigEndian>(self.length)?;
+
+        Ok(buf)
+    }
+}
+
+impl Validate for Envelope {
+    fn validate(&self) -> Result<()> {
+        if self.version != ENVELOPE_VERSION {
+            return Err(ErrorKind::NotSupported.into());
+        }
+
+        if self.nonce.len() != self.algorithm.nonce_size() {
+            return Err(ErrorKind::InvalidLength.into());
+        }
+
+        // NB: the length comes from the header, so it may overflow on 32-bit targets
+        let size = (self.length as usize).checked_add(self.algorithm.tag_size())
+            .ok_or(ErrorKind::InvalidLength)?;
+
+        if self.cyphertext.len() != size {
+            return Err(ErrorKind::InvalidLength.into());
+        }
+
+        Ok(())
+    }
+}
+
+impl BinarySerialize for Envelope {
+    fn to_bytes(&self) -> Result<Vec<u8>> {
+        let mut buf = self.header()?;
+        buf.extend_from_slice(&self.cyphertext);
+
+        Ok(buf)
+    }
+
+    fn from_bytes(b: &[u8]) -> Result<Envelope> {
+        if b.len() < 3 {
+            return Err(ErrorKind::InvalidLength.into());
+        }
+
+        if b[0] != ENVELOPE_MAGIC {
+            return Err(ErrorKind::InvalidFormat.into());
+        }
+
+        let version = b[1];
+        if version != ENVELOPE_VERSION {
+            return Err(ErrorKind::NotSupported.into());
+        }
+
+        let algorithm = CipherAlgorithm::from_u8(b[2])?;
+        let nonce_size = algorithm.nonce_size();
+
+        if b.len() < 3 + nonce_size + 4 {
+            return Err(ErrorKind::InvalidLength.into());
+        }
+
+        let nonce = b[3..3+nonce_size].to_owned();
+        let length = BigEndian::read_u32(&b[3+nonce_size..3+nonce_size+4]);
+        let cyphertext = b[3+nonce_size+4..].to_owned();
+
+        let envelope = Envelope {
+            version: version,
+            algorithm: algorithm,
+            nonce: nonce,
+            length: length,
+            cyphertext: cyphertext,
+        };
+
+        envelope.validate()?;
+
+        Ok(envelope)
+    }
+}
+
+impl HexSerialize for Envelope {
+    fn to_hex(&self) -> Result<String> {
+        Ok(hex::encode(&self.to_bytes()?))
+    }
+
+    fn from_hex(s: &str) -> Result<Envelope> {
+        Self::from_bytes(&hex::decode(s)?)
+    }
+}
+
+impl fmt::Display for Envelope {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:?}", self.to_hex().unwrap())
+    }
+}
@@ -42,6 +42,12 @@ pub enum ErrorKind {
     InvalidLength,
     #[fail(display="Invalid digest")]
     InvalidDigest,
+    #[fail(display="Invalid tag")]
+    InvalidTag,
+    #[fail(display="Invalid shared secret")]
+    InvalidSharedSecret,
+    #[fail(display="Wrong passphrase")]
+    WrongPassphrase,
     #[fail(display="From Failure")]
     FromFailure,
     #[fail(display="Failed serialization")]
@@ -50,6 +56,14 @@ pub enum ErrorKind {
     DeserializationFailure,
     #[fail(display="I/O failure")]
     IOFailure,
+    #[fail(display="Failed memory lock")]
+    MemoryLockFailure,
+    #[fail(display="Invalid handshake")]
+    InvalidHandshake,
+    #[fail(display="Failed integrity check")]
+    IntegrityFailure,
+    #[fail(display="Invalid signature")]
+    InvalidSignature,
 }
 
 impl Fail for Error {
@@ -0,0 +1,232 @@
+// Copyright 2018 Yobicash Ltd.
+//
+// Licensed under the MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>
+// and the Apache 2.0 license <LICENSE-APACHE or https://opensource.org/licenses/Apache-2.0>.
+// This file may not be copied, modified, or distributed except according to those
+// terms.
+
+//! The `gcm` module provides the AES256-GCM authenticated encryption mode
+//! ([NIST SP 800-38D](https://nvlpubs.nist.gov/nistpubs/Legacy/SP/nistspecialpublication800-38d.pdf)).
+
+use byteorder::{BigEndian, ByteOrder};
+use subtle::slices_equal;
+
+use error::ErrorKind;
+use result::Result;
+use encrypt::Key;
+use cipher::AEADCipher;
+use aes::{BlockCipher, AES256};
+use wipe::{Wipe, wipe_value};
+
+/// The size in bytes of an AES256-GCM nonce.
+pub const GCM_NONCE_SIZE: usize = 12;
+
+/// The size in bytes of an AES256-GCM authentication tag.
+pub const GCM_TAG_SIZE: usize = 16;
+
+/// The AES256-GCM authenticated cipher.
+#[derive(Clone)]
+pub struct AES256GCM {
+    /// The expanded AES256 key.
+    aes: AES256,
+    /// The GHASH key H = E(K, 0^128).
+    h: [u64; 2],
+}
+
+impl AEADCipher for AES256GCM {
+    const NONCE_SIZE: usize = GCM_NONCE_SIZE;
+
+    const TAG_SIZE: usize = GCM_TAG_SIZE;
+
+    fn new(key: Key) -> Result<AES256GCM> {
+        let aes = AES256::new(&key)?;
+
+        let mut h = [0u8; 16];
+        let res = aes.encrypt_blocks(&mut h);
+        let words = block_to_words(&h);
+        h.wipe();
+        res?;
+
+        Ok(AES256GCM {
+            aes: aes,
+            h: words,
+        })
+    }
+
+    fn encrypt(&self, nonce: &[u8], ad: &[u8], plain: &[u8]) -> Result<Vec<u8>> {
+        if nonce.len() != GCM_NONCE_SIZE {
+            return Err(ErrorKind::InvalidLength.into());
+        }
+
+        let j0 = self.j0(nonce);
+
+        let mut cyph = plain.to_owned();
+        self.ctr(&j0, &mut cyph)?;
+
+        let tag = self.tag(&j0, ad, &cyph)?;
+        cyph.extend_from_slice(&tag);
+
+        Ok(cyph)
+    }
+
+    fn decrypt(&self, nonce: &[u8], ad: &[u8], cyph: &[u8]) -> Result<Vec<u8>> {
+        if nonce.len() != GCM_NONCE_SIZE {
+            return Err(ErrorKind::InvalidLength.into());
+        }
+
+        let len = cyph.len();
+        if len < GCM_TAG_SIZE {
+            return Err(ErrorKind::InvalidLength.into());
+        }
+
+        let (cyph, tag) = cyph.split_at(len - GCM_TAG_SIZE);
+
+        let j0 = self.j0(nonce);
+
+        if slices_equal(&self.tag(&j0, ad, cyph)?, tag) != 1 {
+            return Err(ErrorKind::InvalidTag.into());
+        }
+
+        let mut plain = cyph.to_owned();
+        self.ctr(&j0, &mut plain)?;
+
+        Ok(plain)
+    }
+}
+
+impl Drop for AES256GCM {
+    fn drop(&mut self) {
+        wipe_value(&mut self.h, [0u64; 2]);
+    }
+}
+
+impl AES256GCM {
+    /// Returns the pre-counter block J0 = nonce || 0^31 || 1.
+    fn j0(&self, nonce: &[u8]) -> [u8; 16] {
+        let mut j0 = [0u8; 16];
+        j0[..GCM_NONCE_SIZE].copy_from_slice(nonce);
+        j0[15] = 1;
+        j0
+    }
+
+    /// XORs `buf` with the keystream starting at inc32(J0).
+    fn ctr(&self, j0: &[u8; 16], buf: &mut [u8]) -> Result<()> {
+        let blocks = (buf.len() + 15) / 16;
+        if blocks == 0 {
+            return Ok(());
+        }
+
+        let mut stream = vec![0u8; blocks * 16];
+        let mut counter = BigEndian::read_u32(&j0[12..]);
+
+        for block in stream.chunks_mut(16) {
+            counter = counter.wrapping_add(1);
+            block[..12].copy_from_slice(&j0[..12]);
+            BigEndian::write_u32(&mut block[12..], counter);
+        }
+
+        self.aes.encrypt_blocks(&mut stream)?;
+
+        for (b, s) in buf.iter_mut().zip(stream.iter()) {
+            *b ^= *s;
+        }
+
+        stream.wipe();
+
+        Ok(())
+    }
+
+    /// Computes the authentication tag of a cyphertext and its associated data.
+    fn tag(&self, j0: &[u8; 16], ad: &[u8], cyph: &[u8]) -> Result<[u8; 16]> {
+        let mut ghash = GHash::new(self.h);
+        ghash.update(ad);
+        ghash.update(cyph);
+
+        let mut lengths = [0u8; 16];
+        BigEndian::write_u64(&mut lengths[..8], (ad.len() as u64) * 8);
+        BigEndian::write_u64(&mut lengths[8..], (cyph.len() as u64) * 8);
+        ghash.update(&lengths);
+
+        let mut tag = *j0;
+        self.aes.encrypt_blocks(&mut tag)?;
+
+        let s = words_to_block(&ghash.finalize());
+        for i in 0..16 {
+            tag[i] ^= s[i];
+        }
+
+        Ok(tag)
+    }
+}
+
+/// The GHASH universal hash over GF(2^128).
+pub(crate) struct GHash {
+    h: [u64; 2],
+    y: [u64; 2],
+}
+
+impl GHash {
+    pub(crate) fn new(h: [u64; 2]) -> GHash {
+        GHash {
+            h: h,
+            y: [0, 0],
+        }
+    }
+
+    /// Absorbs `data`, zero-padding the last partial block.
+    pub(crate) fn update(&mut self, data: &[u8]) {
+        for chunk in data.chunks(16) {
+            let mut block = [0u8; 16];
+            block[..chunk.len()].copy_from_slice(chunk);
+
+            let x = block_to_words(&block);
+            self.y[0] ^= x[0];
+            self.y[1] ^= x[1];
+            self.y = gf_mul(self.y, self.h);
+        }
+    }
+
+    pub(crate) fn finalize(&self) -> [u64; 2] {
+        self.y
+    }
+}
+
+impl Drop for GHash {
+    fn drop(&mut self) {
+        wipe_value(&mut self.h, [0u64; 2]);
+        wipe_value(&mut self.y, [0u64; 2]);
+    }
+}
+
+/// Multiplies two elements of GF(2^128) in the GCM bit order, in constant time.
+fn gf_mul(x: [u64; 2], y: [u64; 2]) -> [u64; 2] {
+    let mut z = [0u64; 2];
+    let mut v = y;
+
+    for i in 0..128 {
+        let word = x[i / 64];
+        let bit = (word >> (63 - (i % 64))) & 1;
+        let mask = 0u64.wrapping_sub(bit);
+
+        z[0] ^= v[0] & mask;
+        z[1] ^= v[1] & mask;
+
+        let lsb = v[1] & 1;
+        v[1] = (v[1] >> 1) | (v[0] << 63);
+        v[0] >>= 1;
+        v[0] ^= 0xe100_0000_0000_0000 & 0u64.wrapping_sub(lsb);
+    }
+
+    z
+}
+
+pub(crate) fn block_to_words(b: &[u8; 16]) -> [u64; 2] {
+    [BigEndian::read_u64(&b[..8]), BigEndian::read_u64(&b[8..])]
+}
+
+pub(crate) fn words_to_block(w: &[u64; 2]) -> [u8; 16] {
+    let mut b = [0u8; 16];
+    BigEndian::write_u64(&mut b[..8], w[0]);
+    BigEndian::write_u64(&mut b[8..], w[1]);
+    b
+}
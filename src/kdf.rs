@@ -0,0 +1,217 @@
+// Copyright 2018 Yobicash Ltd.
+//
+// Licensed under the MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>
+// and the Apache 2.0 license <LICENSE-APACHE or https://opensource.org/licenses/Apache-2.0>.
+// This file may not be copied, modified, or distributed except according to those
+// terms.
+
+//! The `kdf` module provides the key derivation functions: HMAC-SHA512
+//! ([RFC 2104](https://tools.ietf.org/html/rfc2104)), HKDF-SHA512
+//! ([RFC 5869](https://tools.ietf.org/html/rfc5869)), and the context-bound
+//! derivation of subkeys from a Diffie-Hellman exchange.
+
+use digest::Digest;
+use sha2::Sha512;
+use byteorder::{BigEndian, WriteBytesExt};
+
+use error::ErrorKind;
+use result::Result;
+use traits::BinarySerialize;
+use encrypt::{SecretKey, PublicKey, Key};
+use x25519::{X25519SecretKey, X25519PublicKey};
+use wipe::Wipe;
+
+/// The size in bytes of an HMAC-SHA512 output.
+pub const HMAC_SIZE: usize = 64;
+
+/// The size in bytes of a SHA512 block.
+const SHA512_BLOCK_SIZE: usize = 128;
+
+/// The salt used to extract the pseudorandom key of a `SharedSecret`.
+const SHARED_SECRET_SALT: &[u8] = b"yobicrypto-shared-secret";
+
+/// Computes the HMAC-SHA512 of a message.
+pub fn hmac_sha512(key: &[u8], msg: &[u8]) -> [u8; 64] {
+    let mut _key = [0u8; SHA512_BLOCK_SIZE];
+
+    if key.len() > SHA512_BLOCK_SIZE {
+        let mut hasher = Sha512::default();
+        hasher.input(key);
+        _key[..HMAC_SIZE].copy_from_slice(hasher.result().as_slice());
+    } else {
+        _key[..key.len()].copy_from_slice(key);
+    }
+
+    let mut ipad = [0x36u8; SHA512_BLOCK_SIZE];
+    let mut opad = [0x5cu8; SHA512_BLOCK_SIZE];
+    for i in 0..SHA512_BLOCK_SIZE {
+        ipad[i] ^= _key[i];
+        opad[i] ^= _key[i];
+    }
+
+    let mut inner = Sha512::default();
+    inner.input(&ipad);
+    inner.input(msg);
+
+    let mut outer = Sha512::default();
+    outer.input(&opad);
+    outer.input(inner.result().as_slice());
+
+    _key.wipe();
+    ipad.wipe();
+    opad.wipe();
+
+    let mut mac = [0u8; 64];
+    mac.copy_from_slice(outer.result().as_slice());
+    mac
+}
+
+/// HKDF-SHA512 extract step: returns the pseudorandom key.
+pub fn hkdf_extract(salt: &[u8], ikm: &[u8]) -> [u8; 64] {
+    hmac_sha512(salt, ikm)
+}
+
+/// HKDF-SHA512 expand step: returns `len` bytes of output keying material.
+pub fn hkdf_expand(prk: &[u8], info: &[u8], len: usize) -> Result<Vec<u8>> {
+    if len > 255 * HMAC_SIZE {
+        return Err(ErrorKind::InvalidLength.into());
+    }
+
+    let mut okm = Vec::with_capacity(len);
+    let mut t: Vec<u8> = Vec::new();
+    let mut counter = 1u8;
+
+    while okm.len() < len {
+        let mut msg = t.clone();
+        msg.extend_from_slice(info);
+        msg.push(counter);
+
+        t.wipe();
+        t = hmac_sha512(prk, &msg).to_vec();
+        msg.wipe();
+
+        let take = ::std::cmp::min(HMAC_SIZE, len - okm.len());
+        okm.extend_from_slice(&t[..take]);
+        counter = counter.wrapping_add(1);
+    }
+
+    t.wipe();
+
+    Ok(okm)
+}
+
+/// HKDF-SHA512: returns `len` bytes of output keying material.
+pub fn hkdf_sha512(salt: &[u8], ikm: &[u8], info: &[u8], len: usize) -> Result<Vec<u8>> {
+    hkdf_expand(&hkdf_extract(salt, ikm), info, len)
+}
+
+/// The output of a Diffie-Hellman exchange bound to both the public keys and
+/// a caller-supplied context, from which named subkeys are derived.
+///
+/// Every subkey is derived with HKDF-SHA512 from the Diffie-Hellman output,
+/// with the context, the label and the public keys in the info, so that
+/// an exchange cannot be reused for another protocol or purpose.
+#[derive(Clone)]
+pub struct SharedSecret {
+    prk: [u8; 64],
+    context: Vec<u8>,
+    local: Vec<u8>,
+    remote: Vec<u8>,
+}
+
+impl SharedSecret {
+    /// Creates a `SharedSecret` from our `SecretKey` and their `PublicKey`.
+    pub fn new(sk: SecretKey, pk: PublicKey, context: &[u8]) -> Result<SharedSecret> {
+        let local = sk.to_public().to_bytes()?;
+        let remote = pk.to_bytes()?;
+        let mut shared = Key::diffie_hellman(sk, pk)?;
+
+        let secret = SharedSecret::from_parts(&shared, context, local, remote);
+        shared.wipe();
+
+        Ok(secret)
+    }
+
+    /// Creates a `SharedSecret` from our `X25519SecretKey` and their `X25519PublicKey`.
+    pub fn x25519(sk: X25519SecretKey, pk: X25519PublicKey, context: &[u8]) -> Result<SharedSecret> {
+        let mut shared = sk.diffie_hellman(&pk)?;
+        let local = sk.to_public().to_bytes()?;
+        let remote = pk.to_bytes()?;
+
+        let secret = SharedSecret::from_parts(&shared, context, local, remote);
+        shared.wipe();
+
+        Ok(secret)
+    }
+
+    fn from_parts(shared: &[u8], context: &[u8], local: Vec<u8>, remote: Vec<u8>) -> SharedSecret {
+        SharedSecret {
+            prk: hkdf_extract(SHARED_SECRET_SALT, shared),
+            context: context.to_owned(),
+            local: local,
+            remote: remote,
+        }
+    }
+
+    /// Derives `len` bytes shared by both parties (e.g. a MAC key or an IV seed).
+    pub fn derive_bytes(&self, label: &str, len: usize) -> Result<Vec<u8>> {
+        // NB: the public keys are sorted, so that both parties agree on the order
+        let (first, second) = if self.local <= self.remote {
+            (&self.local, &self.remote)
+        } else {
+            (&self.remote, &self.local)
+        };
+
+        let info = self.info(0, label, first, second)?;
+
+        hkdf_expand(&self.prk, &info, len)
+    }
+
+    /// Derives a `Key` shared by both parties.
+    pub fn derive(&self, label: &str) -> Result<Key> {
+        key_from_okm(self.derive_bytes(label, 32)?)
+    }
+
+    /// Derives the `Key` used to send to the other party, which is the
+    /// `Key` the other party receives with.
+    pub fn send_key(&self, label: &str) -> Result<Key> {
+        let info = self.info(1, label, &self.local, &self.remote)?;
+
+        key_from_okm(hkdf_expand(&self.prk, &info, 32)?)
+    }
+
+    /// Derives the `Key` used to receive from the other party, which is the
+    /// `Key` the other party sends with.
+    pub fn recv_key(&self, label: &str) -> Result<Key> {
+        let info = self.info(1, label, &self.remote, &self.local)?;
+
+        key_from_okm(hkdf_expand(&self.prk, &info, 32)?)
+    }
+
+    /// Encodes the HKDF info, length-prefixing every field.
+    fn info(&self, mode: u8, label: &str, first: &[u8], second: &[u8]) -> Result<Vec<u8>> {
+        let mut buf = Vec::new();
+        buf.push(mode);
+
+        for field in &[&self.context[..], label.as_bytes(), first, second] {
+            buf.write_u32::<BigEndian>(field.len() as u32)?;
+            buf.extend_from_slice(field);
+        }
+
+        Ok(buf)
+    }
+}
+
+impl Drop for SharedSecret {
+    fn drop(&mut self) {
+        self.prk.wipe();
+    }
+}
+
+/// Converts output keying material to a `Key`, wiping it.
+fn key_from_okm(mut okm: Vec<u8>) -> Result<Key> {
+    let key = Key::from_bytes(&okm);
+    okm.wipe();
+
+    key
+}
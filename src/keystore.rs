@@ -0,0 +1,355 @@
+// Copyright 2018 Yobicash Ltd.
+//
+// Licensed under the MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>
+// and the Apache 2.0 license <LICENSE-APACHE or https://opensource.org/licenses/Apache-2.0>.
+// This file may not be copied, modified, or distributed except according to those
+// terms.
+
+//! The `keystore` module provides the passphrase-protected storage of `SecretKey`s,
+//! in the spirit of the Ethereum V3 keystore.
+//!
+//! Every entry is encrypted with a `Key` derived from the passphrase with Balloon
+//! hashing and HKDF-SHA512, and records a MAC checked before the decryption, so
+//! that a wrong passphrase fails with `ErrorKind::WrongPassphrase`. The Balloon
+//! params of a loaded entry are bounded as in `check_passphrase_params`.
+
+use rmp_serde::encode as encode_msgpk;
+use rmp_serde::decode as decode_msgpk;
+use subtle::slices_equal;
+use hex;
+
+use error::ErrorKind;
+use result::Result;
+use traits::Validate;
+use traits::{BinarySerialize, HexSerialize};
+use random::Random;
+use hash::Digest;
+use balloon::{BalloonParams, BalloonHasher};
+use kdf::{hmac_sha512, hkdf_expand};
+use cipher::{CipherAlgorithm, Cipher};
+use encrypt::{SecretKey, Key};
+use passphrase::check_passphrase_params;
+use wipe::Wipe;
+
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Read, Write};
+use std::path::Path;
+use std::fmt;
+
+/// The current keystore version.
+pub const KEYSTORE_VERSION: u32 = 1;
+
+/// The size in bytes of a keystore entry ID.
+pub const KEYSTORE_ID_SIZE: usize = 16;
+
+/// A passphrase-protected `SecretKey`.
+#[derive(Clone, Eq, PartialEq, Debug, Serialize, Deserialize)]
+pub struct KeystoreEntry {
+    /// The label of the entry.
+    pub label: String,
+    /// The random ID of the entry, in hex.
+    pub id: String,
+    /// The fingerprint of the `PublicKey` of the `SecretKey`.
+    pub fingerprint: Digest,
+    /// The salt of the KDF.
+    pub salt: Digest,
+    /// The params of the KDF.
+    pub params: BalloonParams,
+    /// The cipher used to encrypt the `SecretKey`.
+    pub algorithm: CipherAlgorithm,
+    /// The nonce used to encrypt the `SecretKey`.
+    pub nonce: Vec<u8>,
+    /// The encrypted `SecretKey`, followed by the authentication tag.
+    pub cyphertext: Vec<u8>,
+    /// The MAC of the entry, computed with the passphrase-derived MAC key.
+    pub mac: Vec<u8>,
+}
+
+impl KeystoreEntry {
+    /// Creates a new `KeystoreEntry` encrypting a `SecretKey` with a passphrase.
+    pub fn new(label: &str, sk: SecretKey, passphrase: &[u8], params: BalloonParams) -> Result<KeystoreEntry> {
+        sk.validate()?;
+
+        let algorithm = CipherAlgorithm::default();
+
+        let mut entry = KeystoreEntry {
+            label: label.to_string(),
+            id: hex::encode(&Random::bytes(KEYSTORE_ID_SIZE as u32)),
+            fingerprint: sk.to_public().fingerprint()?,
+            salt: Digest::from_bytes(&Random::bytes(64))?,
+            params: params,
+            algorithm: algorithm,
+            nonce: Random::bytes(algorithm.nonce_size() as u32),
+            cyphertext: Vec::new(),
+            mac: Vec::new(),
+        };
+
+        let (key, mut mac_key) = entry.derive_keys(passphrase)?;
+
+        entry.cyphertext = Cipher::new(algorithm, key)?
+            .encrypt(&entry.nonce, entry.id.as_bytes(), sk.expose_secret())?;
+        entry.mac = entry.compute_mac(&mac_key).to_vec();
+
+        mac_key.wipe();
+
+        Ok(entry)
+    }
+
+    /// Decrypts the `SecretKey` of the entry.
+    pub fn decrypt(&self, passphrase: &[u8]) -> Result<SecretKey> {
+        self.validate()?;
+
+        let (key, mut mac_key) = self.derive_keys(passphrase)?;
+
+        let valid = slices_equal(&self.compute_mac(&mac_key), &self.mac);
+        mac_key.wipe();
+
+        if valid != 1 {
+            return Err(ErrorKind::WrongPassphrase.into());
+        }
+
+        let mut plain = Cipher::new(self.algorithm, key)?
+            .decrypt(&self.nonce, self.id.as_bytes(), &self.cyphertext)?;
+        let sk = SecretKey::from_bytes(&plain);
+        plain.wipe();
+        let sk = sk?;
+
+        if sk.to_public().fingerprint()? != self.fingerprint {
+            return Err(ErrorKind::InvalidDigest.into());
+        }
+
+        Ok(sk)
+    }
+
+    /// Derives the encryption `Key` and the MAC key from the passphrase.
+    fn derive_keys(&self, passphrase: &[u8]) -> Result<(Key, Vec<u8>)> {
+        check_passphrase_params(self.params)?;
+
+        let hasher = BalloonHasher::new(self.salt, self.params)?;
+        let mut prk = hasher.hash(passphrase)?.to_bytes()?;
+
+        let mut key_buf = hkdf_expand(&prk, b"yobicrypto-keystore-encryption", 32)?;
+        let key = Key::from_bytes(&key_buf)?;
+        let mac_key = hkdf_expand(&prk, b"yobicrypto-keystore-mac", 32)?;
+
+        key_buf.wipe();
+        prk.wipe();
+
+        Ok((key, mac_key))
+    }
+
+    /// Computes the MAC of the ID, the cipher, the nonce and the cyphertext. The
+    /// label is not authenticated, so that entries can be renamed without the passphrase.
+    fn compute_mac(&self, mac_key: &[u8]) -> [u8; 64] {
+        let mut msg = Vec::new();
+        msg.extend_from_slice(self.id.as_bytes());
+        msg.push(self.algorithm.to_u8());
+        msg.extend_from_slice(&self.nonce);
+        msg.extend_from_slice(&self.cyphertext);
+
+        hmac_sha512(mac_key, &msg)
+    }
+}
+
+impl Validate for KeystoreEntry {
+    fn validate(&self) -> Result<()> {
+        check_passphrase_params(self.params)?;
+
+        if self.nonce.len() != self.algorithm.nonce_size() {
+            return Err(ErrorKind::InvalidLength.into());
+        }
+
+        if self.mac.len() != 64 {
+            return Err(ErrorKind::InvalidLength.into());
+        }
+
+        Ok(())
+    }
+}
+
+/// A keystore holding multiple labelled `SecretKey`s.
+#[derive(Clone, Eq, PartialEq, Debug, Serialize, Deserialize)]
+pub struct Keystore {
+    /// The keystore version.
+    pub version: u32,
+    /// The entries of the keystore.
+    pub entries: Vec<KeystoreEntry>,
+}
+
+impl Keystore {
+    /// Creates a new empty `Keystore`.
+    pub fn new() -> Keystore {
+        Keystore {
+            version: KEYSTORE_VERSION,
+            entries: Vec::new(),
+        }
+    }
+
+    /// Loads a `Keystore` from a file.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Keystore> {
+        let mut buf = Vec::new();
+        File::open(path)?.read_to_end(&mut buf)?;
+
+        Keystore::from_bytes(&buf)
+    }
+
+    /// Saves the `Keystore` to a file. The `Keystore` is written to a temporary
+    /// file in the same directory, readable only by its owner, which then replaces
+    /// the target, so that a failed save leaves the previous file intact.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let path = path.as_ref();
+        let buf = self.to_bytes()?;
+
+        let mut tmp_name = path.file_name()
+            .ok_or(ErrorKind::InvalidArgument)?
+            .to_os_string();
+        tmp_name.push(format!(".{}.tmp", hex::encode(&Random::bytes(8))));
+        let tmp_path = path.with_file_name(tmp_name);
+
+        let res = write_private_file(&tmp_path, &buf)
+            .and_then(|_| fs::rename(&tmp_path, path))
+            .and_then(|_| sync_parent_dir(path));
+
+        if res.is_err() {
+            let _ = fs::remove_file(&tmp_path);
+        }
+
+        Ok(res?)
+    }
+
+    /// Returns the labels of the entries.
+    pub fn labels(&self) -> Vec<String> {
+        self.entries.iter().map(|e| e.label.clone()).collect()
+    }
+
+    /// Returns the entry with a given label.
+    pub fn entry(&self, label: &str) -> Result<&KeystoreEntry> {
+        self.entries.iter()
+            .find(|e| e.label == label)
+            .ok_or_else(|| ErrorKind::NotFound.into())
+    }
+
+    /// Adds a `SecretKey` under a new label.
+    pub fn add(&mut self, label: &str, sk: SecretKey, passphrase: &[u8], params: BalloonParams) -> Result<()> {
+        if self.entry(label).is_ok() {
+            return Err(ErrorKind::AlreadyFound.into());
+        }
+
+        let entry = KeystoreEntry::new(label, sk, passphrase, params)?;
+        self.entries.push(entry);
+
+        Ok(())
+    }
+
+    /// Decrypts the `SecretKey` with a given label.
+    pub fn get(&self, label: &str, passphrase: &[u8]) -> Result<SecretKey> {
+        self.entry(label)?.decrypt(passphrase)
+    }
+
+    /// Removes the entry with a given label.
+    pub fn remove(&mut self, label: &str) -> Result<()> {
+        let idx = self.entries.iter()
+            .position(|e| e.label == label)
+            .ok_or(ErrorKind::NotFound)?;
+
+        self.entries.remove(idx);
+
+        Ok(())
+    }
+
+    /// Renames the entry with a given label.
+    pub fn rename(&mut self, label: &str, new_label: &str) -> Result<()> {
+        if self.entry(new_label).is_ok() {
+            return Err(ErrorKind::AlreadyFound.into());
+        }
+
+        let entry = self.entries.iter_mut()
+            .find(|e| e.label == label)
+            .ok_or(ErrorKind::NotFound)?;
+
+        entry.label = new_label.to_string();
+
+        Ok(())
+    }
+}
+
+/// Writes and syncs a new file, readable only by its owner.
+fn write_private_file(path: &Path, buf: &[u8]) -> io::Result<()> {
+    let mut options = OpenOptions::new();
+    options.write(true).create_new(true);
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+
+    let mut file = options.open(path)?;
+    file.write_all(buf)?;
+    file.sync_all()
+}
+
+/// Syncs the directory of a file, so that a rename is persisted.
+#[cfg(unix)]
+fn sync_parent_dir(path: &Path) -> io::Result<()> {
+    match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => File::open(dir)?.sync_all(),
+        _ => File::open(".")?.sync_all(),
+    }
+}
+
+/// Syncs the directory of a file, so that a rename is persisted.
+#[cfg(not(unix))]
+fn sync_parent_dir(_path: &Path) -> io::Result<()> {
+    Ok(())
+}
+
+impl Validate for Keystore {
+    fn validate(&self) -> Result<()> {
+        if self.version != KEYSTORE_VERSION {
+            return Err(ErrorKind::NotSupported.into());
+        }
+
+        for (i, entry) in self.entries.iter().enumerate() {
+            entry.validate()?;
+
+            if self.entries[..i].iter().any(|e| e.label == entry.label) {
+                return Err(ErrorKind::AlreadyFound.into());
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl BinarySerialize for Keystore {
+    fn to_bytes(&self) -> Result<Vec<u8>> {
+        encode_msgpk::to_vec(self)
+            .map_err(|_| ErrorKind::SerializationFailure.into())
+    }
+
+    fn from_bytes(b: &[u8]) -> Result<Keystore> {
+        let keystore: Keystore = decode_msgpk::from_slice(b)
+            .map_err(|_| ErrorKind::DeserializationFailure)?;
+
+        keystore.validate()?;
+
+        Ok(keystore)
+    }
+}
+
+impl HexSerialize for Keystore {
+    fn to_hex(&self) -> Result<String> {
+        Ok(hex::encode(&self.to_bytes()?))
+    }
+
+    fn from_hex(s: &str) -> Result<Keystore> {
+        Self::from_bytes(&hex::decode(s)?)
+    }
+}
+
+impl fmt::Display for Keystore {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:?}", self.to_hex().unwrap())
+    }
+}
@@ -0,0 +1,206 @@
+// Copyright 2018 Yobicash Ltd.
+//
+// Licensed under the MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>
+// and the Apache 2.0 license <LICENSE-APACHE or https://opensource.org/licenses/Apache-2.0>.
+// This file may not be copied, modified, or distributed except according to those
+// terms.
+
+//! The `keywrap` module provides the AES256 key wrap
+//! ([RFC 3394](https://tools.ietf.org/html/rfc3394)), and the AES256 key wrap with
+//! padding ([RFC 5649](https://tools.ietf.org/html/rfc5649)), used to store keys
+//! encrypted under a key-encryption `Key`.
+//!
+//! The unwrapping fails with `ErrorKind::IntegrityFailure` if the wrapped key has been
+//! altered or the key-encryption `Key` is wrong.
+
+use byteorder::{BigEndian, ByteOrder};
+use subtle::slices_equal;
+
+use error::ErrorKind;
+use result::Result;
+use traits::BinarySerialize;
+use wipe::Wipe;
+use aes::{BlockCipher, AES256};
+use encrypt::Key;
+
+/// The size in bytes of a key wrap semiblock.
+pub const KEY_WRAP_BLOCK_SIZE: usize = 8;
+
+/// The default initial value of the key wrap (RFC 3394, section 2.2.3.1).
+pub const KEY_WRAP_IV: [u8; 8] = [0xa6, 0xa6, 0xa6, 0xa6, 0xa6, 0xa6, 0xa6, 0xa6];
+
+/// The constant part of the alternative initial value of the key wrap
+/// with padding (RFC 5649, section 3).
+pub const KEY_WRAP_PAD_IV: [u8; 4] = [0xa6, 0x59, 0x59, 0xa6];
+
+/// Wraps a key of at least 16 bytes, multiple of 8, with a key-encryption `Key` (RFC 3394).
+pub fn aes_key_wrap(kek: &Key, plain: &[u8]) -> Result<Vec<u8>> {
+    if plain.len() < 2 * KEY_WRAP_BLOCK_SIZE || plain.len() % KEY_WRAP_BLOCK_SIZE != 0 {
+        return Err(ErrorKind::InvalidLength.into());
+    }
+
+    wrap(&AES256::new(kek)?, KEY_WRAP_IV, plain)
+}
+
+/// Unwraps a key wrapped with `aes_key_wrap` (RFC 3394).
+pub fn aes_key_unwrap(kek: &Key, cyph: &[u8]) -> Result<Vec<u8>> {
+    if cyph.len() < 3 * KEY_WRAP_BLOCK_SIZE || cyph.len() % KEY_WRAP_BLOCK_SIZE != 0 {
+        return Err(ErrorKind::InvalidLength.into());
+    }
+
+    let (iv, mut plain) = unwrap(&AES256::new(kek)?, cyph)?;
+
+    if slices_equal(&iv, &KEY_WRAP_IV) != 1 {
+        plain.wipe();
+        return Err(ErrorKind::IntegrityFailure.into());
+    }
+
+    Ok(plain)
+}
+
+/// Wraps a key of any non-zero length with a key-encryption `Key` (RFC 5649).
+pub fn aes_key_wrap_pad(kek: &Key, plain: &[u8]) -> Result<Vec<u8>> {
+    if plain.is_empty() || plain.len() > u32::max_value() as usize {
+        return Err(ErrorKind::InvalidLength.into());
+    }
+
+    let mut iv = [0u8; 8];
+    iv[..4].copy_from_slice(&KEY_WRAP_PAD_IV);
+    BigEndian::write_u32(&mut iv[4..], plain.len() as u32);
+
+    let padded_len = (plain.len() + KEY_WRAP_BLOCK_SIZE - 1) / KEY_WRAP_BLOCK_SIZE * KEY_WRAP_BLOCK_SIZE;
+    let mut padded = plain.to_owned();
+    padded.resize(padded_len, 0);
+
+    let aes = AES256::new(kek)?;
+
+    let res = if padded_len == KEY_WRAP_BLOCK_SIZE {
+        // NB: a single semiblock is encrypted with the initial value as one AES block
+        let mut block = iv.to_vec();
+        block.extend_from_slice(&padded);
+        aes.encrypt_blocks(&mut block).map(|_| block)
+    } else {
+        wrap(&aes, iv, &padded)
+    };
+
+    padded.wipe();
+
+    res
+}
+
+/// Unwraps a key wrapped with `aes_key_wrap_pad` (RFC 5649).
+pub fn aes_key_unwrap_pad(kek: &Key, cyph: &[u8]) -> Result<Vec<u8>> {
+    if cyph.len() < 2 * KEY_WRAP_BLOCK_SIZE || cyph.len() % KEY_WRAP_BLOCK_SIZE != 0 {
+        return Err(ErrorKind::InvalidLength.into());
+    }
+
+    let aes = AES256::new(kek)?;
+
+    let (iv, mut padded) = if cyph.len() == 2 * KEY_WRAP_BLOCK_SIZE {
+        let mut block = cyph.to_owned();
+        aes.decrypt_blocks(&mut block)?;
+
+        let mut iv = [0u8; 8];
+        iv.copy_from_slice(&block[..KEY_WRAP_BLOCK_SIZE]);
+        let padded = block[KEY_WRAP_BLOCK_SIZE..].to_vec();
+        block.wipe();
+
+        (iv, padded)
+    } else {
+        unwrap(&aes, cyph)?
+    };
+
+    let len = BigEndian::read_u32(&iv[4..]) as usize;
+
+    // NB: the checks are all done before failing, to not tell which one failed
+    let mut valid = slices_equal(&iv[..4], &KEY_WRAP_PAD_IV);
+
+    if len <= padded.len() - KEY_WRAP_BLOCK_SIZE || len > padded.len() {
+        valid = 0;
+    } else {
+        let zeros = vec![0u8; padded.len() - len];
+        valid &= slices_equal(&padded[len..], &zeros);
+    }
+
+    if valid != 1 {
+        padded.wipe();
+        return Err(ErrorKind::IntegrityFailure.into());
+    }
+
+    padded.truncate(len);
+
+    Ok(padded)
+}
+
+/// Wraps a `Key` with a key-encryption `Key` (RFC 3394).
+pub fn wrap_key(kek: &Key, key: &Key) -> Result<Vec<u8>> {
+    aes_key_wrap(kek, key.expose_secret())
+}
+
+/// Unwraps a `Key` wrapped with `wrap_key`.
+pub fn unwrap_key(kek: &Key, cyph: &[u8]) -> Result<Key> {
+    let mut plain = aes_key_unwrap(kek, cyph)?;
+    let key = Key::from_bytes(&plain);
+    plain.wipe();
+
+    key
+}
+
+/// The wrapping function W (RFC 3394, section 2.2.1), with a given initial value.
+fn wrap(aes: &AES256, iv: [u8; 8], plain: &[u8]) -> Result<Vec<u8>> {
+    let n = plain.len() / KEY_WRAP_BLOCK_SIZE;
+
+    let mut cyph = iv.to_vec();
+    cyph.extend_from_slice(plain);
+
+    let mut block = [0u8; 16];
+
+    for j in 0..6 {
+        for i in 1..n + 1 {
+            let off = i * KEY_WRAP_BLOCK_SIZE;
+
+            block[..8].copy_from_slice(&cyph[..8]);
+            block[8..].copy_from_slice(&cyph[off..off + 8]);
+            aes.encrypt_blocks(&mut block)?;
+
+            let t = (n * j + i) as u64;
+            let a = BigEndian::read_u64(&block[..8]) ^ t;
+            BigEndian::write_u64(&mut cyph[..8], a);
+            cyph[off..off + 8].copy_from_slice(&block[8..]);
+        }
+    }
+
+    block.wipe();
+
+    Ok(cyph)
+}
+
+/// The unwrapping function W^-1 (RFC 3394, section 2.2.2), returning the
+/// initial value, to be checked by the caller, and the plaintext.
+fn unwrap(aes: &AES256, cyph: &[u8]) -> Result<([u8; 8], Vec<u8>)> {
+    let n = cyph.len() / KEY_WRAP_BLOCK_SIZE - 1;
+
+    let mut iv = [0u8; 8];
+    iv.copy_from_slice(&cyph[..8]);
+    let mut plain = cyph[8..].to_vec();
+
+    let mut block = [0u8; 16];
+
+    for j in (0..6).rev() {
+        for i in (1..n + 1).rev() {
+            let off = (i - 1) * KEY_WRAP_BLOCK_SIZE;
+
+            let t = (n * j + i) as u64;
+            BigEndian::write_u64(&mut block[..8], BigEndian::read_u64(&iv) ^ t);
+            block[8..].copy_from_slice(&plain[off..off + 8]);
+            aes.decrypt_blocks(&mut block)?;
+
+            iv.copy_from_slice(&block[..8]);
+            plain[off..off + 8].copy_from_slice(&block[8..]);
+        }
+    }
+
+    block.wipe();
+
+    Ok((iv, plain))
+}
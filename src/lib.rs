@@ -6,7 +6,7 @@
 // terms.
 
 //! Yobicrypto is the cryptographic toolkit used by the `Yobicash` cryptocurrency.
-//! It implements symmetric and assymmetric encryption primitives (AES256, x25519),
+//! It implements symmetric and assymmetric encryption primitives (AES256-GCM, AES256-GCM-SIV, ChaCha20-Poly1305, x25519),
 //! zero-knowledge proofs using the Schnorr Protocol (curve25519) made non-interactive
 //! with the Fiat-Shamir Transform (SHA3-512), and proof-of-work through Balloon Hashing
 //! ("such alpha, such CPU"). Caveat emptor.
@@ -31,10 +31,14 @@ extern crate ctaes_sys;
 extern crate rand;
 extern crate rug;
 extern crate byteorder;
+extern crate libc;
 
 pub mod error;
 pub mod result;
 pub mod traits;
+pub mod wipe;
+pub mod secret_serde;
+pub mod locked;
 pub mod random;
 pub mod hash;
 pub mod memory;
@@ -43,11 +47,31 @@ pub mod pow;
 pub mod scalar;
 pub mod point;
 pub mod zkp;
+pub mod x25519;
+pub mod kdf;
+pub mod aes;
+pub mod cipher;
+pub mod gcm;
+pub mod siv;
+pub mod chacha;
+pub mod envelope;
 pub mod encrypt;
+pub mod passphrase;
+pub mod keystore;
+pub mod recipients;
+pub mod stream;
+pub mod rotate;
+pub mod noise;
+pub mod ratchet;
+pub mod keywrap;
+pub mod cmac;
+pub mod signature;
 
 pub use self::error::*;
 pub use self::result::*;
 pub use self::traits::*;
+pub use self::wipe::*;
+pub use self::locked::*;
 pub use self::random::*;
 pub use self::hash::*;
 pub use self::memory::*;
@@ -56,4 +80,22 @@ pub use self::pow::*;
 pub use self::scalar::*;
 pub use self::point::*;
 pub use self::zkp::*;
+pub use self::x25519::*;
+pub use self::kdf::*;
+pub use self::aes::*;
+pub use self::cipher::*;
+pub use self::gcm::*;
+pub use self::siv::*;
+pub use self::chacha::*;
+pub use self::envelope::*;
 pub use self::encrypt::*;
+pub use self::passphrase::*;
+pub use self::keystore::*;
+pub use self::recipients::*;
+pub use self::stream::*;
+pub use self::rotate::*;
+pub use self::noise::*;
+pub use self::ratchet::*;
+pub use self::keywrap::*;
+pub use self::cmac::*;
+pub use self::signature::*;
@@ -0,0 +1,139 @@
+// Copyright 2018 Yobicash Ltd.
+//
+// Licensed under the MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>
+// and the Apache 2.0 license <LICENSE-APACHE or https://opensource.org/licenses/Apache-2.0>.
+// This file may not be copied, modified, or distributed except according to those
+// terms.
+
+//! The `locked` module provides a container for long-lived secrets, like wallet keys.
+//!
+//! The secret is kept in its own pages, locked in RAM with `mlock` so that it is never
+//! written to swap and, on Linux, excluded from core dumps with `MADV_DONTDUMP`.
+//! The pages are wiped when the container is dropped.
+
+use libc;
+
+use error::ErrorKind;
+use result::Result;
+use wipe::Wipe;
+
+use std::ops::{Deref, DerefMut};
+use std::{cmp, mem, ptr, slice};
+
+/// A value kept in locked memory.
+pub struct Locked<T> {
+    ptr: *mut T,
+    len: usize,
+}
+
+impl<T> Locked<T> {
+    /// Moves a value in locked memory. The value is moved, so the caller
+    /// should create it right before, to leave as few copies as possible.
+    pub fn new(value: T) -> Result<Locked<T>> {
+        let len = locked_size::<T>();
+        let ptr = unsafe { lock_alloc(len)? } as *mut T;
+
+        unsafe {
+            ptr::write(ptr, value);
+        }
+
+        Ok(Locked {
+            ptr: ptr,
+            len: len,
+        })
+    }
+}
+
+impl<T> Deref for Locked<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        unsafe { &*self.ptr }
+    }
+}
+
+impl<T> DerefMut for Locked<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        unsafe { &mut *self.ptr }
+    }
+}
+
+impl<T> Drop for Locked<T> {
+    fn drop(&mut self) {
+        unsafe {
+            ptr::drop_in_place(self.ptr);
+            lock_free(self.ptr as *mut u8, self.len);
+        }
+    }
+}
+
+unsafe impl<T: Send> Send for Locked<T> {}
+
+unsafe impl<T: Sync> Sync for Locked<T> {}
+
+/// Returns the size of the pages holding a `T`.
+fn locked_size<T>() -> usize {
+    let page = page_size();
+    let size = cmp::max(mem::size_of::<T>(), 1);
+
+    (size + page - 1) / page * page
+}
+
+#[cfg(unix)]
+fn page_size() -> usize {
+    unsafe { libc::sysconf(libc::_SC_PAGESIZE) as usize }
+}
+
+#[cfg(not(unix))]
+fn page_size() -> usize {
+    4096
+}
+
+/// Maps `len` bytes of locked memory.
+#[cfg(unix)]
+unsafe fn lock_alloc(len: usize) -> Result<*mut u8> {
+    let ptr = libc::mmap(ptr::null_mut(),
+                         len,
+                         libc::PROT_READ | libc::PROT_WRITE,
+                         libc::MAP_PRIVATE | libc::MAP_ANON,
+                         -1,
+                         0);
+    if ptr == libc::MAP_FAILED {
+        return Err(ErrorKind::MemoryLockFailure.into());
+    }
+
+    if libc::mlock(ptr, len) != 0 {
+        libc::munmap(ptr, len);
+        return Err(ErrorKind::MemoryLockFailure.into());
+    }
+
+    #[cfg(target_os = "linux")]
+    {
+        if libc::madvise(ptr, len, libc::MADV_DONTDUMP) != 0 {
+            libc::munlock(ptr, len);
+            libc::munmap(ptr, len);
+            return Err(ErrorKind::MemoryLockFailure.into());
+        }
+    }
+
+    Ok(ptr as *mut u8)
+}
+
+#[cfg(not(unix))]
+unsafe fn lock_alloc(_len: usize) -> Result<*mut u8> {
+    Err(ErrorKind::NotSupported.into())
+}
+
+/// Wipes and unmaps `len` bytes of locked memory.
+#[cfg(unix)]
+unsafe fn lock_free(ptr: *mut u8, len: usize) {
+    slice::from_raw_parts_mut(ptr, len).wipe();
+
+    libc::munlock(ptr as *mut libc::c_void, len);
+    libc::munmap(ptr as *mut libc::c_void, len);
+}
+
+#[cfg(not(unix))]
+unsafe fn lock_free(ptr: *mut u8, len: usize) {
+    slice::from_raw_parts_mut(ptr, len).wipe();
+}
@@ -0,0 +1,673 @@
+// Copyright 2018 Yobicash Ltd.
+//
+// Licensed under the MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>
+// and the Apache 2.0 license <LICENSE-APACHE or https://opensource.org/licenses/Apache-2.0>.
+// This file may not be copied, modified, or distributed except according to those
+// terms.
+
+//! The `noise` module provides the handshakes of the
+//! [Noise Protocol Framework](https://noiseprotocol.org/noise.html) (revision 34),
+//! with the XX, IK and NK patterns, X25519, AES256-GCM or ChaCha20-Poly1305, and SHA512.
+//!
+//! The Diffie-Hellman is the RFC 7748 X25519 of `X25519SecretKey` and `X25519PublicKey`,
+//! so that the handshakes interoperate with any other Noise implementation.
+//!
+//! A finished `HandshakeState` turns into a `TransportState`, which rekeys the
+//! sending and receiving `Cipher`s every `NOISE_REKEY_INTERVAL` messages. Over a
+//! stream, like a TCP connection, the messages are framed with `write_frame` and
+//! `read_frame`, prefixing them with their length as a big-endian `u16`.
+
+use digest::Digest;
+use sha2::Sha512;
+use byteorder::{BigEndian, LittleEndian, ByteOrder, ReadBytesExt, WriteBytesExt};
+
+use error::ErrorKind;
+use result::Result;
+use traits::BinarySerialize;
+use wipe::Wipe;
+use kdf::hkdf_sha512;
+use cipher::{CipherAlgorithm, Cipher};
+use encrypt::Key;
+use x25519::{X25519SecretKey, X25519PublicKey, X25519_KEY_SIZE};
+
+use std::io::{Read, Write};
+
+/// The maximum size in bytes of a Noise message.
+pub const NOISE_MAX_MESSAGE_SIZE: usize = 65535;
+
+/// The size in bytes of the authentication tag of a Noise message.
+pub const NOISE_TAG_SIZE: usize = 16;
+
+/// The number of messages after which a `TransportState` rekeys a `Cipher`.
+pub const NOISE_REKEY_INTERVAL: u64 = 1 << 20;
+
+/// The size in bytes of the SHA512 output.
+const HASH_SIZE: usize = 64;
+
+/// A Noise handshake pattern.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum HandshakePattern {
+    /// The responder `X25519PublicKey` is known to the initiator, who stays anonymous.
+    NK,
+    /// Both parties transmit their `X25519PublicKey`.
+    XX,
+    /// The responder `X25519PublicKey` is known to the initiator, who transmits
+    /// its own in the first message.
+    IK,
+}
+
+impl HandshakePattern {
+    /// Returns the name of the `HandshakePattern`.
+    pub fn name(&self) -> &'static str {
+        match *self {
+            HandshakePattern::NK => "NK",
+            HandshakePattern::XX => "XX",
+            HandshakePattern::IK => "IK",
+        }
+    }
+
+    /// Returns true if the responder `X25519PublicKey` is known before the handshake.
+    fn has_responder_premessage(&self) -> bool {
+        match *self {
+            HandshakePattern::NK | HandshakePattern::IK => true,
+            HandshakePattern::XX => false,
+        }
+    }
+
+    /// Returns true if the initiator has a static `X25519SecretKey`.
+    fn needs_initiator_static(&self) -> bool {
+        match *self {
+            HandshakePattern::NK => false,
+            HandshakePattern::XX | HandshakePattern::IK => true,
+        }
+    }
+
+    /// Returns the tokens of the messages of the handshake.
+    fn messages(&self) -> Vec<Vec<Token>> {
+        match *self {
+            HandshakePattern::NK => vec![
+                vec![Token::E, Token::ES],
+                vec![Token::E, Token::EE],
+            ],
+            HandshakePattern::XX => vec![
+                vec![Token::E],
+                vec![Token::E, Token::EE, Token::S, Token::ES],
+                vec![Token::S, Token::SE],
+            ],
+            HandshakePattern::IK => vec![
+                vec![Token::E, Token::ES, Token::S, Token::SS],
+                vec![Token::E, Token::EE, Token::SE],
+            ],
+        }
+    }
+}
+
+/// A token of a Noise message pattern.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+enum Token {
+    E,
+    S,
+    EE,
+    ES,
+    SE,
+    SS,
+}
+
+/// The parameters of a Noise protocol.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub struct NoiseParams {
+    /// The handshake pattern.
+    pub pattern: HandshakePattern,
+    /// The cipher, either `AES256GCM` or `ChaCha20Poly1305`.
+    pub algorithm: CipherAlgorithm,
+}
+
+impl NoiseParams {
+    /// Creates new `NoiseParams`.
+    pub fn new(pattern: HandshakePattern, algorithm: CipherAlgorithm) -> Result<NoiseParams> {
+        match algorithm {
+            CipherAlgorithm::AES256GCM | CipherAlgorithm::ChaCha20Poly1305 => {},
+            _ => return Err(ErrorKind::NotSupported.into()),
+        }
+
+        Ok(NoiseParams {
+            pattern: pattern,
+            algorithm: algorithm,
+        })
+    }
+
+    /// Parses `NoiseParams` from a protocol name (e.g. `Noise_XX_25519_AESGCM_SHA512`).
+    pub fn from_name(name: &str) -> Result<NoiseParams> {
+        let parts: Vec<&str> = name.split('_').collect();
+        if parts.len() != 5 || parts[0] != "Noise" || parts[2] != "25519" || parts[4] != "SHA512" {
+            return Err(ErrorKind::NotSupported.into());
+        }
+
+        let pattern = match parts[1] {
+            "NK" => HandshakePattern::NK,
+            "XX" => HandshakePattern::XX,
+            "IK" => HandshakePattern::IK,
+            _ => return Err(ErrorKind::NotSupported.into()),
+        };
+
+        let algorithm = match parts[3] {
+            "AESGCM" => CipherAlgorithm::AES256GCM,
+            "ChaChaPoly" => CipherAlgorithm::ChaCha20Poly1305,
+            _ => return Err(ErrorKind::NotSupported.into()),
+        };
+
+        NoiseParams::new(pattern, algorithm)
+    }
+
+    /// Returns the protocol name.
+    pub fn name(&self) -> String {
+        let cipher = match self.algorithm {
+            CipherAlgorithm::ChaCha20Poly1305 => "ChaChaPoly",
+            _ => "AESGCM",
+        };
+
+        format!("Noise_{}_25519_{}_SHA512", self.pattern.name(), cipher)
+    }
+}
+
+/// A Noise cipher state: a `Cipher` and a message counter used as nonce.
+#[derive(Clone)]
+struct CipherState {
+    algorithm: CipherAlgorithm,
+    cipher: Option<Cipher>,
+    n: u64,
+}
+
+impl CipherState {
+    fn new(algorithm: CipherAlgorithm) -> CipherState {
+        CipherState {
+            algorithm: algorithm,
+            cipher: None,
+            n: 0,
+        }
+    }
+
+    fn initialize_key(&mut self, key: Key) -> Result<()> {
+        self.cipher = Some(Cipher::new(self.algorithm, key)?);
+        self.n = 0;
+
+        Ok(())
+    }
+
+    fn has_key(&self) -> bool {
+        self.cipher.is_some()
+    }
+
+    /// Returns the cipher nonce of a counter: AES256-GCM encodes it in
+    /// big-endian, ChaCha20-Poly1305 in little-endian.
+    fn nonce(&self, n: u64) -> [u8; 12] {
+        let mut nonce = [0u8; 12];
+
+        match self.algorithm {
+            CipherAlgorithm::ChaCha20Poly1305 => LittleEndian::write_u64(&mut nonce[4..], n),
+            _ => BigEndian::write_u64(&mut nonce[4..], n),
+        }
+
+        nonce
+    }
+
+    fn encrypt_with_ad(&mut self, ad: &[u8], plain: &[u8]) -> Result<Vec<u8>> {
+        let cyph = match self.cipher {
+            None => return Ok(plain.to_owned()),
+            Some(ref cipher) => {
+                if self.n == u64::max_value() {
+                    return Err(ErrorKind::OutOfBound.into());
+                }
+
+                cipher.encrypt(&self.nonce(self.n), ad, plain)?
+            },
+        };
+
+        self.n += 1;
+
+        Ok(cyph)
+    }
+
+    fn decrypt_with_ad(&mut self, ad: &[u8], cyph: &[u8]) -> Result<Vec<u8>> {
+        let plain = match self.cipher {
+            None => return Ok(cyph.to_owned()),
+            Some(ref cipher) => {
+                if self.n == u64::max_value() {
+                    return Err(ErrorKind::OutOfBound.into());
+                }
+
+                cipher.decrypt(&self.nonce(self.n), ad, cyph)?
+            },
+        };
+
+        self.n += 1;
+
+        Ok(plain)
+    }
+
+    /// Returns the state rekeyed with the first 32 bytes of the encryption of
+    /// 32 zero bytes with the maximum nonce, keeping the counter.
+    fn rekeyed(&self) -> Result<CipherState> {
+        let mut _key = match self.cipher {
+            None => return Err(ErrorKind::InvalidHandshake.into()),
+            Some(ref cipher) => cipher.encrypt(&self.nonce(u64::max_value()), &[], &[0u8; 32])?,
+        };
+
+        let key = Key::from_bytes(&_key[..32]);
+        _key.wipe();
+
+        Ok(CipherState {
+            algorithm: self.algorithm,
+            cipher: Some(Cipher::new(self.algorithm, key?)?),
+            n: self.n,
+        })
+    }
+}
+
+/// The Noise symmetric state: the chaining key, the handshake hash and a `CipherState`.
+#[derive(Clone)]
+struct SymmetricState {
+    cs: CipherState,
+    ck: [u8; HASH_SIZE],
+    h: [u8; HASH_SIZE],
+}
+
+impl SymmetricState {
+    fn new(params: &NoiseParams) -> SymmetricState {
+        let name = params.name();
+
+        let mut h = [0u8; HASH_SIZE];
+        if name.len() <= HASH_SIZE {
+            h[..name.len()].copy_from_slice(name.as_bytes());
+        } else {
+            h.copy_from_slice(Sha512::digest(name.as_bytes()).as_slice());
+        }
+
+        SymmetricState {
+            cs: CipherState::new(params.algorithm),
+            ck: h,
+            h: h,
+        }
+    }
+
+    fn mix_key(&mut self, ikm: &[u8]) -> Result<()> {
+        let mut okm = hkdf_sha512(&self.ck, ikm, &[], 2 * HASH_SIZE)?;
+
+        self.ck.copy_from_slice(&okm[..HASH_SIZE]);
+        let key = Key::from_bytes(&okm[HASH_SIZE..HASH_SIZE + 32]);
+        okm.wipe();
+
+        self.cs.initialize_key(key?)?;
+
+        Ok(())
+    }
+
+    fn mix_hash(&mut self, data: &[u8]) {
+        let mut hasher = Sha512::default();
+        hasher.input(&self.h);
+        hasher.input(data);
+
+        self.h.copy_from_slice(hasher.result().as_slice());
+    }
+
+    fn encrypt_and_hash(&mut self, plain: &[u8]) -> Result<Vec<u8>> {
+        let cyph = self.cs.encrypt_with_ad(&self.h, plain)?;
+        self.mix_hash(&cyph);
+
+        Ok(cyph)
+    }
+
+    fn decrypt_and_hash(&mut self, cyph: &[u8]) -> Result<Vec<u8>> {
+        let plain = self.cs.decrypt_with_ad(&self.h, cyph)?;
+        self.mix_hash(cyph);
+
+        Ok(plain)
+    }
+
+    fn split(&self) -> Result<(CipherState, CipherState)> {
+        let mut okm = hkdf_sha512(&self.ck, &[], &[], 2 * HASH_SIZE)?;
+
+        let key_1 = Key::from_bytes(&okm[..32]);
+        let key_2 = Key::from_bytes(&okm[HASH_SIZE..HASH_SIZE + 32]);
+        okm.wipe();
+
+        let mut cs_1 = CipherState::new(self.cs.algorithm);
+        cs_1.initialize_key(key_1?)?;
+
+        let mut cs_2 = CipherState::new(self.cs.algorithm);
+        cs_2.initialize_key(key_2?)?;
+
+        Ok((cs_1, cs_2))
+    }
+}
+
+impl Drop for SymmetricState {
+    fn drop(&mut self) {
+        self.ck.wipe();
+    }
+}
+
+/// The state of a Noise handshake.
+pub struct HandshakeState {
+    params: NoiseParams,
+    initiator: bool,
+    symmetric: SymmetricState,
+    s: Option<X25519SecretKey>,
+    e: Option<X25519SecretKey>,
+    rs: Option<X25519PublicKey>,
+    re: Option<X25519PublicKey>,
+    messages: Vec<Vec<Token>>,
+    idx: usize,
+}
+
+impl HandshakeState {
+    /// Creates the `HandshakeState` of the initiator. The static `X25519SecretKey`
+    /// is required by XX and IK, the responder `X25519PublicKey` by NK and IK.
+    pub fn initiator(params: NoiseParams, prologue: &[u8], s: Option<X25519SecretKey>, rs: Option<X25519PublicKey>) -> Result<HandshakeState> {
+        if params.pattern.needs_initiator_static() != s.is_some() {
+            return Err(ErrorKind::InvalidHandshake.into());
+        }
+
+        if params.pattern.has_responder_premessage() != rs.is_some() {
+            return Err(ErrorKind::InvalidHandshake.into());
+        }
+
+        HandshakeState::new(params, true, prologue, s, rs)
+    }
+
+    /// Creates the `HandshakeState` of the responder, which always has a static
+    /// `X25519SecretKey`.
+    pub fn responder(params: NoiseParams, prologue: &[u8], s: X25519SecretKey) -> Result<HandshakeState> {
+        HandshakeState::new(params, false, prologue, Some(s), None)
+    }
+
+    fn new(params: NoiseParams, initiator: bool, prologue: &[u8], s: Option<X25519SecretKey>, rs: Option<X25519PublicKey>) -> Result<HandshakeState> {
+        let mut symmetric = SymmetricState::new(&params);
+        symmetric.mix_hash(prologue);
+
+        if params.pattern.has_responder_premessage() {
+            let responder_pk = if initiator {
+                rs.ok_or(ErrorKind::InvalidHandshake)?
+            } else {
+                s.as_ref().ok_or(ErrorKind::InvalidHandshake)?.to_public()
+            };
+
+            symmetric.mix_hash(&responder_pk.to_bytes()?);
+        }
+
+        Ok(HandshakeState {
+            params: params,
+            initiator: initiator,
+            symmetric: symmetric,
+            s: s,
+            e: None,
+            rs: rs,
+            re: None,
+            messages: params.pattern.messages(),
+            idx: 0,
+        })
+    }
+
+    /// Sets the ephemeral `X25519SecretKey` instead of generating a random one.
+    /// Only meant for test vectors: reusing an ephemeral key breaks the security
+    /// of the handshake.
+    pub fn set_ephemeral(&mut self, e: X25519SecretKey) {
+        self.e = Some(e);
+    }
+
+    /// Returns the `NoiseParams` of the handshake.
+    pub fn params(&self) -> NoiseParams {
+        self.params
+    }
+
+    /// Returns true if the handshake is finished.
+    pub fn is_finished(&self) -> bool {
+        self.idx == self.messages.len()
+    }
+
+    /// Returns true if it is our turn to write a message.
+    pub fn is_my_turn(&self) -> bool {
+        !self.is_finished() && (self.idx % 2 == 0) == self.initiator
+    }
+
+    /// Returns the static `X25519PublicKey` of the other party, if known.
+    pub fn remote_static(&self) -> Option<X25519PublicKey> {
+        self.rs
+    }
+
+    /// Returns the handshake hash, which uniquely identifies the handshake.
+    pub fn handshake_hash(&self) -> Vec<u8> {
+        self.symmetric.h.to_vec()
+    }
+
+    /// Writes the next handshake message, carrying a payload.
+    pub fn write_message(&mut self, payload: &[u8]) -> Result<Vec<u8>> {
+        if !self.is_my_turn() {
+            return Err(ErrorKind::InvalidHandshake.into());
+        }
+
+        let mut message = Vec::new();
+
+        for token in self.messages[self.idx].clone() {
+            match token {
+                Token::E => {
+                    if self.e.is_none() {
+                        self.e = Some(X25519SecretKey::random());
+                    }
+
+                    let epk = self.e.as_ref().unwrap().to_public().to_bytes()?;
+                    self.symmetric.mix_hash(&epk);
+                    message.extend_from_slice(&epk);
+                },
+                Token::S => {
+                    let spk = self.s.as_ref()
+                        .ok_or(ErrorKind::InvalidHandshake)?
+                        .to_public()
+                        .to_bytes()?;
+                    let cyph = self.symmetric.encrypt_and_hash(&spk)?;
+                    message.extend_from_slice(&cyph);
+                },
+                _ => {
+                    let (rs, re) = (self.rs, self.re);
+                    let mut shared = self.dh(token, rs, re)?;
+                    let res = self.symmetric.mix_key(&shared);
+                    shared.wipe();
+                    res?;
+                },
+            }
+        }
+
+        let cyph = self.symmetric.encrypt_and_hash(payload)?;
+        message.extend_from_slice(&cyph);
+
+        if message.len() > NOISE_MAX_MESSAGE_SIZE {
+            return Err(ErrorKind::InvalidLength.into());
+        }
+
+        self.idx += 1;
+
+        Ok(message)
+    }
+
+    /// Reads the next handshake message, returning its payload.
+    pub fn read_message(&mut self, message: &[u8]) -> Result<Vec<u8>> {
+        if self.is_finished() || self.is_my_turn() {
+            return Err(ErrorKind::InvalidHandshake.into());
+        }
+
+        if message.len() > NOISE_MAX_MESSAGE_SIZE {
+            return Err(ErrorKind::InvalidLength.into());
+        }
+
+        // NB: the state is only updated if the whole message is valid, and the
+        // secret keys are borrowed instead of copied in the scratch state
+        let mut symmetric = self.symmetric.clone();
+        let mut rs = self.rs;
+        let mut re = self.re;
+
+        let mut rest = message;
+
+        for token in self.messages[self.idx].iter() {
+            match *token {
+                Token::E => {
+                    if rest.len() < X25519_KEY_SIZE {
+                        return Err(ErrorKind::InvalidLength.into());
+                    }
+
+                    let (epk, tail) = rest.split_at(X25519_KEY_SIZE);
+                    re = Some(X25519PublicKey::from_bytes(epk)?);
+                    symmetric.mix_hash(epk);
+                    rest = tail;
+                },
+                Token::S => {
+                    let len = X25519_KEY_SIZE + if symmetric.cs.has_key() { NOISE_TAG_SIZE } else { 0 };
+                    if rest.len() < len {
+                        return Err(ErrorKind::InvalidLength.into());
+                    }
+
+                    let (cyph, tail) = rest.split_at(len);
+                    let spk = symmetric.decrypt_and_hash(cyph)?;
+                    rs = Some(X25519PublicKey::from_bytes(&spk)?);
+                    rest = tail;
+                },
+                _ => {
+                    let mut shared = self.dh(*token, rs, re)?;
+                    let res = symmetric.mix_key(&shared);
+                    shared.wipe();
+                    res?;
+                },
+            }
+        }
+
+        let payload = symmetric.decrypt_and_hash(rest)?;
+
+        self.symmetric = symmetric;
+        self.rs = rs;
+        self.re = re;
+        self.idx += 1;
+
+        Ok(payload)
+    }
+
+    /// Returns the Diffie-Hellman of a token, with the given remote public keys.
+    fn dh(&self, token: Token, rs: Option<X25519PublicKey>, re: Option<X25519PublicKey>) -> Result<[u8; 32]> {
+        let (sk, pk) = match (token, self.initiator) {
+            (Token::EE, _) => (&self.e, re),
+            (Token::SS, _) => (&self.s, rs),
+            (Token::ES, true) | (Token::SE, false) => (&self.e, rs),
+            (Token::ES, false) | (Token::SE, true) => (&self.s, re),
+            _ => return Err(ErrorKind::InvalidHandshake.into()),
+        };
+
+        let sk = sk.as_ref().ok_or(ErrorKind::InvalidHandshake)?;
+        let pk = pk.ok_or(ErrorKind::InvalidHandshake)?;
+
+        sk.diffie_hellman(&pk)
+    }
+
+    /// Turns a finished handshake into a `TransportState`.
+    pub fn into_transport(self) -> Result<TransportState> {
+        if !self.is_finished() {
+            return Err(ErrorKind::InvalidHandshake.into());
+        }
+
+        let (cs_1, cs_2) = self.symmetric.split()?;
+        let (send, recv) = if self.initiator { (cs_1, cs_2) } else { (cs_2, cs_1) };
+
+        Ok(TransportState {
+            send: send,
+            recv: recv,
+            rs: self.rs,
+            handshake_hash: self.handshake_hash(),
+            rekey_interval: NOISE_REKEY_INTERVAL,
+        })
+    }
+}
+
+/// The state of a Noise transport, after the handshake.
+pub struct TransportState {
+    send: CipherState,
+    recv: CipherState,
+    rs: Option<X25519PublicKey>,
+    handshake_hash: Vec<u8>,
+    rekey_interval: u64,
+}
+
+impl TransportState {
+    /// Returns the static `X25519PublicKey` of the other party, if known.
+    pub fn remote_static(&self) -> Option<X25519PublicKey> {
+        self.rs
+    }
+
+    /// Returns the hash of the handshake.
+    pub fn handshake_hash(&self) -> Vec<u8> {
+        self.handshake_hash.clone()
+    }
+
+    /// Sets the number of messages after which the `Cipher`s are rekeyed.
+    /// Both parties must use the same interval.
+    pub fn set_rekey_interval(&mut self, interval: u64) -> Result<()> {
+        if interval == 0 {
+            return Err(ErrorKind::InvalidArgument.into());
+        }
+
+        self.rekey_interval = interval;
+
+        Ok(())
+    }
+
+    /// Encrypts a message.
+    pub fn write_message(&mut self, payload: &[u8]) -> Result<Vec<u8>> {
+        if payload.len() + NOISE_TAG_SIZE > NOISE_MAX_MESSAGE_SIZE {
+            return Err(ErrorKind::InvalidLength.into());
+        }
+
+        if self.send.n != 0 && self.send.n % self.rekey_interval == 0 {
+            self.send = self.send.rekeyed()?;
+        }
+
+        self.send.encrypt_with_ad(&[], payload)
+    }
+
+    /// Decrypts a message.
+    pub fn read_message(&mut self, message: &[u8]) -> Result<Vec<u8>> {
+        if message.len() > NOISE_MAX_MESSAGE_SIZE {
+            return Err(ErrorKind::InvalidLength.into());
+        }
+
+        if self.recv.n != 0 && self.recv.n % self.rekey_interval == 0 {
+            // NB: the rekeyed state is kept only if the message decrypts, or a forged
+            // message at the boundary would rekey twice and break the session
+            let mut recv = self.recv.rekeyed()?;
+            let plain = recv.decrypt_with_ad(&[], message)?;
+            self.recv = recv;
+
+            return Ok(plain);
+        }
+
+        self.recv.decrypt_with_ad(&[], message)
+    }
+}
+
+/// Writes a Noise message to a stream, prefixed by its length as a big-endian `u16`.
+pub fn write_frame<W: Write>(writer: &mut W, message: &[u8]) -> Result<()> {
+    if message.len() > NOISE_MAX_MESSAGE_SIZE {
+        return Err(ErrorKind::InvalidLength.into());
+    }
+
+    writer.write_u16::<BigEndian>(message.len() as u16)?;
+    writer.write_all(message)?;
+    writer.flush()?;
+
+    Ok(())
+}
+
+/// Reads a Noise message written with `write_frame` from a stream.
+pub fn read_frame<R: Read>(reader: &mut R) -> Result<Vec<u8>> {
+    let len = reader.read_u16::<BigEndian>()? as usize;
+
+    let mut message = vec![0u8; len];
+    reader.read_exact(&mut message)?;
+
+    Ok(message)
+}
@@ -0,0 +1,105 @@
+// Copyright 2018 Yobicash Ltd.
+//
+// Licensed under the MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>
+// and the Apache 2.0 license <LICENSE-APACHE or https://opensource.org/licenses/Apache-2.0>.
+// This file may not be copied, modified, or distributed except according to those
+// terms.
+
+//! The `passphrase` module provides passphrase-based encryption, using Balloon
+//! hashing to derive the `Key`.
+//!
+//! The cyphertext is laid out as `salt || s_cost || t_cost || delta || envelope`,
+//! where the params are big-endian `u32`s. The header is authenticated as
+//! associated data of the `Envelope`.
+//!
+//! The params are read from the header before the authentication, so they are
+//! bounded by `PASSPHRASE_MIN_*` and `PASSPHRASE_MAX_*` before hashing: a forged
+//! header cannot exhaust the memory or the CPU, nor skip the Balloon mixing rounds.
+//! At the maxima a decryption attempt costs at most `2^24` Balloon hash calls.
+
+use byteorder::{BigEndian, ByteOrder, WriteBytesExt};
+
+use error::ErrorKind;
+use result::Result;
+use traits::{Validate, BinarySerialize};
+use random::Random;
+use hash::Digest;
+use balloon::BalloonParams;
+use encrypt::{Key, sym_encrypt_with_ad, sym_decrypt_with_ad};
+
+/// The size in bytes of the header of a passphrase-encrypted cyphertext.
+pub const PASSPHRASE_HEADER_SIZE: usize = 64 + 12;
+
+/// The minimum Balloon `s_cost` of a passphrase-derived `Key`.
+pub const PASSPHRASE_MIN_S_COST: u32 = 16;
+
+/// The maximum Balloon `s_cost` of a passphrase-derived `Key` (8 MiB of digests).
+pub const PASSPHRASE_MAX_S_COST: u32 = 1 << 17;
+
+/// The minimum Balloon `t_cost` of a passphrase-derived `Key`.
+pub const PASSPHRASE_MIN_T_COST: u32 = 2;
+
+/// The maximum Balloon `t_cost` of a passphrase-derived `Key`.
+pub const PASSPHRASE_MAX_T_COST: u32 = 8;
+
+/// The maximum Balloon `delta` of a passphrase-derived `Key`.
+pub const PASSPHRASE_MAX_DELTA: u32 = 16;
+
+/// Checks that the `BalloonParams` of a passphrase-derived `Key` are within
+/// the passphrase bounds, failing with `ErrorKind::OutOfBound` otherwise.
+pub fn check_passphrase_params(params: BalloonParams) -> Result<()> {
+    params.validate()?;
+
+    if params.s_cost < PASSPHRASE_MIN_S_COST || params.s_cost > PASSPHRASE_MAX_S_COST {
+        return Err(ErrorKind::OutOfBound.into());
+    }
+
+    if params.t_cost < PASSPHRASE_MIN_T_COST || params.t_cost > PASSPHRASE_MAX_T_COST {
+        return Err(ErrorKind::OutOfBound.into());
+    }
+
+    if params.delta > PASSPHRASE_MAX_DELTA {
+        return Err(ErrorKind::OutOfBound.into());
+    }
+
+    Ok(())
+}
+
+/// Encrypts a plaintext with a `Key` derived from a passphrase and a random salt.
+pub fn encrypt_with_passphrase(passphrase: &[u8], params: BalloonParams, plaintext: &[u8]) -> Result<Vec<u8>> {
+    check_passphrase_params(params)?;
+
+    let salt = Digest::from_bytes(&Random::bytes(64))?;
+    let key = Key::from_passphrase(passphrase, salt, params)?;
+
+    let mut header = salt.to_bytes()?;
+    header.write_u32::<BigEndian>(params.s_cost)?;
+    header.write_u32::<BigEndian>(params.t_cost)?;
+    header.write_u32::<BigEndian>(params.delta)?;
+
+    let mut cyph = header.clone();
+    cyph.extend_from_slice(&sym_encrypt_with_ad(key, &header, plaintext)?);
+
+    Ok(cyph)
+}
+
+/// Decrypts a cyphertext produced by `encrypt_with_passphrase`. A wrong passphrase
+/// fails with `ErrorKind::InvalidTag`.
+pub fn decrypt_with_passphrase(passphrase: &[u8], cyph: &[u8]) -> Result<Vec<u8>> {
+    if cyph.len() < PASSPHRASE_HEADER_SIZE {
+        return Err(ErrorKind::InvalidLength.into());
+    }
+
+    let (header, envelope) = cyph.split_at(PASSPHRASE_HEADER_SIZE);
+
+    let salt = Digest::from_bytes(&header[..64])?;
+    let s_cost = BigEndian::read_u32(&header[64..68]);
+    let t_cost = BigEndian::read_u32(&header[68..72]);
+    let delta = BigEndian::read_u32(&header[72..76]);
+    let params = BalloonParams::new(s_cost, t_cost, delta)?;
+    check_passphrase_params(params)?;
+
+    let key = Key::from_passphrase(passphrase, salt, params)?;
+
+    sym_decrypt_with_ad(key, header, envelope)
+}
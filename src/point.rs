@@ -8,10 +8,11 @@
 //! The `point` module provides types and methods for ECC points on
 //! Curve25519.
 
-use curve25519::constants::ED25519_BASEPOINT_POINT;
+use curve25519::constants::{ED25519_BASEPOINT_POINT, BASEPOINT_ORDER};
+use curve25519::edwards::vartime;
 use curve25519::edwards::CompressedEdwardsY;
 use curve25519::edwards::ExtendedPoint as CurvePoint;
-use curve25519::traits::Identity;
+use curve25519::traits::{Identity, IsIdentity};
 use subtle::Equal;
 use hex;
 
@@ -22,6 +23,7 @@ use traits::{BinarySerialize, HexSerialize};
 use scalar::Scalar;
 
 use std::ops::{Add, Sub, Mul};
+use std::iter;
 use std::fmt;
 
 /// A point is a ECC point on the Edwards form of Curve25519.
@@ -45,6 +47,17 @@ impl Point {
 
         Ok(point)
     }
+
+    /// Returns true if the `Point` has a small order, dividing the cofactor 8.
+    pub fn is_small_order(&self) -> bool {
+        self.0.is_small_order()
+    }
+
+    /// Returns true if the `Point` has no small-order component, i.e. it is in the
+    /// subgroup of prime order l. The check is in variable time.
+    pub fn is_torsion_free(&self) -> bool {
+        vartime::multiscalar_mult(iter::once(&BASEPOINT_ORDER), iter::once(&self.0)).is_identity()
+    }
 }
 
 impl Default for Point {
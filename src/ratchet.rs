@@ -0,0 +1,241 @@
+// Copyright 2018 Yobicash Ltd.
+//
+// Licensed under the MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>
+// and the Apache 2.0 license <LICENSE-APACHE or https://opensource.org/licenses/Apache-2.0>.
+// This file may not be copied, modified, or distributed except according to those
+// terms.
+
+//! The `ratchet` module provides a forward-secure symmetric key ratchet.
+//!
+//! A `ChainKey` derives a fresh message `Key` for each message with HMAC-SHA512 and
+//! then replaces itself with another HMAC-SHA512 output, so a compromised `ChainKey`
+//! does not reveal the keys of the previous messages. A `SymmetricRatchet` encrypts
+//! with the message keys, and keeps a bounded store of the keys of the messages
+//! skipped by an out-of-order delivery.
+//!
+//! A channel uses one `SymmetricRatchet` per direction, e.g. created from the
+//! `send_key` and `recv_key` of a `SharedSecret`.
+
+use rmp_serde::encode as encode_msgpk;
+use rmp_serde::decode as decode_msgpk;
+use byteorder::{BigEndian, ByteOrder};
+use hex;
+
+use error::ErrorKind;
+use result::Result;
+use traits::{Validate, BinarySerialize, HexSerialize};
+use wipe::Wipe;
+use kdf::hmac_sha512;
+use encrypt::{Key, sym_encrypt_with_ad, sym_decrypt_with_ad};
+use secret_serde;
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// The default maximum number of skipped message keys kept by a `SymmetricRatchet`.
+pub const RATCHET_MAX_SKIP: usize = 1000;
+
+/// The size in bytes of the message index prefixed to a ratchet cyphertext.
+const INDEX_SIZE: usize = 8;
+
+/// The HMAC-SHA512 message deriving a message `Key`.
+const MESSAGE_KEY_SEED: &[u8] = &[0x01];
+
+/// The HMAC-SHA512 message deriving the next `ChainKey`.
+const CHAIN_KEY_SEED: &[u8] = &[0x02];
+
+/// A chain key, deriving the message keys of a symmetric ratchet.
+#[derive(Clone, Eq, PartialEq, Debug, Default, Serialize, Deserialize)]
+pub struct ChainKey {
+    #[serde(with = "secret_serde")]
+    key: Key,
+    index: u64,
+}
+
+impl ChainKey {
+    /// Creates a new `ChainKey` from a `Key` shared by both parties.
+    pub fn new(key: Key) -> ChainKey {
+        ChainKey {
+            key: key,
+            index: 0,
+        }
+    }
+
+    /// Returns the index of the next message `Key`.
+    pub fn index(&self) -> u64 {
+        self.index
+    }
+
+    /// Returns the next message `Key` with its index, and advances the chain.
+    /// The previous chain key is overwritten and cannot be recovered.
+    pub fn next_key(&mut self) -> Result<(u64, Key)> {
+        if self.index == u64::max_value() {
+            return Err(ErrorKind::OutOfBound.into());
+        }
+
+        let mut _message_key = hmac_sha512(self.key.expose_secret(), MESSAGE_KEY_SEED);
+        let mut _chain_key = hmac_sha512(self.key.expose_secret(), CHAIN_KEY_SEED);
+
+        let message_key = Key::from_bytes(&_message_key[..32]);
+        let chain_key = Key::from_bytes(&_chain_key[..32]);
+        _message_key.wipe();
+        _chain_key.wipe();
+
+        let index = self.index;
+        self.key = chain_key?;
+        self.index += 1;
+
+        Ok((index, message_key?))
+    }
+}
+
+/// A forward-secure symmetric ratchet, encrypting each message with a new `Key`.
+#[derive(Clone, Eq, PartialEq, Debug, Default, Serialize, Deserialize)]
+pub struct SymmetricRatchet {
+    chain: ChainKey,
+    #[serde(with = "secret_serde::map")]
+    skipped: BTreeMap<u64, Key>,
+    max_skip: usize,
+}
+
+impl SymmetricRatchet {
+    /// Creates a new `SymmetricRatchet` from a `Key` shared by both parties.
+    pub fn new(key: Key) -> SymmetricRatchet {
+        SymmetricRatchet::with_max_skip(key, RATCHET_MAX_SKIP)
+    }
+
+    /// Creates a new `SymmetricRatchet` keeping at most `max_skip` skipped message keys.
+    pub fn with_max_skip(key: Key, max_skip: usize) -> SymmetricRatchet {
+        SymmetricRatchet {
+            chain: ChainKey::new(key),
+            skipped: BTreeMap::new(),
+            max_skip: max_skip,
+        }
+    }
+
+    /// Returns the index of the next message.
+    pub fn index(&self) -> u64 {
+        self.chain.index()
+    }
+
+    /// Returns the number of skipped message keys that are stored.
+    pub fn skipped_len(&self) -> usize {
+        self.skipped.len()
+    }
+
+    /// Encrypts a message with the next message `Key`, authenticating also the
+    /// associated data.
+    pub fn encrypt(&mut self, ad: &[u8], plain: &[u8]) -> Result<Vec<u8>> {
+        let (index, key) = self.chain.next_key()?;
+
+        let mut cyph = vec![0u8; INDEX_SIZE];
+        BigEndian::write_u64(&mut cyph, index);
+
+        let envelope = sym_encrypt_with_ad(key, &message_ad(index, ad), plain)?;
+        cyph.extend_from_slice(&envelope);
+
+        Ok(cyph)
+    }
+
+    /// Decrypts a message, in any order. A message can be decrypted only once.
+    pub fn decrypt(&mut self, ad: &[u8], cyph: &[u8]) -> Result<Vec<u8>> {
+        if cyph.len() < INDEX_SIZE {
+            return Err(ErrorKind::InvalidLength.into());
+        }
+
+        let index = BigEndian::read_u64(&cyph[..INDEX_SIZE]);
+        let envelope = &cyph[INDEX_SIZE..];
+        let ad = message_ad(index, ad);
+
+        if index < self.chain.index() {
+            let plain = {
+                let key = self.skipped.get(&index).ok_or(ErrorKind::NotFound)?;
+                sym_decrypt_with_ad(key.clone(), &ad, envelope)?
+            };
+
+            self.skipped.remove(&index);
+
+            return Ok(plain);
+        }
+
+        if index - self.chain.index() > self.max_skip as u64 {
+            return Err(ErrorKind::OutOfBound.into());
+        }
+
+        // NB: the ratchet advances only if the message is authentic
+        let mut chain = self.chain.clone();
+        let mut skipped = Vec::new();
+
+        while chain.index() < index {
+            skipped.push(chain.next_key()?);
+        }
+
+        let (_, key) = chain.next_key()?;
+        let plain = sym_decrypt_with_ad(key, &ad, envelope)?;
+
+        self.chain = chain;
+        self.skipped.extend(skipped);
+
+        while self.skipped.len() > self.max_skip {
+            let oldest = *self.skipped.keys().next().unwrap();
+            self.skipped.remove(&oldest);
+        }
+
+        Ok(plain)
+    }
+}
+
+/// Binds the message index to the associated data.
+fn message_ad(index: u64, ad: &[u8]) -> Vec<u8> {
+    let mut buf = vec![0u8; INDEX_SIZE];
+    BigEndian::write_u64(&mut buf, index);
+    buf.extend_from_slice(ad);
+
+    buf
+}
+
+impl Validate for SymmetricRatchet {
+    fn validate(&self) -> Result<()> {
+        if self.skipped.len() > self.max_skip {
+            return Err(ErrorKind::OutOfBound.into());
+        }
+
+        if self.skipped.keys().any(|index| *index >= self.chain.index()) {
+            return Err(ErrorKind::InvalidArgument.into());
+        }
+
+        Ok(())
+    }
+}
+
+impl BinarySerialize for SymmetricRatchet {
+    fn to_bytes(&self) -> Result<Vec<u8>> {
+        encode_msgpk::to_vec(self)
+            .map_err(|_| ErrorKind::SerializationFailure.into())
+    }
+
+    fn from_bytes(b: &[u8]) -> Result<SymmetricRatchet> {
+        let ratchet: SymmetricRatchet = decode_msgpk::from_slice(b)
+            .map_err(|_| ErrorKind::DeserializationFailure)?;
+
+        ratchet.validate()?;
+
+        Ok(ratchet)
+    }
+}
+
+impl HexSerialize for SymmetricRatchet {
+    fn to_hex(&self) -> Result<String> {
+        Ok(hex::encode(&self.to_bytes()?))
+    }
+
+    fn from_hex(s: &str) -> Result<SymmetricRatchet> {
+        Self::from_bytes(&hex::decode(s)?)
+    }
+}
+
+impl fmt::Display for SymmetricRatchet {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "SymmetricRatchet(index: {}, skipped: {})", self.index(), self.skipped_len())
+    }
+}
@@ -0,0 +1,164 @@
+// Copyright 2018 Yobicash Ltd.
+//
+// Licensed under the MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>
+// and the Apache 2.0 license <LICENSE-APACHE or https://opensource.org/licenses/Apache-2.0>.
+// This file may not be copied, modified, or distributed except according to those
+// terms.
+
+//! The `recipients` module provides the encryption of a plaintext to many `PublicKey`s.
+//!
+//! The plaintext is encrypted once in an `Envelope` under a random `Key`, which is
+//! wrapped in a slot for every recipient with a `Key` derived from the x25519 exchange
+//! between a fresh ephemeral `SecretKey` and the recipient `PublicKey`. The cyphertext
+//! is laid out as `epk || algorithm || hints || count || slots || envelope`, where
+//! `count` is a big-endian `u32`, and the whole header is authenticated as associated
+//! data of the `Envelope`.
+//!
+//! Slots do not name their recipient, so a recipient finds their own slot by trial
+//! decryption and learns nothing about the other recipients. When explicitly requested,
+//! every slot is prefixed by a hint of the recipient `PublicKey` fingerprint.
+
+use byteorder::{BigEndian, ByteOrder, WriteBytesExt};
+
+use error::ErrorKind;
+use result::Result;
+use traits::{Validate, BinarySerialize};
+use random::Random;
+use cipher::{CipherAlgorithm, Cipher};
+use encrypt::{SecretKey, PublicKey, Key, sealed_key};
+use encrypt::{sym_encrypt_with_cipher, sym_decrypt_with_ad};
+use wipe::Wipe;
+
+/// The size in bytes of a recipient hint.
+pub const RECIPIENT_HINT_SIZE: usize = 8;
+
+/// The size in bytes of a wrapped `Key`, without the hint.
+const SLOT_KEY_SIZE: usize = 32;
+
+/// Encrypts a plaintext once to many `PublicKey`s, without revealing the recipients.
+pub fn encrypt_to_many(pks: &[PublicKey], plaintext: &[u8]) -> Result<Vec<u8>> {
+    encrypt_to_many_with(CipherAlgorithm::default(), pks, false, plaintext)
+}
+
+/// Encrypts a plaintext once to many `PublicKey`s, prefixing every slot with a hint
+/// of the recipient. The hints reveal the recipients to anyone knowing their `PublicKey`s.
+pub fn encrypt_to_many_with_hints(pks: &[PublicKey], plaintext: &[u8]) -> Result<Vec<u8>> {
+    encrypt_to_many_with(CipherAlgorithm::default(), pks, true, plaintext)
+}
+
+/// Encrypts a plaintext once to many `PublicKey`s with a given `CipherAlgorithm`.
+pub fn encrypt_to_many_with(algorithm: CipherAlgorithm, pks: &[PublicKey], hints: bool, plaintext: &[u8]) -> Result<Vec<u8>> {
+    if pks.is_empty() || pks.len() > u32::max_value() as usize {
+        return Err(ErrorKind::InvalidArgument.into());
+    }
+
+    let esk = SecretKey::random();
+    let epk = esk.to_public().to_bytes()?;
+    let key = Key::new();
+
+    let cipher_nonce = vec![0u8; algorithm.nonce_size()];
+
+    let mut header = Vec::new();
+    header.extend_from_slice(&epk);
+    header.push(algorithm.to_u8());
+    header.push(if hints { 1 } else { 0 });
+    header.write_u32::<BigEndian>(pks.len() as u32)?;
+
+    for pk in shuffle(pks)?.iter() {
+        pk.validate()?;
+
+        let pk_buf = pk.to_bytes()?;
+        let slot_key = sealed_key(Key::shared(esk.clone(), *pk)?, &epk, &pk_buf);
+
+        if hints {
+            header.extend_from_slice(&recipient_hint(pk)?);
+        }
+
+        // NB: the zero nonce is safe, as every slot key is used only once
+        let slot = Cipher::new(algorithm, slot_key)?
+            .encrypt(&cipher_nonce, &epk, key.expose_secret())?;
+        header.extend_from_slice(&slot);
+    }
+
+    let mut cyph = header.clone();
+    cyph.extend_from_slice(&sym_encrypt_with_cipher(algorithm, key, &header, plaintext)?);
+
+    Ok(cyph)
+}
+
+/// Decrypts a cyphertext produced by `encrypt_to_many`, finding the slot of the
+/// `SecretKey`. Fails with `ErrorKind::NotFound` if the `SecretKey` is not a recipient.
+pub fn decrypt_from_many(sk: SecretKey, cyph: &[u8]) -> Result<Vec<u8>> {
+    sk.validate()?;
+
+    if cyph.len() < 32 + 6 {
+        return Err(ErrorKind::InvalidLength.into());
+    }
+
+    let epk = &cyph[..32];
+    let algorithm = CipherAlgorithm::from_u8(cyph[32])?;
+    let hints = match cyph[33] {
+        0 => false,
+        1 => true,
+        _ => return Err(ErrorKind::InvalidFormat.into()),
+    };
+    let count = BigEndian::read_u32(&cyph[34..38]) as usize;
+
+    let hint_size = if hints { RECIPIENT_HINT_SIZE } else { 0 };
+    let slot_size = hint_size + SLOT_KEY_SIZE + algorithm.tag_size();
+
+    let slots_len = count.checked_mul(slot_size)
+        .ok_or(ErrorKind::InvalidLength)?;
+    if count == 0 || cyph.len() < 38 + slots_len {
+        return Err(ErrorKind::InvalidLength.into());
+    }
+
+    let (header, envelope) = cyph.split_at(38 + slots_len);
+
+    let pk = sk.to_public();
+    let pk_buf = pk.to_bytes()?;
+    let hint = recipient_hint(&pk)?;
+
+    let slot_key = sealed_key(Key::shared(sk, PublicKey::from_bytes(epk)?)?, epk, &pk_buf);
+    let cipher = Cipher::new(algorithm, slot_key)?;
+    let cipher_nonce = vec![0u8; algorithm.nonce_size()];
+
+    for slot in header[38..].chunks(slot_size) {
+        let (slot_hint, wrapped) = slot.split_at(hint_size);
+
+        if hints && slot_hint != &hint[..] {
+            continue;
+        }
+
+        if let Ok(mut key_buf) = cipher.decrypt(&cipher_nonce, epk, wrapped) {
+            let key = Key::from_bytes(&key_buf);
+            key_buf.wipe();
+            let key = key?;
+
+            return sym_decrypt_with_ad(key, header, envelope);
+        }
+    }
+
+    Err(ErrorKind::NotFound.into())
+}
+
+/// Returns the hint of a recipient, the prefix of its `PublicKey` fingerprint.
+fn recipient_hint(pk: &PublicKey) -> Result<Vec<u8>> {
+    let mut hint = pk.fingerprint()?.to_bytes()?;
+    hint.truncate(RECIPIENT_HINT_SIZE);
+
+    Ok(hint)
+}
+
+/// Shuffles the `PublicKey`s, so that the order of the slots does not depend on
+/// the order given by the caller.
+fn shuffle(pks: &[PublicKey]) -> Result<Vec<PublicKey>> {
+    let mut pks = pks.to_vec();
+
+    for i in (1..pks.len()).rev() {
+        let j = Random::u64_range(0..(i as u64 + 1))? as usize;
+        pks.swap(i, j);
+    }
+
+    Ok(pks)
+}
@@ -0,0 +1,285 @@
+// Copyright 2018 Yobicash Ltd.
+//
+// Licensed under the MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>
+// and the Apache 2.0 license <LICENSE-APACHE or https://opensource.org/licenses/Apache-2.0>.
+// This file may not be copied, modified, or distributed except according to those
+// terms.
+
+//! The `rotate` module provides the re-encryption of cyphertexts from a retired
+//! `Key` (or `SecretKey`) to a new one, without handing the plaintexts to the caller.
+//!
+//! The rotation of an `Envelope` is idempotent: a cyphertext that already opens
+//! with the new `Key` is left as it is, so an interrupted rotation can be run again
+//! over the same cyphertexts. The rotation of a stream records its progress in a
+//! `StreamCheckpoint`, from which an interrupted rotation is resumed.
+
+use rmp_serde::encode as encode_msgpk;
+use rmp_serde::decode as decode_msgpk;
+use hex;
+
+use error::ErrorKind;
+use result::Result;
+use traits::{Validate, BinarySerialize, HexSerialize};
+use wipe::Wipe;
+use encrypt::{SecretKey, PublicKey, Key};
+use envelope::Envelope;
+use stream::{StreamEncryptor, StreamDecryptor, STREAM_CHUNK_SIZE, stream_header_algorithm};
+
+use std::io::{Read, Write};
+use std::fmt;
+
+/// The outcome of the rotation of a cyphertext.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum RotationStatus {
+    /// The cyphertext has been re-encrypted with the new `Key`.
+    Rotated,
+    /// The cyphertext was already encrypted with the new `Key`.
+    AlreadyRotated,
+}
+
+/// The report of the rotation of a batch of cyphertexts.
+#[derive(Clone, Eq, PartialEq, Debug, Default)]
+pub struct RotationReport {
+    /// The indexes of the rotated cyphertexts.
+    pub rotated: Vec<usize>,
+    /// The indexes of the cyphertexts that were already rotated.
+    pub already_rotated: Vec<usize>,
+    /// The indexes of the cyphertexts that could not be rotated, with the reason.
+    pub failed: Vec<(usize, ErrorKind)>,
+}
+
+impl RotationReport {
+    /// Returns true if every cyphertext has been rotated.
+    pub fn is_complete(&self) -> bool {
+        self.failed.is_empty()
+    }
+}
+
+/// The progress of the rotation of a stream, to resume it after an interruption.
+///
+/// The chunks before `chunks` have been rotated: the input stream has been consumed
+/// up to `reader_offset`, and the output stream written up to `writer_offset`.
+#[derive(Clone, Eq, PartialEq, Debug, Default, Serialize, Deserialize)]
+pub struct StreamCheckpoint {
+    /// The header of the input stream.
+    pub old_header: Vec<u8>,
+    /// The header of the output stream.
+    pub new_header: Vec<u8>,
+    /// The number of rotated chunks.
+    pub chunks: u32,
+}
+
+impl StreamCheckpoint {
+    /// Returns the offset of the first chunk to rotate in the input stream.
+    pub fn reader_offset(&self) -> Result<u64> {
+        stream_offset(&self.old_header, self.chunks)
+    }
+
+    /// Returns the offset of the first chunk to write in the output stream.
+    pub fn writer_offset(&self) -> Result<u64> {
+        stream_offset(&self.new_header, self.chunks)
+    }
+}
+
+/// Returns the size in bytes of the header and of the first chunks of a stream.
+fn stream_offset(header: &[u8], chunks: u32) -> Result<u64> {
+    let algorithm = stream_header_algorithm(header)?;
+    let chunk_len = (STREAM_CHUNK_SIZE + algorithm.tag_size()) as u64;
+
+    Ok(header.len() as u64 + u64::from(chunks) * chunk_len)
+}
+
+impl Validate for StreamCheckpoint {
+    fn validate(&self) -> Result<()> {
+        stream_header_algorithm(&self.old_header)?;
+        stream_header_algorithm(&self.new_header)?;
+
+        Ok(())
+    }
+}
+
+impl BinarySerialize for StreamCheckpoint {
+    fn to_bytes(&self) -> Result<Vec<u8>> {
+        encode_msgpk::to_vec(self)
+            .map_err(|_| ErrorKind::SerializationFailure.into())
+    }
+
+    fn from_bytes(b: &[u8]) -> Result<StreamCheckpoint> {
+        let checkpoint: StreamCheckpoint = decode_msgpk::from_slice(b)
+            .map_err(|_| ErrorKind::DeserializationFailure)?;
+
+        checkpoint.validate()?;
+
+        Ok(checkpoint)
+    }
+}
+
+impl HexSerialize for StreamCheckpoint {
+    fn to_hex(&self) -> Result<String> {
+        Ok(hex::encode(&self.to_bytes()?))
+    }
+
+    fn from_hex(s: &str) -> Result<StreamCheckpoint> {
+        Self::from_bytes(&hex::decode(s)?)
+    }
+}
+
+impl fmt::Display for StreamCheckpoint {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:?}", self.to_hex().unwrap())
+    }
+}
+
+/// Rotation of cyphertexts from an old `Key` to a new `Key`.
+#[derive(Clone)]
+pub struct KeyRotation {
+    old_key: Key,
+    new_key: Key,
+}
+
+impl KeyRotation {
+    /// Creates a new `KeyRotation`.
+    pub fn new(old_key: Key, new_key: Key) -> Result<KeyRotation> {
+        if old_key == new_key {
+            return Err(ErrorKind::InvalidArgument.into());
+        }
+
+        Ok(KeyRotation {
+            old_key: old_key,
+            new_key: new_key,
+        })
+    }
+
+    /// Creates a new `KeyRotation` for the cyphertexts exchanged with a `PublicKey`
+    /// with `assym_encrypt`, moving them from an old to a new `SecretKey`.
+    pub fn assym(old_sk: SecretKey, new_sk: SecretKey, pk: PublicKey) -> Result<KeyRotation> {
+        let old_key = Key::shared(old_sk, pk)?;
+        let new_key = Key::shared(new_sk, pk)?;
+
+        KeyRotation::new(old_key, new_key)
+    }
+
+    /// Rotates a cyphertext produced by `sym_encrypt` or `assym_encrypt`.
+    pub fn rotate(&self, cyph: &[u8]) -> Result<Vec<u8>> {
+        self.rotate_with_ad(&[], cyph).map(|(_, cyph)| cyph)
+    }
+
+    /// Rotates a cyphertext produced by `sym_encrypt_with_ad`, keeping its
+    /// `CipherAlgorithm`. A cyphertext already encrypted with the new `Key`
+    /// is returned unchanged.
+    pub fn rotate_with_ad(&self, ad: &[u8], cyph: &[u8]) -> Result<(RotationStatus, Vec<u8>)> {
+        let envelope = Envelope::from_bytes(cyph)?;
+
+        let mut plain = match envelope.decrypt(self.old_key.clone(), ad) {
+            Ok(plain) => plain,
+            Err(e) => {
+                if e.kind() == ErrorKind::InvalidTag {
+                    if let Ok(mut plain) = envelope.decrypt(self.new_key.clone(), ad) {
+                        plain.wipe();
+                        return Ok((RotationStatus::AlreadyRotated, cyph.to_owned()));
+                    }
+                }
+
+                return Err(e);
+            },
+        };
+
+        let rotated = Envelope::encrypt_with(envelope.algorithm, self.new_key.clone(), ad, &plain);
+        plain.wipe();
+
+        Ok((RotationStatus::Rotated, rotated?.to_bytes()?))
+    }
+
+    /// Rotates in place a batch of cyphertexts produced by `sym_encrypt` or
+    /// `assym_encrypt`. The cyphertexts that could not be rotated are left
+    /// unchanged and reported with the reason.
+    pub fn rotate_batch(&self, cyphs: &mut [Vec<u8>]) -> RotationReport {
+        let mut report = RotationReport::default();
+
+        for (idx, cyph) in cyphs.iter_mut().enumerate() {
+            match self.rotate_with_ad(&[], cyph) {
+                Ok((RotationStatus::Rotated, rotated)) => {
+                    *cyph = rotated;
+                    report.rotated.push(idx);
+                },
+                Ok((RotationStatus::AlreadyRotated, _)) => {
+                    report.already_rotated.push(idx);
+                },
+                Err(e) => {
+                    report.failed.push((idx, e.kind()));
+                },
+            }
+        }
+
+        report
+    }
+
+    /// Rotates a stream produced by `StreamEncryptor`, one chunk at a time, keeping
+    /// its `CipherAlgorithm`. The output is rejected by the `StreamDecryptor` unless
+    /// the whole stream is rotated.
+    pub fn rotate_stream<R: Read, W: Write>(&self, reader: R, writer: W) -> Result<W> {
+        self.rotate_stream_with_checkpoints(reader, writer, |_, _| Ok(()))
+    }
+
+    /// Rotates a stream as `rotate_stream`, calling `on_checkpoint` with the flushed
+    /// writer and a `StreamCheckpoint` after the header and after every chunk but the
+    /// last one. Once the writer is synced, the `StreamCheckpoint` can be stored to
+    /// resume an interrupted rotation with `resume_stream`. An error returned by
+    /// `on_checkpoint` stops the rotation.
+    pub fn rotate_stream_with_checkpoints<R, W, F>(&self, reader: R, writer: W, on_checkpoint: F) -> Result<W>
+        where R: Read, W: Write, F: FnMut(&mut W, &StreamCheckpoint) -> Result<()>
+    {
+        let decryptor = StreamDecryptor::new(self.old_key.clone(), reader)?;
+        let encryptor = StreamEncryptor::with_cipher(decryptor.algorithm(), self.new_key.clone(), writer)?;
+
+        rotate_chunks(decryptor, encryptor, on_checkpoint)
+    }
+
+    /// Resumes an interrupted stream rotation from its last `StreamCheckpoint`. The
+    /// reader must start at the `reader_offset` of the input stream, and the writer
+    /// at the `writer_offset` of the output stream, discarding anything written after it.
+    pub fn resume_stream<R, W, F>(&self, checkpoint: &StreamCheckpoint, reader: R, writer: W, on_checkpoint: F) -> Result<W>
+        where R: Read, W: Write, F: FnMut(&mut W, &StreamCheckpoint) -> Result<()>
+    {
+        checkpoint.validate()?;
+
+        let decryptor = StreamDecryptor::resume(self.old_key.clone(), &checkpoint.old_header, checkpoint.chunks, reader)?;
+        let encryptor = StreamEncryptor::resume(self.new_key.clone(), &checkpoint.new_header, checkpoint.chunks, writer)?;
+
+        rotate_chunks(decryptor, encryptor, on_checkpoint)
+    }
+}
+
+/// Re-encrypts the remaining chunks of a stream, checkpointing before each one.
+fn rotate_chunks<R, W, F>(mut decryptor: StreamDecryptor<R>, mut encryptor: StreamEncryptor<W>, mut on_checkpoint: F) -> Result<W>
+    where R: Read, W: Write, F: FnMut(&mut W, &StreamCheckpoint) -> Result<()>
+{
+    let old_header = decryptor.header();
+    let new_header = encryptor.header();
+
+    loop {
+        let checkpoint = StreamCheckpoint {
+            old_header: old_header.clone(),
+            new_header: new_header.clone(),
+            chunks: encryptor.counter(),
+        };
+
+        encryptor.flush()?;
+        on_checkpoint(encryptor.get_mut(), &checkpoint)?;
+
+        // NB: a resumed chunk is encrypted again with the same nonce, and so
+        // written again with the same bytes
+        let (mut plain, last) = decryptor.next_chunk()?;
+        let res = encryptor.write_chunk(&plain, last);
+        plain.wipe();
+        res?;
+
+        if last {
+            break;
+        }
+    }
+
+    encryptor.flush()?;
+
+    Ok(encryptor.into_inner())
+}
@@ -12,11 +12,13 @@ use rand::thread_rng;
 use sha2::Sha512;
 use curve25519::scalar::Scalar as CurveScalar;
 use hex;
+use subtle::slices_equal;
 
 use error::ErrorKind;
 use result::Result;
 use traits::Validate;
 use traits::{BinarySerialize, HexSerialize};
+use wipe::{Wipe, wipe_value};
 
 use std::ops::{Add, Sub, Mul};
 use std::fmt;
@@ -55,6 +57,20 @@ impl Scalar {
         let _scalar = CurveScalar::hash_from_bytes::<Sha512>(message);
         Scalar(_scalar)
     }
+
+    /// Creates a scalar from 64 bytes, reduced modulo the group order.
+    pub fn from_bytes_wide(b: &[u8; 64]) -> Scalar {
+        Scalar(CurveScalar::from_bytes_mod_order_wide(b))
+    }
+
+    /// Returns the multiplicative inverse of the scalar.
+    pub fn invert(&self) -> Result<Scalar> {
+        if self.0 == CurveScalar::zero() {
+            return Err(ErrorKind::InvalidArgument.into());
+        }
+
+        Ok(Scalar(self.0.invert()))
+    }
 }
 
 impl Default for Scalar {
@@ -132,3 +148,86 @@ impl fmt::Display for Scalar {
         write!(f, "{:?}", self.to_hex().unwrap())
     }
 }
+
+/// A secret `Scalar`, like a private key or the instance of a zero-knowledge proof.
+///
+/// Unlike a `Scalar`, a `SecretScalar` is not `Copy`, is wiped on drop, is redacted
+/// when formatted and compared in constant time, and does not implement serde's
+/// traits. Its value is only accessible through `SecretScalar::expose_secret` and
+/// the `BinarySerialize` and `HexSerialize` conversions.
+#[derive(Clone, Default)]
+pub struct SecretScalar(Scalar);
+
+impl SecretScalar {
+    /// Creates a `SecretScalar` from a `Scalar`.
+    pub fn new(scalar: Scalar) -> SecretScalar {
+        SecretScalar(scalar)
+    }
+
+    /// Creates a random `SecretScalar`.
+    pub fn random() -> SecretScalar {
+        SecretScalar(Scalar::random())
+    }
+
+    /// Returns the underlying `Scalar`. The caller is responsible for not
+    /// leaking it.
+    pub fn expose_secret(&self) -> &Scalar {
+        &self.0
+    }
+}
+
+impl PartialEq for SecretScalar {
+    fn eq(&self, other: &SecretScalar) -> bool {
+        slices_equal((self.0).0.as_bytes(), (other.0).0.as_bytes()) == 1
+    }
+}
+
+impl Eq for SecretScalar {}
+
+impl Drop for SecretScalar {
+    fn drop(&mut self) {
+        wipe_value(&mut (self.0).0, CurveScalar::zero());
+    }
+}
+
+impl Validate for SecretScalar {
+    fn validate(&self) -> Result<()> {
+        self.0.validate()
+    }
+}
+
+impl BinarySerialize for SecretScalar {
+    fn to_bytes(&self) -> Result<Vec<u8>> {
+        self.0.to_bytes()
+    }
+
+    fn from_bytes(b: &[u8]) -> Result<SecretScalar> {
+        Ok(SecretScalar(Scalar::from_bytes(b)?))
+    }
+}
+
+impl HexSerialize for SecretScalar {
+    fn to_hex(&self) -> Result<String> {
+        Ok(hex::encode((self.0).0.as_bytes()))
+    }
+
+    fn from_hex(s: &str) -> Result<SecretScalar> {
+        let mut b = hex::decode(s)?;
+        let scalar = Self::from_bytes(&b);
+        b.wipe();
+
+        scalar
+    }
+}
+
+impl fmt::Debug for SecretScalar {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "SecretScalar(<redacted>)")
+    }
+}
+
+impl fmt::Display for SecretScalar {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "SecretScalar(<redacted>)")
+    }
+}
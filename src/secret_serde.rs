@@ -0,0 +1,111 @@
+// Copyright 2018 Yobicash Ltd.
+//
+// Licensed under the MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>
+// and the Apache 2.0 license <LICENSE-APACHE or https://opensource.org/licenses/Apache-2.0>.
+// This file may not be copied, modified, or distributed except according to those
+// terms.
+
+//! The `secret_serde` module provides the explicit serde (de)serialization of the
+//! secret types, which do not implement `Serialize` and `Deserialize`.
+//!
+//! A field holding a secret is serialized as the bytes of its `BinarySerialize`
+//! implementation only when annotated with `#[serde(with = "secret_serde")]`, or
+//! `#[serde(with = "secret_serde::map")]` for a `BTreeMap` of secrets.
+
+use serde::{Serialize, Serializer, Deserialize, Deserializer};
+use serde::de::{self, Visitor, SeqAccess};
+
+use traits::BinarySerialize;
+use wipe::Wipe;
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// Serializes a secret as the bytes of its `BinarySerialize` implementation.
+pub fn serialize<T, S>(secret: &T, serializer: S) -> ::std::result::Result<S::Ok, S::Error>
+    where T: BinarySerialize, S: Serializer
+{
+    let mut _buf = secret.to_bytes().map_err(|e| ::serde::ser::Error::custom(e))?;
+    let res = serializer.serialize_bytes(&_buf);
+    _buf.wipe();
+
+    res
+}
+
+/// Deserializes a secret from the bytes of its `BinarySerialize` implementation.
+pub fn deserialize<'de, T, D>(deserializer: D) -> ::std::result::Result<T, D::Error>
+    where T: BinarySerialize, D: Deserializer<'de>
+{
+    let mut _buf = deserializer.deserialize_bytes(BytesVisitor)?;
+    let res = T::from_bytes(&_buf).map_err(|e| de::Error::custom(e));
+    _buf.wipe();
+
+    res
+}
+
+/// Visitor of a byte buffer, encoded either as bytes or as a sequence.
+struct BytesVisitor;
+
+impl<'de> Visitor<'de> for BytesVisitor {
+    type Value = Vec<u8>;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("the bytes of a secret")
+    }
+
+    fn visit_bytes<E: de::Error>(self, v: &[u8]) -> ::std::result::Result<Vec<u8>, E> {
+        Ok(v.to_vec())
+    }
+
+    fn visit_byte_buf<E: de::Error>(self, v: Vec<u8>) -> ::std::result::Result<Vec<u8>, E> {
+        Ok(v)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> ::std::result::Result<Vec<u8>, A::Error> {
+        let mut buf = Vec::new();
+        while let Some(b) = seq.next_element()? {
+            buf.push(b);
+        }
+
+        Ok(buf)
+    }
+}
+
+/// A borrowed secret, serialized with `secret_serde::serialize`.
+struct SecretRef<'a, T: 'a>(&'a T);
+
+impl<'a, T: BinarySerialize> Serialize for SecretRef<'a, T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> ::std::result::Result<S::Ok, S::Error> {
+        serialize(self.0, serializer)
+    }
+}
+
+/// An owned secret, deserialized with `secret_serde::deserialize`.
+struct SecretOwned<T>(T);
+
+impl<'de, T: BinarySerialize> Deserialize<'de> for SecretOwned<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> ::std::result::Result<SecretOwned<T>, D::Error> {
+        deserialize(deserializer).map(SecretOwned)
+    }
+}
+
+/// The explicit serde (de)serialization of a `BTreeMap` of secrets.
+pub mod map {
+    use super::*;
+
+    /// Serializes a `BTreeMap` of secrets.
+    pub fn serialize<K, T, S>(map: &BTreeMap<K, T>, serializer: S) -> ::std::result::Result<S::Ok, S::Error>
+        where K: Serialize + Ord, T: BinarySerialize, S: Serializer
+    {
+        serializer.collect_map(map.iter().map(|(k, v)| (k, SecretRef(v))))
+    }
+
+    /// Deserializes a `BTreeMap` of secrets.
+    pub fn deserialize<'de, K, T, D>(deserializer: D) -> ::std::result::Result<BTreeMap<K, T>, D::Error>
+        where K: Deserialize<'de> + Ord, T: BinarySerialize, D: Deserializer<'de>
+    {
+        let map: BTreeMap<K, SecretOwned<T>> = BTreeMap::deserialize(deserializer)?;
+
+        Ok(map.into_iter().map(|(k, v)| (k, v.0)).collect())
+    }
+}
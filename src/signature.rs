@@ -0,0 +1,345 @@
+// Copyright 2018 Yobicash Ltd.
+//
+// Licensed under the MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>
+// and the Apache 2.0 license <LICENSE-APACHE or https://opensource.org/licenses/Apache-2.0>.
+// This file may not be copied, modified, or distributed except according to those
+// terms.
+
+//! The `signature` module provides the Ed25519, Ed25519ctx and Ed25519ph signatures
+//! ([RFC 8032](https://tools.ietf.org/html/rfc8032)) of `SecretKey` and `PublicKey`.
+//!
+//! A `SecretKey` is a scalar, while an RFC 8032 secret key is a seed from which
+//! the scalar and a nonce prefix are hashed. `SigningKey::from_seed` creates the
+//! RFC 8032 keys, and signs exactly like any other implementation; `SecretKey::sign`
+//! derives the nonce prefix from the scalar instead. Both signatures are verified
+//! by any RFC 8032 verifier.
+//!
+//! The verification is strict: it rejects non-canonical encodings of `S` and `R`,
+//! and `R` and `A` points of small order, so that a signature cannot be malleated.
+
+use digest::Digest;
+use sha2::Sha512;
+use curve25519::scalar::Scalar as CurveScalar;
+use curve25519::edwards::CompressedEdwardsY;
+use hex;
+
+use error::ErrorKind;
+use result::Result;
+use traits::{BinarySerialize, HexSerialize};
+use wipe::Wipe;
+use scalar::{Scalar, SecretScalar};
+use point::Point;
+use encrypt::{SecretKey, PublicKey};
+
+use std::fmt;
+
+/// The size in bytes of a `Signature`.
+pub const SIGNATURE_SIZE: usize = 64;
+
+/// The maximum size in bytes of an Ed25519ctx or Ed25519ph context.
+pub const SIGNATURE_CONTEXT_MAX_SIZE: usize = 255;
+
+/// The prefix of the domain separation of Ed25519ctx and Ed25519ph (RFC 8032, section 5.1).
+const DOM2_PREFIX: &[u8] = b"SigEd25519 no Ed25519 collisions";
+
+/// The label of the nonce prefix derived from a `SecretKey`.
+const NONCE_PREFIX_LABEL: &[u8] = b"yobicrypto-ed25519-nonce-prefix";
+
+/// An RFC 8032 signature.
+#[derive(Copy, Clone, Eq, PartialEq, Default, Debug, Serialize, Deserialize)]
+pub struct Signature {
+    /// The encoded point `R`.
+    pub r: [u8; 32],
+    /// The encoded scalar `S`.
+    pub s: [u8; 32],
+}
+
+impl BinarySerialize for Signature {
+    fn to_bytes(&self) -> Result<Vec<u8>> {
+        let mut buf = self.r.to_vec();
+        buf.extend_from_slice(&self.s);
+
+        Ok(buf)
+    }
+
+    fn from_bytes(b: &[u8]) -> Result<Signature> {
+        if b.len() != SIGNATURE_SIZE {
+            return Err(ErrorKind::InvalidLength.into());
+        }
+
+        let mut sig = Signature::default();
+        sig.r.copy_from_slice(&b[..32]);
+        sig.s.copy_from_slice(&b[32..]);
+
+        Ok(sig)
+    }
+}
+
+impl HexSerialize for Signature {
+    fn to_hex(&self) -> Result<String> {
+        Ok(hex::encode(&self.to_bytes()?))
+    }
+
+    fn from_hex(s: &str) -> Result<Signature> {
+        Self::from_bytes(&hex::decode(s)?)
+    }
+}
+
+impl fmt::Display for Signature {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:?}", self.to_hex().unwrap())
+    }
+}
+
+/// The variant of an RFC 8032 signature, with its context.
+#[derive(Copy, Clone)]
+enum Variant<'a> {
+    Pure,
+    Ctx(&'a [u8]),
+    Ph(&'a [u8]),
+}
+
+impl<'a> Variant<'a> {
+    /// Returns the domain separation `dom2(F, C)`, empty for Ed25519.
+    fn dom(&self) -> Result<Vec<u8>> {
+        let (flag, ctx) = match *self {
+            Variant::Pure => return Ok(Vec::new()),
+            Variant::Ctx(ctx) => {
+                // NB: RFC 8032 forbids Ed25519ctx with an empty context
+                if ctx.is_empty() {
+                    return Err(ErrorKind::InvalidArgument.into());
+                }
+
+                (0u8, ctx)
+            },
+            Variant::Ph(ctx) => (1u8, ctx),
+        };
+
+        if ctx.len() > SIGNATURE_CONTEXT_MAX_SIZE {
+            return Err(ErrorKind::InvalidLength.into());
+        }
+
+        let mut dom = DOM2_PREFIX.to_vec();
+        dom.push(flag);
+        dom.push(ctx.len() as u8);
+        dom.extend_from_slice(ctx);
+
+        Ok(dom)
+    }
+
+    /// Returns the signed message, which is the SHA512 of the message for Ed25519ph.
+    fn message(&self, msg: &[u8]) -> Vec<u8> {
+        match *self {
+            Variant::Ph(_) => Sha512::digest(msg).to_vec(),
+            _ => msg.to_owned(),
+        }
+    }
+}
+
+/// A key signing RFC 8032 signatures: the secret scalar, its `PublicKey`
+/// and the nonce prefix.
+#[derive(Clone)]
+pub struct SigningKey {
+    sk: SecretKey,
+    pk: PublicKey,
+    prefix: [u8; 32],
+}
+
+impl SigningKey {
+    /// Creates a `SigningKey` from a 32 bytes RFC 8032 secret key.
+    pub fn from_seed(seed: &[u8]) -> Result<SigningKey> {
+        if seed.len() != 32 {
+            return Err(ErrorKind::InvalidLength.into());
+        }
+
+        let mut h = [0u8; 64];
+        h.copy_from_slice(Sha512::digest(seed).as_slice());
+
+        h[0] &= 248;
+        h[31] &= 127;
+        h[31] |= 64;
+
+        let mut _s = [0u8; 32];
+        _s.copy_from_slice(&h[..32]);
+
+        // NB: the clamped scalar is reduced, which leaves its multiples of the basepoint unchanged
+        let mut _sk = CurveScalar::from_bytes_mod_order(_s).to_bytes();
+        let sk = SecretKey::from_bytes(&_sk);
+
+        let mut prefix = [0u8; 32];
+        prefix.copy_from_slice(&h[32..]);
+
+        _s.wipe();
+        _sk.wipe();
+        h.wipe();
+
+        let sk = sk?;
+        let pk = sk.to_public();
+
+        Ok(SigningKey {
+            sk: sk,
+            pk: pk,
+            prefix: prefix,
+        })
+    }
+
+    /// Creates a `SigningKey` from a `SecretKey`, deriving the nonce prefix from it.
+    pub fn from_secret_key(sk: SecretKey) -> SigningKey {
+        let mut hasher = Sha512::default();
+        hasher.input(NONCE_PREFIX_LABEL);
+        hasher.input(sk.expose_secret());
+        let mut _h = hasher.result();
+
+        let mut prefix = [0u8; 32];
+        prefix.copy_from_slice(&_h[..32]);
+        _h.as_mut_slice().wipe();
+
+        let pk = sk.to_public();
+
+        SigningKey {
+            sk: sk,
+            pk: pk,
+            prefix: prefix,
+        }
+    }
+
+    /// Returns the `SecretKey`.
+    pub fn secret_key(&self) -> &SecretKey {
+        &self.sk
+    }
+
+    /// Returns the `PublicKey`.
+    pub fn public_key(&self) -> PublicKey {
+        self.pk
+    }
+
+    /// Signs a message with Ed25519.
+    pub fn sign(&self, msg: &[u8]) -> Result<Signature> {
+        self.sign_variant(Variant::Pure, msg)
+    }
+
+    /// Signs a message with Ed25519ctx, with a context of 1 to 255 bytes.
+    pub fn sign_ctx(&self, ctx: &[u8], msg: &[u8]) -> Result<Signature> {
+        self.sign_variant(Variant::Ctx(ctx), msg)
+    }
+
+    /// Signs the SHA512 of a message with Ed25519ph, with a context of up to 255 bytes.
+    pub fn sign_ph(&self, ctx: &[u8], msg: &[u8]) -> Result<Signature> {
+        self.sign_variant(Variant::Ph(ctx), msg)
+    }
+
+    fn sign_variant(&self, variant: Variant, msg: &[u8]) -> Result<Signature> {
+        let dom = variant.dom()?;
+        let msg = variant.message(msg);
+        let a = SecretScalar::from_bytes(self.sk.expose_secret())?;
+        let pk = self.pk.to_bytes()?;
+
+        let mut hasher = Sha512::default();
+        hasher.input(&dom);
+        hasher.input(&self.prefix);
+        hasher.input(&msg);
+        let r = SecretScalar::new(Scalar(CurveScalar::from_hash(hasher)));
+
+        let mut sig = Signature::default();
+        sig.r.copy_from_slice(&(&Point::default() * r.expose_secret()).to_bytes()?);
+
+        let k = challenge(&dom, &sig.r, &pk, &msg);
+        let _ka = SecretScalar::new(&k * a.expose_secret());
+        let s = r.expose_secret() + _ka.expose_secret();
+        sig.s.copy_from_slice(&s.to_bytes()?);
+
+        Ok(sig)
+    }
+}
+
+impl Drop for SigningKey {
+    fn drop(&mut self) {
+        self.prefix.wipe();
+    }
+}
+
+impl fmt::Debug for SigningKey {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "SigningKey(<redacted>)")
+    }
+}
+
+impl SecretKey {
+    /// Signs a message with Ed25519.
+    pub fn sign(&self, msg: &[u8]) -> Result<Signature> {
+        SigningKey::from_secret_key(self.clone()).sign(msg)
+    }
+
+    /// Signs a message with Ed25519ctx, with a context of 1 to 255 bytes.
+    pub fn sign_ctx(&self, ctx: &[u8], msg: &[u8]) -> Result<Signature> {
+        SigningKey::from_secret_key(self.clone()).sign_ctx(ctx, msg)
+    }
+
+    /// Signs the SHA512 of a message with Ed25519ph, with a context of up to 255 bytes.
+    pub fn sign_ph(&self, ctx: &[u8], msg: &[u8]) -> Result<Signature> {
+        SigningKey::from_secret_key(self.clone()).sign_ph(ctx, msg)
+    }
+}
+
+impl PublicKey {
+    /// Verifies an Ed25519 signature of a message.
+    pub fn verify(&self, msg: &[u8], sig: &Signature) -> Result<()> {
+        self.verify_variant(Variant::Pure, msg, sig)
+    }
+
+    /// Verifies an Ed25519ctx signature of a message.
+    pub fn verify_ctx(&self, ctx: &[u8], msg: &[u8], sig: &Signature) -> Result<()> {
+        self.verify_variant(Variant::Ctx(ctx), msg, sig)
+    }
+
+    /// Verifies an Ed25519ph signature of a message.
+    pub fn verify_ph(&self, ctx: &[u8], msg: &[u8], sig: &Signature) -> Result<()> {
+        self.verify_variant(Variant::Ph(ctx), msg, sig)
+    }
+
+    fn verify_variant(&self, variant: Variant, msg: &[u8], sig: &Signature) -> Result<()> {
+        let dom = variant.dom()?;
+        let msg = variant.message(msg);
+        let pk = self.to_bytes()?;
+
+        let a = Point::from_bytes(&pk)?;
+        if a.0.is_small_order() {
+            return Err(ErrorKind::InvalidSignature.into());
+        }
+
+        let s = match CurveScalar::from_canonical_bytes(sig.s) {
+            Some(s) => Scalar(s),
+            None => return Err(ErrorKind::InvalidSignature.into()),
+        };
+
+        let r = match CompressedEdwardsY(sig.r).decompress() {
+            Some(r) => Point(r),
+            None => return Err(ErrorKind::InvalidSignature.into()),
+        };
+
+        if r.0.compress().to_bytes() != sig.r || r.0.is_small_order() {
+            return Err(ErrorKind::InvalidSignature.into());
+        }
+
+        let k = challenge(&dom, &sig.r, &pk, &msg);
+
+        // NB: the cofactorless equation [S]B = R + [k]A, checked on the encodings
+        let expected = &(&Point::default() * &s) - &(&a * &k);
+        if expected.0.compress().to_bytes() != sig.r {
+            return Err(ErrorKind::InvalidSignature.into());
+        }
+
+        Ok(())
+    }
+}
+
+/// Computes the challenge `k = SHA512(dom || R || A || M)`.
+fn challenge(dom: &[u8], r: &[u8], pk: &[u8], msg: &[u8]) -> Scalar {
+    let mut hasher = Sha512::default();
+    hasher.input(dom);
+    hasher.input(r);
+    hasher.input(pk);
+    hasher.input(msg);
+
+    Scalar(CurveScalar::from_hash(hasher))
+}
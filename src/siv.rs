@@ -0,0 +1,249 @@
+// Copyright 2018 Yobicash Ltd.
+//
+// Licensed under the MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>
+// and the Apache 2.0 license <LICENSE-APACHE or https://opensource.org/licenses/Apache-2.0>.
+// This file may not be copied, modified, or distributed except according to those
+// terms.
+
+//! The `siv` module provides the AES256-GCM-SIV nonce misuse-resistant authenticated
+//! encryption mode ([RFC 8452](https://tools.ietf.org/html/rfc8452)), and the
+//! deterministic encryption built on it.
+//!
+//! Reusing a nonce with AES256-GCM-SIV only leaks whether two messages, with their
+//! associated data, are equal. The deterministic encryption uses a fixed nonce, so
+//! that identical inputs give identical cyphertexts.
+
+use byteorder::{LittleEndian, ByteOrder};
+use subtle::slices_equal;
+
+use error::ErrorKind;
+use result::Result;
+use traits::BinarySerialize;
+use encrypt::Key;
+use cipher::AEADCipher;
+use aes::{BlockCipher, AES256};
+use gcm::{GHash, block_to_words, words_to_block};
+use wipe::Wipe;
+
+/// The size in bytes of an AES256-GCM-SIV nonce.
+pub const GCM_SIV_NONCE_SIZE: usize = 12;
+
+/// The size in bytes of an AES256-GCM-SIV authentication tag.
+pub const GCM_SIV_TAG_SIZE: usize = 16;
+
+/// The maximum size in bytes of the plaintext and of the associated data.
+const GCM_SIV_MAX_SIZE: u64 = 1 << 36;
+
+/// The AES256-GCM-SIV authenticated cipher.
+#[derive(Clone)]
+pub struct AES256GCMSIV {
+    /// The expanded key-generating key.
+    aes: AES256,
+}
+
+impl AEADCipher for AES256GCMSIV {
+    const NONCE_SIZE: usize = GCM_SIV_NONCE_SIZE;
+
+    const TAG_SIZE: usize = GCM_SIV_TAG_SIZE;
+
+    fn new(key: Key) -> Result<AES256GCMSIV> {
+        Ok(AES256GCMSIV {
+            aes: AES256::new(&key)?,
+        })
+    }
+
+    fn encrypt(&self, nonce: &[u8], ad: &[u8], plain: &[u8]) -> Result<Vec<u8>> {
+        check_sizes(nonce, ad, plain)?;
+
+        let (enc_aes, mut auth_key) = self.derive_keys(nonce)?;
+
+        let tag = tag(&enc_aes, &auth_key, nonce, ad, plain)?;
+        auth_key.wipe();
+
+        let mut cyph = plain.to_owned();
+        ctr(&enc_aes, &tag, &mut cyph)?;
+        cyph.extend_from_slice(&tag);
+
+        Ok(cyph)
+    }
+
+    fn decrypt(&self, nonce: &[u8], ad: &[u8], cyph: &[u8]) -> Result<Vec<u8>> {
+        let len = cyph.len();
+        if len < GCM_SIV_TAG_SIZE {
+            return Err(ErrorKind::InvalidLength.into());
+        }
+
+        let (cyph, tag) = cyph.split_at(len - GCM_SIV_TAG_SIZE);
+
+        check_sizes(nonce, ad, cyph)?;
+
+        let (enc_aes, mut auth_key) = self.derive_keys(nonce)?;
+
+        let mut _tag = [0u8; 16];
+        _tag.copy_from_slice(tag);
+
+        let mut plain = cyph.to_owned();
+        ctr(&enc_aes, &_tag, &mut plain)?;
+
+        let valid = slices_equal(&self::tag(&enc_aes, &auth_key, nonce, ad, &plain)?, tag);
+        auth_key.wipe();
+
+        if valid != 1 {
+            plain.wipe();
+            return Err(ErrorKind::InvalidTag.into());
+        }
+
+        Ok(plain)
+    }
+}
+
+impl AES256GCMSIV {
+    /// Derives the per-nonce encryption key and authentication key.
+    fn derive_keys(&self, nonce: &[u8]) -> Result<(AES256, [u8; 16])> {
+        let mut output = [0u8; 6 * 16];
+        for (i, block) in output.chunks_mut(16).enumerate() {
+            LittleEndian::write_u32(&mut block[..4], i as u32);
+            block[4..].copy_from_slice(nonce);
+        }
+
+        self.aes.encrypt_blocks(&mut output)?;
+
+        let mut auth_key = [0u8; 16];
+        let mut enc_key = [0u8; 32];
+        for (i, block) in output.chunks(16).enumerate() {
+            if i < 2 {
+                auth_key[i*8..(i+1)*8].copy_from_slice(&block[..8]);
+            } else {
+                enc_key[(i-2)*8..(i-1)*8].copy_from_slice(&block[..8]);
+            }
+        }
+
+        let enc_aes = AES256::new(&Key::from_bytes(&enc_key)?)?;
+
+        output.wipe();
+        enc_key.wipe();
+
+        Ok((enc_aes, auth_key))
+    }
+}
+
+/// Encrypts a plaintext deterministically with AES256-GCM-SIV, authenticating it
+/// together with the associated data. Identical inputs give identical cyphertexts,
+/// which is the only information leaked.
+pub fn encrypt_deterministic(key: Key, ad: &[u8], plaintext: &[u8]) -> Result<Vec<u8>> {
+    AES256GCMSIV::new(key)?.encrypt(&[0u8; GCM_SIV_NONCE_SIZE], ad, plaintext)
+}
+
+/// Decrypts a cyphertext produced by `encrypt_deterministic`.
+pub fn decrypt_deterministic(key: Key, ad: &[u8], cyph: &[u8]) -> Result<Vec<u8>> {
+    AES256GCMSIV::new(key)?.decrypt(&[0u8; GCM_SIV_NONCE_SIZE], ad, cyph)
+}
+
+fn check_sizes(nonce: &[u8], ad: &[u8], plain: &[u8]) -> Result<()> {
+    if nonce.len() != GCM_SIV_NONCE_SIZE {
+        return Err(ErrorKind::InvalidLength.into());
+    }
+
+    if ad.len() as u64 > GCM_SIV_MAX_SIZE || plain.len() as u64 > GCM_SIV_MAX_SIZE {
+        return Err(ErrorKind::InvalidLength.into());
+    }
+
+    Ok(())
+}
+
+/// Computes the tag of a plaintext and its associated data.
+fn tag(enc_aes: &AES256, auth_key: &[u8; 16], nonce: &[u8], ad: &[u8], plain: &[u8]) -> Result<[u8; 16]> {
+    let mut polyval = Polyval::new(auth_key);
+    polyval.update(ad);
+    polyval.update(plain);
+
+    let mut lengths = [0u8; 16];
+    LittleEndian::write_u64(&mut lengths[..8], (ad.len() as u64) * 8);
+    LittleEndian::write_u64(&mut lengths[8..], (plain.len() as u64) * 8);
+    polyval.update(&lengths);
+
+    let mut s = polyval.finalize();
+    for i in 0..GCM_SIV_NONCE_SIZE {
+        s[i] ^= nonce[i];
+    }
+    s[15] &= 0x7f;
+
+    enc_aes.encrypt_blocks(&mut s)?;
+
+    Ok(s)
+}
+
+/// XORs `buf` with the keystream starting at the tag with the most significant bit set,
+/// with a little-endian 32-bit counter.
+fn ctr(enc_aes: &AES256, tag: &[u8; 16], buf: &mut [u8]) -> Result<()> {
+    let blocks = (buf.len() + 15) / 16;
+    if blocks == 0 {
+        return Ok(());
+    }
+
+    let mut stream = vec![0u8; blocks * 16];
+    let mut counter = LittleEndian::read_u32(&tag[..4]);
+
+    for block in stream.chunks_mut(16) {
+        LittleEndian::write_u32(&mut block[..4], counter);
+        block[4..].copy_from_slice(&tag[4..]);
+        block[15] |= 0x80;
+        counter = counter.wrapping_add(1);
+    }
+
+    enc_aes.encrypt_blocks(&mut stream)?;
+
+    for (b, s) in buf.iter_mut().zip(stream.iter()) {
+        *b ^= *s;
+    }
+
+    stream.wipe();
+
+    Ok(())
+}
+
+/// The POLYVAL universal hash, computed with GHASH on byte-reversed blocks
+/// (RFC 8452, appendix A).
+struct Polyval {
+    ghash: GHash,
+}
+
+impl Polyval {
+    fn new(h: &[u8; 16]) -> Polyval {
+        let mut _h = *h;
+        _h.reverse();
+        let ghash = GHash::new(mul_x(block_to_words(&_h)));
+        _h.wipe();
+
+        Polyval {
+            ghash: ghash,
+        }
+    }
+
+    /// Absorbs `data`, zero-padding the last partial block.
+    fn update(&mut self, data: &[u8]) {
+        for chunk in data.chunks(16) {
+            let mut block = [0u8; 16];
+            block[..chunk.len()].copy_from_slice(chunk);
+            block.reverse();
+
+            self.ghash.update(&block);
+        }
+    }
+
+    fn finalize(&self) -> [u8; 16] {
+        let mut s = words_to_block(&self.ghash.finalize());
+        s.reverse();
+        s
+    }
+}
+
+/// Multiplies an element of GF(2^128) by x in the GCM bit order.
+fn mul_x(v: [u64; 2]) -> [u64; 2] {
+    let lsb = v[1] & 1;
+
+    let mut r = [0u64; 2];
+    r[1] = (v[1] >> 1) | (v[0] << 63);
+    r[0] = (v[0] >> 1) ^ (0xe100_0000_0000_0000 & 0u64.wrapping_sub(lsb));
+    r
+}
@@ -0,0 +1,347 @@
+// Copyright 2018 Yobicash Ltd.
+//
+// Licensed under the MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>
+// and the Apache 2.0 license <LICENSE-APACHE or https://opensource.org/licenses/Apache-2.0>.
+// This file may not be copied, modified, or distributed except according to those
+// terms.
+
+//! The `stream` module provides online segmented encryption with the
+//! [STREAM](https://eprint.iacr.org/2015/189.pdf) construction over any
+//! `CipherAlgorithm`.
+//!
+//! The stream starts with the identifier of the `CipherAlgorithm` and a random
+//! nonce prefix, followed by chunks of `STREAM_CHUNK_SIZE` plaintext bytes, each
+//! one sealed with the nonce `prefix || counter || last_flag`. Truncated, reordered
+//! or duplicated chunks fail the authentication.
+
+use byteorder::{BigEndian, ByteOrder};
+
+use error::ErrorKind;
+use result::Result;
+use random::Random;
+use encrypt::{SecretKey, PublicKey, Key};
+use cipher::{CipherAlgorithm, Cipher};
+
+use std::io::{self, Read, Write};
+
+/// The size in bytes of the plaintext of a stream chunk.
+pub const STREAM_CHUNK_SIZE: usize = 65536;
+
+/// The size in bytes of the chunk counter and last flag ending a chunk nonce.
+pub const STREAM_NONCE_SUFFIX_SIZE: usize = 5;
+
+/// Returns the size in bytes of the header of a stream encrypted with a
+/// `CipherAlgorithm`: the cipher identifier and the nonce prefix.
+pub fn stream_header_size(algorithm: CipherAlgorithm) -> usize {
+    1 + algorithm.nonce_size() - STREAM_NONCE_SUFFIX_SIZE
+}
+
+/// Returns the `CipherAlgorithm` of a stream header, checking its size.
+pub(crate) fn stream_header_algorithm(header: &[u8]) -> Result<CipherAlgorithm> {
+    if header.is_empty() {
+        return Err(ErrorKind::InvalidLength.into());
+    }
+
+    let algorithm = CipherAlgorithm::from_u8(header[0])?;
+
+    if header.len() != stream_header_size(algorithm) {
+        return Err(ErrorKind::InvalidLength.into());
+    }
+
+    Ok(algorithm)
+}
+
+/// Builds the nonce of a chunk.
+fn chunk_nonce(prefix: &[u8], counter: u32, last: bool) -> Vec<u8> {
+    let mut nonce = prefix.to_vec();
+    nonce.extend_from_slice(&[0u8; STREAM_NONCE_SUFFIX_SIZE]);
+    let len = nonce.len();
+    BigEndian::write_u32(&mut nonce[len-STREAM_NONCE_SUFFIX_SIZE..len-1], counter);
+    nonce[len-1] = if last { 1 } else { 0 };
+    nonce
+}
+
+/// Encryptor writing a STREAM-encrypted payload to an inner writer.
+pub struct StreamEncryptor<W: Write> {
+    cipher: Cipher,
+    prefix: Vec<u8>,
+    counter: u32,
+    buffer: Vec<u8>,
+    writer: W,
+}
+
+impl<W: Write> StreamEncryptor<W> {
+    /// Creates a new `StreamEncryptor` with the default `CipherAlgorithm` and
+    /// writes the stream header.
+    pub fn new(key: Key, writer: W) -> Result<StreamEncryptor<W>> {
+        StreamEncryptor::with_cipher(CipherAlgorithm::default(), key, writer)
+    }
+
+    /// Creates a new `StreamEncryptor` with a given `CipherAlgorithm` and
+    /// writes the stream header.
+    pub fn with_cipher(algorithm: CipherAlgorithm, key: Key, mut writer: W) -> Result<StreamEncryptor<W>> {
+        let prefix_len = stream_header_size(algorithm) - 1;
+        let prefix = Random::bytes(prefix_len as u32);
+        writer.write_all(&[algorithm.to_u8()])?;
+        writer.write_all(&prefix)?;
+
+        Ok(StreamEncryptor {
+            cipher: Cipher::new(algorithm, key)?,
+            prefix: prefix,
+            counter: 0,
+            buffer: Vec::new(),
+            writer: writer,
+        })
+    }
+
+    /// Creates a new `StreamEncryptor` generating the key with x25519.
+    pub fn assym(sk: SecretKey, pk: PublicKey, writer: W) -> Result<StreamEncryptor<W>> {
+        StreamEncryptor::assym_with_cipher(CipherAlgorithm::default(), sk, pk, writer)
+    }
+
+    /// Creates a new `StreamEncryptor` with a given `CipherAlgorithm`, generating
+    /// the key with x25519.
+    pub fn assym_with_cipher(algorithm: CipherAlgorithm, sk: SecretKey, pk: PublicKey, writer: W) -> Result<StreamEncryptor<W>> {
+        let key = Key::shared(sk, pk)?;
+
+        StreamEncryptor::with_cipher(algorithm, key, writer)
+    }
+
+    /// Creates a `StreamEncryptor` continuing a stream of which the header and
+    /// the first `counter` chunks have already been written.
+    pub(crate) fn resume(key: Key, header: &[u8], counter: u32, writer: W) -> Result<StreamEncryptor<W>> {
+        let algorithm = stream_header_algorithm(header)?;
+
+        Ok(StreamEncryptor {
+            cipher: Cipher::new(algorithm, key)?,
+            prefix: header[1..].to_vec(),
+            counter: counter,
+            buffer: Vec::new(),
+            writer: writer,
+        })
+    }
+
+    /// Returns the `CipherAlgorithm` of the stream.
+    pub fn algorithm(&self) -> CipherAlgorithm {
+        self.cipher.algorithm()
+    }
+
+    /// Returns the header of the stream.
+    pub(crate) fn header(&self) -> Vec<u8> {
+        let mut header = vec![self.algorithm().to_u8()];
+        header.extend_from_slice(&self.prefix);
+        header
+    }
+
+    /// Returns the number of chunks written, excluding the last one.
+    pub(crate) fn counter(&self) -> u32 {
+        self.counter
+    }
+
+    /// Returns a mutable reference to the inner writer.
+    pub(crate) fn get_mut(&mut self) -> &mut W {
+        &mut self.writer
+    }
+
+    /// Returns the inner writer, without writing the buffered data.
+    pub(crate) fn into_inner(self) -> W {
+        self.writer
+    }
+
+    /// Encrypts and writes a chunk.
+    pub(crate) fn write_chunk(&mut self, plain: &[u8], last: bool) -> Result<()> {
+        let nonce = chunk_nonce(&self.prefix, self.counter, last);
+        let cyph = self.cipher.encrypt(&nonce, &[], plain)?;
+        self.writer.write_all(&cyph)?;
+
+        if !last {
+            self.counter = self.counter.checked_add(1)
+                .ok_or(ErrorKind::OutOfBound)?;
+        }
+
+        Ok(())
+    }
+
+    /// Writes the last chunk and returns the inner writer. A stream that is
+    /// not finished will be rejected by the `StreamDecryptor`.
+    pub fn finish(mut self) -> Result<W> {
+        let plain = self.buffer.split_off(0);
+        self.write_chunk(&plain, true)?;
+        self.writer.flush()?;
+
+        Ok(self.writer)
+    }
+}
+
+impl<W: Write> Write for StreamEncryptor<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.buffer.extend_from_slice(buf);
+
+        // NB: a full chunk is kept in the buffer, as it may be the last one
+        while self.buffer.len() > STREAM_CHUNK_SIZE {
+            let rest = self.buffer.split_off(STREAM_CHUNK_SIZE);
+            let plain = ::std::mem::replace(&mut self.buffer, rest);
+            self.write_chunk(&plain, false)
+                .map_err(|e| io::Error::new(io::ErrorKind::Other, format!("{}", e)))?;
+        }
+
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+}
+
+/// Decryptor reading a STREAM-encrypted payload from an inner reader.
+pub struct StreamDecryptor<R: Read> {
+    cipher: Cipher,
+    prefix: Vec<u8>,
+    counter: u32,
+    buffer: Vec<u8>,
+    plain: Vec<u8>,
+    position: usize,
+    finished: bool,
+    reader: R,
+}
+
+impl<R: Read> StreamDecryptor<R> {
+    /// Creates a new `StreamDecryptor` and reads the stream header, which
+    /// selects the `CipherAlgorithm`.
+    pub fn new(key: Key, mut reader: R) -> Result<StreamDecryptor<R>> {
+        let mut id = [0u8; 1];
+        reader.read_exact(&mut id)?;
+        let algorithm = CipherAlgorithm::from_u8(id[0])?;
+
+        let mut prefix = vec![0u8; stream_header_size(algorithm) - 1];
+        reader.read_exact(&mut prefix)?;
+
+        Ok(StreamDecryptor {
+            cipher: Cipher::new(algorithm, key)?,
+            prefix: prefix,
+            counter: 0,
+            buffer: Vec::new(),
+            plain: Vec::new(),
+            position: 0,
+            finished: false,
+            reader: reader,
+        })
+    }
+
+    /// Creates a `StreamDecryptor` continuing a stream of which the header and
+    /// the first `counter` chunks have already been read.
+    pub(crate) fn resume(key: Key, header: &[u8], counter: u32, reader: R) -> Result<StreamDecryptor<R>> {
+        let algorithm = stream_header_algorithm(header)?;
+
+        Ok(StreamDecryptor {
+            cipher: Cipher::new(algorithm, key)?,
+            prefix: header[1..].to_vec(),
+            counter: counter,
+            buffer: Vec::new(),
+            plain: Vec::new(),
+            position: 0,
+            finished: false,
+            reader: reader,
+        })
+    }
+
+    /// Creates a new `StreamDecryptor` generating the key with x25519.
+    pub fn assym(sk: SecretKey, pk: PublicKey, reader: R) -> Result<StreamDecryptor<R>> {
+        let key = Key::shared(sk, pk)?;
+
+        StreamDecryptor::new(key, reader)
+    }
+
+    /// Returns the `CipherAlgorithm` of the stream.
+    pub fn algorithm(&self) -> CipherAlgorithm {
+        self.cipher.algorithm()
+    }
+
+    /// Returns the header of the stream.
+    pub(crate) fn header(&self) -> Vec<u8> {
+        let mut header = vec![self.algorithm().to_u8()];
+        header.extend_from_slice(&self.prefix);
+        header
+    }
+
+    /// Reads and decrypts the next chunk, returning it with its last flag.
+    pub(crate) fn next_chunk(&mut self) -> Result<(Vec<u8>, bool)> {
+        if self.finished {
+            return Err(ErrorKind::OutOfBound.into());
+        }
+
+        self.read_chunk()?;
+
+        let plain = ::std::mem::replace(&mut self.plain, Vec::new());
+        self.position = 0;
+
+        Ok((plain, self.finished))
+    }
+
+    /// Returns the inner reader.
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+
+    /// Reads and decrypts the next chunk.
+    fn read_chunk(&mut self) -> Result<()> {
+        let tag_size = self.algorithm().tag_size();
+        let chunk_len = STREAM_CHUNK_SIZE + tag_size;
+
+        // NB: one byte more than a chunk is read to know if the chunk is the last one
+        let mut tmp = [0u8; 4096];
+        while self.buffer.len() <= chunk_len {
+            let want = ::std::cmp::min(tmp.len(), chunk_len + 1 - self.buffer.len());
+            match self.reader.read(&mut tmp[..want]) {
+                Ok(0) => break,
+                Ok(n) => self.buffer.extend_from_slice(&tmp[..n]),
+                Err(ref e) if e.kind() == io::ErrorKind::Interrupted => {},
+                Err(e) => return Err(e.into()),
+            }
+        }
+
+        let last = self.buffer.len() <= chunk_len;
+        let rest = if last {
+            Vec::new()
+        } else {
+            self.buffer.split_off(chunk_len)
+        };
+        let cyph = ::std::mem::replace(&mut self.buffer, rest);
+
+        if cyph.len() < tag_size {
+            return Err(ErrorKind::InvalidLength.into());
+        }
+
+        let nonce = chunk_nonce(&self.prefix, self.counter, last);
+        self.plain = self.cipher.decrypt(&nonce, &[], &cyph)?;
+        self.position = 0;
+
+        if last {
+            self.finished = true;
+        } else {
+            self.counter = self.counter.checked_add(1)
+                .ok_or(ErrorKind::OutOfBound)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl<R: Read> Read for StreamDecryptor<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        while self.position == self.plain.len() {
+            if self.finished {
+                return Ok(0);
+            }
+
+            self.read_chunk()
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, format!("{}", e)))?;
+        }
+
+        let len = ::std::cmp::min(buf.len(), self.plain.len() - self.position);
+        buf[..len].copy_from_slice(&self.plain[self.position..self.position + len]);
+        self.position += len;
+
+        Ok(len)
+    }
+}
@@ -0,0 +1,64 @@
+// Copyright 2018 Yobicash Ltd.
+//
+// Licensed under the MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>
+// and the Apache 2.0 license <LICENSE-APACHE or https://opensource.org/licenses/Apache-2.0>.
+// This file may not be copied, modified, or distributed except according to those
+// terms.
+
+//! The `wipe` module provides the wiping of secret memory.
+//!
+//! The writes are volatile and followed by a compiler fence, so that they are not
+//! optimized away even when the memory is not read afterwards.
+
+use std::ptr;
+use std::sync::atomic::{compiler_fence, Ordering};
+
+/// Trait implemented by types whose memory can be wiped.
+pub trait Wipe {
+    /// Overwrites the memory with zeros.
+    fn wipe(&mut self);
+}
+
+impl Wipe for [u8] {
+    fn wipe(&mut self) {
+        for b in self.iter_mut() {
+            unsafe {
+                ptr::write_volatile(b, 0);
+            }
+        }
+
+        compiler_fence(Ordering::SeqCst);
+    }
+}
+
+impl Wipe for Vec<u8> {
+    fn wipe(&mut self) {
+        // NB: the spare capacity may hold bytes of a previous content
+        let cap = self.capacity();
+        self.resize(cap, 0);
+        self.as_mut_slice().wipe();
+        self.clear();
+    }
+}
+
+impl Wipe for [u32] {
+    fn wipe(&mut self) {
+        for w in self.iter_mut() {
+            unsafe {
+                ptr::write_volatile(w, 0);
+            }
+        }
+
+        compiler_fence(Ordering::SeqCst);
+    }
+}
+
+/// Overwrites a value with another one, typically its zero value, with a
+/// volatile write.
+pub fn wipe_value<T: Copy>(value: &mut T, zero: T) {
+    unsafe {
+        ptr::write_volatile(value, zero);
+    }
+
+    compiler_fence(Ordering::SeqCst);
+}
@@ -0,0 +1,408 @@
+// Copyright 2018 Yobicash Ltd.
+//
+// Licensed under the MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>
+// and the Apache 2.0 license <LICENSE-APACHE or https://opensource.org/licenses/Apache-2.0>.
+// This file may not be copied, modified, or distributed except according to those
+// terms.
+
+//! The `x25519` module provides the X25519 Diffie-Hellman function
+//! ([RFC 7748](https://tools.ietf.org/html/rfc7748)), interoperable with
+//! any standard X25519 peer.
+
+use byteorder::{LittleEndian, ByteOrder};
+use subtle::slices_equal;
+use hex;
+
+use error::ErrorKind;
+use result::Result;
+use traits::{BinarySerialize, HexSerialize};
+use random::Random;
+use wipe::Wipe;
+
+use std::fmt;
+
+/// The size in bytes of X25519 keys and shared secrets.
+pub const X25519_KEY_SIZE: usize = 32;
+
+/// The u-coordinate of the base point of Curve25519.
+pub const X25519_BASEPOINT: [u8; 32] = [
+    9, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+    0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+];
+
+/// An X25519 secret key. The key is clamped when used, as in RFC 7748.
+///
+/// The `X25519SecretKey` is redacted when formatted, and its bytes are only
+/// accessible through `X25519SecretKey::expose_secret`. It does not implement
+/// serde's traits.
+#[derive(Clone, Default)]
+pub struct X25519SecretKey([u8; 32]);
+
+impl X25519SecretKey {
+    /// Creates a `X25519SecretKey` from a byte array.
+    pub fn new(b: [u8; 32]) -> X25519SecretKey {
+        X25519SecretKey(b)
+    }
+
+    /// Creates a random `X25519SecretKey`.
+    pub fn random() -> X25519SecretKey {
+        let mut b = [0u8; 32];
+        b.copy_from_slice(&Random::bytes(32));
+        X25519SecretKey(b)
+    }
+
+    /// Creates a `X25519SecretKey` from a byte slice.
+    pub fn from_bytes(b: &[u8]) -> Result<X25519SecretKey> {
+        if b.len() != X25519_KEY_SIZE {
+            return Err(ErrorKind::InvalidLength.into());
+        }
+
+        let mut _sk = [0u8; 32];
+        _sk.copy_from_slice(b);
+
+        Ok(X25519SecretKey(_sk))
+    }
+
+    /// Creates a `X25519SecretKey` from an hex string.
+    pub fn from_hex(s: &str) -> Result<X25519SecretKey> {
+        let mut b = hex::decode(s)?;
+        let sk = Self::from_bytes(&b);
+        b.wipe();
+
+        sk
+    }
+
+    /// Returns the underlying bytes. The caller is responsible for not
+    /// leaking them.
+    pub fn expose_secret(&self) -> &[u8] {
+        &self.0
+    }
+
+    /// Converts the `X25519SecretKey` to a `X25519PublicKey`.
+    pub fn to_public(&self) -> X25519PublicKey {
+        X25519PublicKey(x25519(self.0, X25519_BASEPOINT))
+    }
+
+    /// Computes the shared secret with a `X25519PublicKey`, failing if the
+    /// shared secret is all zeros (the `X25519PublicKey` has a low order).
+    pub fn diffie_hellman(&self, pk: &X25519PublicKey) -> Result<[u8; 32]> {
+        let shared = x25519(self.0, pk.0);
+
+        if slices_equal(&shared, &[0u8; 32]) == 1 {
+            return Err(ErrorKind::InvalidSharedSecret.into());
+        }
+
+        Ok(shared)
+    }
+}
+
+impl Drop for X25519SecretKey {
+    fn drop(&mut self) {
+        self.0.wipe();
+    }
+}
+
+impl PartialEq for X25519SecretKey {
+    fn eq(&self, other: &X25519SecretKey) -> bool {
+        slices_equal(&self.0, &other.0) == 1
+    }
+}
+
+impl Eq for X25519SecretKey {}
+
+impl fmt::Debug for X25519SecretKey {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "X25519SecretKey(<redacted>)")
+    }
+}
+
+impl fmt::Display for X25519SecretKey {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "X25519SecretKey(<redacted>)")
+    }
+}
+
+/// An X25519 public key, the u-coordinate of a point of Curve25519.
+#[derive(Copy, Clone, Eq, PartialEq, Default, Debug, Serialize, Deserialize)]
+pub struct X25519PublicKey([u8; 32]);
+
+impl X25519PublicKey {
+    /// Creates a `X25519PublicKey` from a byte array.
+    pub fn new(b: [u8; 32]) -> X25519PublicKey {
+        X25519PublicKey(b)
+    }
+}
+
+impl BinarySerialize for X25519PublicKey {
+    fn to_bytes(&self) -> Result<Vec<u8>> {
+        Ok(self.0.to_vec())
+    }
+
+    fn from_bytes(b: &[u8]) -> Result<X25519PublicKey> {
+        if b.len() != X25519_KEY_SIZE {
+            return Err(ErrorKind::InvalidLength.into());
+        }
+
+        let mut _pk = [0u8; 32];
+        _pk.copy_from_slice(b);
+
+        Ok(X25519PublicKey(_pk))
+    }
+}
+
+impl HexSerialize for X25519PublicKey {
+    fn to_hex(&self) -> Result<String> {
+        Ok(hex::encode(&self.to_bytes()?))
+    }
+
+    fn from_hex(s: &str) -> Result<X25519PublicKey> {
+        Self::from_bytes(&hex::decode(s)?)
+    }
+}
+
+impl fmt::Display for X25519PublicKey {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:?}", self.to_hex().unwrap())
+    }
+}
+
+/// Clamps a scalar as in RFC 7748, section 5.
+pub fn x25519_clamp(mut k: [u8; 32]) -> [u8; 32] {
+    k[0] &= 248;
+    k[31] &= 127;
+    k[31] |= 64;
+    k
+}
+
+/// The X25519 function of RFC 7748: multiplies the u-coordinate `u` by the
+/// clamped scalar `k` with the Montgomery ladder, in constant time.
+pub fn x25519(k: [u8; 32], u: [u8; 32]) -> [u8; 32] {
+    let mut k = x25519_clamp(k);
+
+    let x_1 = FieldElement::from_bytes(&u);
+    let mut x_2 = FieldElement::one();
+    let mut z_2 = FieldElement::zero();
+    let mut x_3 = x_1;
+    let mut z_3 = FieldElement::one();
+    let mut swap = 0u64;
+
+    for t in (0..255).rev() {
+        let k_t = ((k[t / 8] >> (t % 8)) & 1) as u64;
+        swap ^= k_t;
+        FieldElement::cswap(&mut x_2, &mut x_3, swap);
+        FieldElement::cswap(&mut z_2, &mut z_3, swap);
+        swap = k_t;
+
+        let a = x_2.add(&z_2);
+        let aa = a.square();
+        let b = x_2.sub(&z_2);
+        let bb = b.square();
+        let e = aa.sub(&bb);
+        let c = x_3.add(&z_3);
+        let d = x_3.sub(&z_3);
+        let da = d.mul(&a);
+        let cb = c.mul(&b);
+        x_3 = da.add(&cb).square();
+        z_3 = x_1.mul(&da.sub(&cb).square());
+        x_2 = aa.mul(&bb);
+        z_2 = e.mul(&aa.add(&e.mul_small(121665)));
+    }
+
+    FieldElement::cswap(&mut x_2, &mut x_3, swap);
+    FieldElement::cswap(&mut z_2, &mut z_3, swap);
+
+    k.wipe();
+
+    x_2.mul(&z_2.invert()).to_bytes()
+}
+
+/// An element of GF(2^255 - 19) in radix 2^51.
+#[derive(Copy, Clone)]
+struct FieldElement([u64; 5]);
+
+const MASK_51: u64 = (1 << 51) - 1;
+
+impl FieldElement {
+    fn zero() -> FieldElement {
+        FieldElement([0; 5])
+    }
+
+    fn one() -> FieldElement {
+        FieldElement([1, 0, 0, 0, 0])
+    }
+
+    /// Decodes a little-endian u-coordinate, masking the most significant bit.
+    fn from_bytes(b: &[u8; 32]) -> FieldElement {
+        FieldElement([
+            LittleEndian::read_u64(&b[0..8]) & MASK_51,
+            (LittleEndian::read_u64(&b[6..14]) >> 3) & MASK_51,
+            (LittleEndian::read_u64(&b[12..20]) >> 6) & MASK_51,
+            (LittleEndian::read_u64(&b[19..27]) >> 1) & MASK_51,
+            (LittleEndian::read_u64(&b[24..32]) >> 12) & MASK_51,
+        ])
+    }
+
+    /// Encodes the element in its canonical little-endian form.
+    fn to_bytes(&self) -> [u8; 32] {
+        let mut l = FieldElement::carry(self.0).0;
+
+        // q is 1 iff l >= p, as l < 2p after the carry
+        let mut q = (l[0] + 19) >> 51;
+        q = (l[1] + q) >> 51;
+        q = (l[2] + q) >> 51;
+        q = (l[3] + q) >> 51;
+        q = (l[4] + q) >> 51;
+
+        l[0] += 19 * q;
+        l[1] += l[0] >> 51;
+        l[0] &= MASK_51;
+        l[2] += l[1] >> 51;
+        l[1] &= MASK_51;
+        l[3] += l[2] >> 51;
+        l[2] &= MASK_51;
+        l[4] += l[3] >> 51;
+        l[3] &= MASK_51;
+        l[4] &= MASK_51;
+
+        let mut b = [0u8; 32];
+        LittleEndian::write_u64(&mut b[0..8], l[0] | (l[1] << 51));
+        LittleEndian::write_u64(&mut b[8..16], (l[1] >> 13) | (l[2] << 38));
+        LittleEndian::write_u64(&mut b[16..24], (l[2] >> 26) | (l[3] << 25));
+        LittleEndian::write_u64(&mut b[24..32], (l[3] >> 39) | (l[4] << 12));
+        b
+    }
+
+    /// Propagates the carries, leaving every limb below 2^52.
+    fn carry(mut l: [u64; 5]) -> FieldElement {
+        let c = l[0] >> 51;
+        l[0] &= MASK_51;
+        l[1] += c;
+        let c = l[1] >> 51;
+        l[1] &= MASK_51;
+        l[2] += c;
+        let c = l[2] >> 51;
+        l[2] &= MASK_51;
+        l[3] += c;
+        let c = l[3] >> 51;
+        l[3] &= MASK_51;
+        l[4] += c;
+        let c = l[4] >> 51;
+        l[4] &= MASK_51;
+        l[0] += c * 19;
+
+        FieldElement(l)
+    }
+
+    fn add(&self, other: &FieldElement) -> FieldElement {
+        let mut l = [0u64; 5];
+        for i in 0..5 {
+            l[i] = self.0[i] + other.0[i];
+        }
+
+        FieldElement::carry(l)
+    }
+
+    fn sub(&self, other: &FieldElement) -> FieldElement {
+        // NB: 4p is added to avoid underflows
+        let four_p = [
+            0x1f_ffff_ffff_ffb4,
+            0x1f_ffff_ffff_fffc,
+            0x1f_ffff_ffff_fffc,
+            0x1f_ffff_ffff_fffc,
+            0x1f_ffff_ffff_fffc,
+        ];
+
+        let mut l = [0u64; 5];
+        for i in 0..5 {
+            l[i] = (self.0[i] + four_p[i]) - other.0[i];
+        }
+
+        FieldElement::carry(l)
+    }
+
+    fn mul(&self, other: &FieldElement) -> FieldElement {
+        let a = &self.0;
+        let b = &other.0;
+
+        let m = |x: u64, y: u64| (x as u128) * (y as u128);
+
+        let b1_19 = b[1] * 19;
+        let b2_19 = b[2] * 19;
+        let b3_19 = b[3] * 19;
+        let b4_19 = b[4] * 19;
+
+        let c0 = m(a[0], b[0]) + m(a[1], b4_19) + m(a[2], b3_19) + m(a[3], b2_19) + m(a[4], b1_19);
+        let c1 = m(a[0], b[1]) + m(a[1], b[0]) + m(a[2], b4_19) + m(a[3], b3_19) + m(a[4], b2_19);
+        let c2 = m(a[0], b[2]) + m(a[1], b[1]) + m(a[2], b[0]) + m(a[3], b4_19) + m(a[4], b3_19);
+        let c3 = m(a[0], b[3]) + m(a[1], b[2]) + m(a[2], b[1]) + m(a[3], b[0]) + m(a[4], b4_19);
+        let c4 = m(a[0], b[4]) + m(a[1], b[3]) + m(a[2], b[2]) + m(a[3], b[1]) + m(a[4], b[0]);
+
+        FieldElement::reduce_wide([c0, c1, c2, c3, c4])
+    }
+
+    fn square(&self) -> FieldElement {
+        self.mul(self)
+    }
+
+    fn mul_small(&self, n: u32) -> FieldElement {
+        let mut c = [0u128; 5];
+        for i in 0..5 {
+            c[i] = (self.0[i] as u128) * (n as u128);
+        }
+
+        FieldElement::reduce_wide(c)
+    }
+
+    /// Reduces the wide limbs of a product.
+    fn reduce_wide(mut c: [u128; 5]) -> FieldElement {
+        let mask = MASK_51 as u128;
+
+        c[1] += c[0] >> 51;
+        c[2] += c[1] >> 51;
+        c[3] += c[2] >> 51;
+        c[4] += c[3] >> 51;
+        let carry = (c[4] >> 51) as u64;
+
+        let mut l = [
+            (c[0] & mask) as u64,
+            (c[1] & mask) as u64,
+            (c[2] & mask) as u64,
+            (c[3] & mask) as u64,
+            (c[4] & mask) as u64,
+        ];
+
+        l[0] += carry * 19;
+        l[1] += l[0] >> 51;
+        l[0] &= MASK_51;
+
+        FieldElement(l)
+    }
+
+    /// Computes the inverse as self^(p - 2).
+    fn invert(&self) -> FieldElement {
+        // NB: p - 2 = 2^255 - 21
+        let mut exp = [0xffu8; 32];
+        exp[0] = 0xeb;
+        exp[31] = 0x7f;
+
+        let mut res = FieldElement::one();
+        for i in (0..255).rev() {
+            res = res.square();
+            if (exp[i / 8] >> (i % 8)) & 1 == 1 {
+                res = res.mul(self);
+            }
+        }
+
+        res
+    }
+
+    /// Swaps `a` and `b` if `swap` is 1, in constant time.
+    fn cswap(a: &mut FieldElement, b: &mut FieldElement, swap: u64) {
+        let mask = 0u64.wrapping_sub(swap);
+        for i in 0..5 {
+            let t = mask & (a.0[i] ^ b.0[i]);
+            a.0[i] ^= t;
+            b.0[i] ^= t;
+        }
+    }
+}
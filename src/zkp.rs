@@ -7,18 +7,33 @@
 
 //! The `zkp` module provides Schnorr Algorithm types and methods.
 
+use byteorder::{BigEndian, WriteBytesExt};
+use curve25519::constants::BASE_CMPRSSD;
+use curve25519::edwards::vartime;
+use curve25519::traits::IsIdentity;
 use hex;
+use serde::{Deserialize, Deserializer};
+use serde::de;
 
 use error::ErrorKind;
 use result::Result;
 use traits::Validate;
 use traits::{BinarySerialize, HexSerialize};
-use scalar::Scalar;
+use random::Random;
+use wipe::Wipe;
+use kdf::hmac_sha512;
+use scalar::{Scalar, SecretScalar};
 use point::Point;
 
 use std::io::Write;
 use std::fmt;
 
+/// The domain separation tag of the nonce.
+const ZKP_NONCE_TAG: &[u8] = b"yobicrypto-zkp-nonce";
+
+/// The domain separation tag of the message-bound challenge.
+const ZKP_CHALLENGE_TAG: &[u8] = b"yobicrypto-zkp-challenge";
+
 /// The ZKP witness is the publicly known variable of the relation R(x, w)
 /// in the language L of the statements of the type `w = g^x`, for g
 /// a generator of an elliptic curve G. The receiver uses the Schnorr Protocol
@@ -26,7 +41,7 @@ use std::fmt;
 /// value of x. In this way we can build a simple anonymous credential system.
 ///
 /// See the `ZKPProof` type and the `output` module to see its usage.
-#[derive(Copy, Clone, Eq, PartialEq, Default, Debug, Serialize, Deserialize)]
+#[derive(Copy, Clone, Eq, PartialEq, Default, Debug, Serialize)]
 pub struct ZKPWitness(pub Point);
 
 impl ZKPWitness {
@@ -37,11 +52,13 @@ impl ZKPWitness {
         Ok(ZKPWitness(&Point::default() * &instance))
     }
 
-    /// Creates a  new `ZKPWitness` from a `Point`.
+    /// Creates a  new `ZKPWitness` from a `Point`, which must not have a small order
+    /// nor a small-order component.
     pub fn from_point(point: Point) -> Result<ZKPWitness> {
-        point.validate()?;
+        let witness = ZKPWitness(point);
+        witness.validate()?;
 
-        Ok(ZKPWitness(point))
+        Ok(witness)
     }
 
     /// Returns the underlying `Point`.
@@ -52,7 +69,15 @@ impl ZKPWitness {
 
 impl Validate for ZKPWitness {
     fn validate(&self) -> Result<()> {
-        self.0.validate()
+        self.0.validate()?;
+
+        // NB: the instance of a small-order witness, like the identity, is trivial,
+        // and a small-order component would let the verifications disagree
+        if self.0.is_small_order() || !self.0.is_torsion_free() {
+            return Err(ErrorKind::InvalidArgument.into());
+        }
+
+        Ok(())
     }
 }
 
@@ -62,7 +87,7 @@ impl BinarySerialize for ZKPWitness {
     }
 
     fn from_bytes(b: &[u8]) -> Result<ZKPWitness> {
-        Ok(ZKPWitness(Point::from_bytes(b)?))
+        ZKPWitness::from_point(Point::from_bytes(b)?)
     }
 }
 
@@ -72,7 +97,15 @@ impl HexSerialize for ZKPWitness {
     }
 
     fn from_hex(s: &str) -> Result<ZKPWitness> {
-        Ok(ZKPWitness(Point::from_hex(s)?))
+        ZKPWitness::from_point(Point::from_hex(s)?)
+    }
+}
+
+impl<'de> Deserialize<'de> for ZKPWitness {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> ::std::result::Result<ZKPWitness, D::Error> {
+        let point = Point::deserialize(deserializer)?;
+
+        ZKPWitness::from_point(point).map_err(|e| de::Error::custom(e))
     }
 }
 
@@ -82,41 +115,82 @@ impl fmt::Display for ZKPWitness {
     }
 }
 
+/// The report of the batch verification of `ZKPProof`s.
+#[derive(Clone, Eq, PartialEq, Debug, Default)]
+pub struct ZKPBatchReport {
+    /// The indexes of the invalid proofs.
+    pub invalid: Vec<usize>,
+}
+
+impl ZKPBatchReport {
+    /// Returns true if every proof is valid.
+    pub fn is_valid(&self) -> bool {
+        self.invalid.is_empty()
+    }
+}
+
 /// The ZKP proof is a non-interactive cryptographical proof of the knowledge
 /// of a secret value `x` for wich `w = g^x` is true, where g is a generator
 /// of the elliptic curve G.  
 ///
 /// See the `input` module to see its usage.
-#[derive(Copy, Clone, Eq, PartialEq, Default, Debug, Serialize, Deserialize)]
+#[derive(Clone, Eq, PartialEq, Default, Debug, Serialize, Deserialize)]
 pub struct ZKPProof {
-    /// The public coin, a `Point` t = g^v, where v is a (pseudo-)random `Scalar` and g
-    /// the base point.
+    /// The public coin, a `Point` t = g^v, where v is a `Scalar` derived from the secret
+    /// instance and the message, and g the base point.
     pub public_coin: Point,
-    /// The challenge, a `Scalar` c = H(g, w, t), where g is the base point,
-    /// w the witness Point, and t the public coin.
+    /// The challenge, a `Scalar` c = H(g, w, t, m), where g is the base point,
+    /// w the witness Point, t the public coin, and m the message with its context
+    /// (c = H(g, w, t) for the legacy proofs).
     pub challenge: Scalar,
-    /// The response, a `Scalar` r = v - c*x, where v is the (pseudo-)random `Scalar`
+    /// The response, a `Scalar` r = v - c*x, where v is the `Scalar`
     /// used to obtain the public coin, c is the challenge and x is the secret instance.
     pub response: Scalar, // r = v - cx mod q-1; accepts if t = (g^r)*(w^c) mod q
 }
 
 impl ZKPProof {
-    /// Creates a zero-knowledge proof from a witness instance and a message. 
+    /// Creates a legacy zero-knowledge proof from a witness instance and a message.
+    /// The message does not affect the challenge, so the proof is not bound to it:
+    /// use `ZKPProof::prove` instead.
     pub fn new(instance: Scalar, message: &[u8]) -> Result<ZKPProof> {
+        ZKPProof::create(instance, message, None, false)
+    }
+
+    /// Creates a legacy zero-knowledge proof like `ZKPProof::new`, with a hedged nonce.
+    pub fn new_hedged(instance: Scalar, message: &[u8]) -> Result<ZKPProof> {
+        ZKPProof::create(instance, message, None, true)
+    }
+
+    /// Creates a zero-knowledge proof from a witness instance, bound to a message
+    /// and to a context (e.g. the protocol and the purpose of the proof).
+    pub fn prove(instance: Scalar, message: &[u8], context: &[u8]) -> Result<ZKPProof> {
+        ZKPProof::create(instance, message, Some(context), false)
+    }
+
+    /// Creates a zero-knowledge proof like `ZKPProof::prove`, with a hedged nonce.
+    pub fn prove_hedged(instance: Scalar, message: &[u8], context: &[u8]) -> Result<ZKPProof> {
+        ZKPProof::create(instance, message, Some(context), true)
+    }
+
+    /// Creates a proof, bound to the message if a context is given, and a legacy
+    /// proof otherwise.
+    fn create(instance: Scalar, message: &[u8], context: Option<&[u8]>, hedged: bool) -> Result<ZKPProof> {
+        let instance = SecretScalar::new(instance);
+        instance.validate()?;
+
         let g = Point::default();
 
-        let witness = &g * &instance;
-        let public_coin_scalar = Scalar::from_hash(message);
-        let public_coin = &g * &public_coin_scalar;
+        let witness = &g * instance.expose_secret();
+        let public_coin_scalar = ZKPProof::nonce(&instance, message, context, hedged)?;
+        let public_coin = &g * public_coin_scalar.expose_secret();
 
-        let mut buf = Vec::new();
-        buf.write_all(&g.to_bytes()?)?;
-        buf.write_all(&witness.to_bytes()?)?;
-        buf.write_all(&public_coin.to_bytes()?)?;
-        
-        let challenge = Scalar::from_hash(&buf);
+        let challenge = match context {
+            Some(context) => ZKPProof::challenge(&witness, &public_coin, message, context)?,
+            None => ZKPProof::legacy_challenge(&witness, &public_coin)?,
+        };
 
-        let response = &public_coin_scalar - &(&challenge*&instance);
+        let _cx = SecretScalar::new(&challenge * instance.expose_secret());
+        let response = public_coin_scalar.expose_secret() - _cx.expose_secret();
 
         Ok(ZKPProof {
             public_coin: public_coin,
@@ -125,17 +199,217 @@ impl ZKPProof {
         })
     }
 
-    /// Verifies the zero-knowledge proof against a witness.
-    pub fn verify(&self, witness: ZKPWitness) -> Result<bool> {
-    // r = v - cx mod q-1; accepts if t = (g^r)*(w^c) mod q
+    /// Verifies the zero-knowledge proof against a witness, a message and a context.
+    pub fn verify(&self, witness: ZKPWitness, message: &[u8], context: &[u8]) -> Result<bool> {
+        witness.validate()?;
+
+        if !ZKPProof::valid_public_coin(&self.public_coin) {
+            return Ok(false);
+        }
+
+        let challenge = ZKPProof::challenge(&witness.to_point(), &self.public_coin, message, context)?;
+        if self.challenge != challenge {
+            return Ok(false);
+        }
+
+        Ok(self.verify_relation(witness))
+    }
+
+    /// Verifies a legacy zero-knowledge proof, created with `ZKPProof::new`, against
+    /// a witness. The proof is not bound to any message.
+    pub fn verify_legacy(&self, witness: ZKPWitness) -> Result<bool> {
+        witness.validate()?;
+
+        if !ZKPProof::valid_public_coin(&self.public_coin) {
+            return Ok(false);
+        }
+
+        let challenge = ZKPProof::legacy_challenge(&witness.to_point(), &self.public_coin)?;
+        if self.challenge != challenge {
+            return Ok(false);
+        }
+
+        Ok(self.verify_relation(witness))
+    }
+
+    /// Verifies a batch of zero-knowledge proofs, each with its witness and its message,
+    /// against a context. See `ZKPProof::verify_batch_legacy`.
+    pub fn verify_batch(items: &[(ZKPProof, ZKPWitness, &[u8])], context: &[u8]) -> Result<ZKPBatchReport> {
+        let mut checked = Vec::new();
+
+        for &(ref proof, witness, message) in items {
+            let valid = witness.validate().is_ok() && ZKPProof::valid_public_coin(&proof.public_coin) &&
+                proof.challenge == ZKPProof::challenge(&witness.to_point(), &proof.public_coin, message, context)?;
+
+            checked.push((proof, witness, valid));
+        }
+
+        Ok(ZKPProof::verify_batch_relations(checked))
+    }
+
+    /// Verifies a batch of legacy zero-knowledge proofs, each with its witness.
+    ///
+    /// The relations t = (g^r)*(w^c) of all the proofs are checked at once with a
+    /// random linear combination, in a single multiscalar multiplication. If the batch
+    /// fails, the proofs are verified one by one to find the invalid ones. The
+    /// `zkpbench` example compares it with the verification one by one.
+    ///
+    /// NB: the batch equation is multiplied by the cofactor, while `ZKPProof::verify_legacy`
+    /// is exact. They agree because both reject the witnesses and the public coins
+    /// with a small-order component.
+    pub fn verify_batch_legacy(items: &[(ZKPProof, ZKPWitness)]) -> Result<ZKPBatchReport> {
+        let mut checked = Vec::new();
+
+        for &(ref proof, witness) in items {
+            let valid = witness.validate().is_ok() && ZKPProof::valid_public_coin(&proof.public_coin) &&
+                proof.challenge == ZKPProof::legacy_challenge(&witness.to_point(), &proof.public_coin)?;
+
+            checked.push((proof, witness, valid));
+        }
+
+        Ok(ZKPProof::verify_batch_relations(checked))
+    }
+
+    /// Verifies the relations of the proofs whose challenge is valid, and reports
+    /// the invalid proofs.
+    fn verify_batch_relations(checked: Vec<(&ZKPProof, ZKPWitness, bool)>) -> ZKPBatchReport {
+        let mut report = ZKPBatchReport::default();
+
+        let mut scalars = Vec::new();
+        let mut points = Vec::new();
+        let mut g_scalar = Scalar::default();
+
+        // NB: sum(z_i*t_i) - (sum(z_i*r_i))*g - sum(z_i*c_i*w_i) = 0, with random z_i
+        for (i, &(proof, witness, valid)) in checked.iter().enumerate() {
+            if !valid {
+                report.invalid.push(i);
+                continue;
+            }
+
+            let z = Scalar::random();
+
+            g_scalar = &g_scalar - &(&z * &proof.response);
+            scalars.push(&Scalar::default() - &(&z * &proof.challenge));
+            points.push(witness.to_point());
+            scalars.push(z);
+            points.push(proof.public_coin);
+        }
+
+        scalars.push(g_scalar);
+        points.push(Point::default());
+
+        let sum = vartime::multiscalar_mult(scalars.iter().map(|s| &s.0), points.iter().map(|p| &p.0));
+
+        if !sum.mult_by_cofactor().is_identity() {
+            for (i, &(proof, witness, valid)) in checked.iter().enumerate() {
+                if valid && !proof.verify_relation(witness) {
+                    report.invalid.push(i);
+                }
+            }
+
+            report.invalid.sort();
+        }
+
+        report
+    }
+
+    /// Returns the compact encoding of the proof, without the public coin.
+    ///
+    /// The compact proof verifies if and only if the proof does: a public coin
+    /// with a small-order component, which the compact proof cannot carry, is
+    /// rejected by both.
+    pub fn to_compact(&self) -> ZKPCompactProof {
+        ZKPCompactProof {
+            challenge: self.challenge.clone(),
+            response: self.response.clone(),
+        }
+    }
+
+    /// Creates a proof from its compact encoding, recomputing the public coin
+    /// from the witness.
+    pub fn from_compact(compact: &ZKPCompactProof, witness: ZKPWitness) -> Result<ZKPProof> {
         witness.validate()?;
-        
+
+        Ok(ZKPProof {
+            public_coin: compact.public_coin(witness),
+            challenge: compact.challenge.clone(),
+            response: compact.response.clone(),
+        })
+    }
+
+    /// Checks that t = (g^r)*(w^c).
+    fn verify_relation(&self, witness: ZKPWitness) -> bool {
         let g = Point::default();
 
         let gr = &g * &self.response;
         let wc = &witness.to_point() * &self.challenge;
 
-        Ok(self.public_coin == &gr + &wc)
+        self.public_coin == &gr + &wc
+    }
+
+    /// Checks that the public coin has neither a small order nor a small-order component.
+    fn valid_public_coin(public_coin: &Point) -> bool {
+        !public_coin.is_small_order() && public_coin.is_torsion_free()
+    }
+
+    /// Derives the `Scalar` of the public coin, deterministically as in RFC 6979,
+    /// with the HMAC-SHA512 of the message keyed by the secret instance. A hedged
+    /// nonce also mixes in fresh randomness, which protects against fault attacks
+    /// while not depending only on the quality of the random source.
+    fn nonce(instance: &SecretScalar, message: &[u8], context: Option<&[u8]>, hedged: bool) -> Result<SecretScalar> {
+        let mut buf = Vec::new();
+        buf.write_all(ZKP_NONCE_TAG)?;
+
+        // NB: a legacy and a bound proof of the same message must not share a nonce
+        match context {
+            Some(context) => {
+                buf.write_u8(1)?;
+                buf.write_u64::<BigEndian>(context.len() as u64)?;
+                buf.write_all(context)?;
+            },
+            None => buf.write_u8(0)?,
+        }
+
+        buf.write_u64::<BigEndian>(message.len() as u64)?;
+        buf.write_all(message)?;
+
+        if hedged {
+            buf.write_all(&Random::bytes(32))?;
+        }
+
+        let mut _key = instance.to_bytes()?;
+        let mut _nonce = hmac_sha512(&_key, &buf);
+        let nonce = SecretScalar::new(Scalar::from_bytes_wide(&_nonce));
+        _key.wipe();
+        _nonce.wipe();
+
+        Ok(nonce)
+    }
+
+    /// Computes the challenge c = H(tag, context, g, w, t, message), length-prefixing
+    /// the context and the message.
+    fn challenge(witness: &Point, public_coin: &Point, message: &[u8], context: &[u8]) -> Result<Scalar> {
+        let mut buf = Vec::new();
+        buf.write_all(ZKP_CHALLENGE_TAG)?;
+        buf.write_u64::<BigEndian>(context.len() as u64)?;
+        buf.write_all(context)?;
+        buf.write_all(BASE_CMPRSSD.as_bytes())?;
+        buf.write_all(&witness.to_bytes()?)?;
+        buf.write_all(&public_coin.to_bytes()?)?;
+        buf.write_u64::<BigEndian>(message.len() as u64)?;
+        buf.write_all(message)?;
+
+        Ok(Scalar::from_hash(&buf))
+    }
+
+    /// Computes the legacy challenge c = H(g, w, t).
+    fn legacy_challenge(witness: &Point, public_coin: &Point) -> Result<Scalar> {
+        let mut buf = Vec::new();
+        buf.write_all(BASE_CMPRSSD.as_bytes())?;
+        buf.write_all(&witness.to_bytes()?)?;
+        buf.write_all(&public_coin.to_bytes()?)?;
+
+        Ok(Scalar::from_hash(&buf))
     }
 }
 
@@ -186,3 +460,98 @@ impl HexSerialize for ZKPProof {
         Self::from_bytes(&hex::decode(s)?)
     }
 }
+
+/// The compact ZKP proof is a `ZKPProof` without the public coin, which the
+/// verifier recomputes from the witness as t = (g^r)*(w^c) before checking the
+/// challenge. It is 64 bytes long instead of 96.
+#[derive(Clone, Eq, PartialEq, Default, Debug, Serialize, Deserialize)]
+pub struct ZKPCompactProof {
+    /// The challenge, a `Scalar` c = H(g, w, t, m), as in `ZKPProof`.
+    pub challenge: Scalar,
+    /// The response, a `Scalar` r = v - c*x, as in `ZKPProof`.
+    pub response: Scalar,
+}
+
+impl ZKPCompactProof {
+    /// Verifies the compact zero-knowledge proof against a witness, a message and a context.
+    pub fn verify(&self, witness: ZKPWitness, message: &[u8], context: &[u8]) -> Result<bool> {
+        witness.validate()?;
+
+        let public_coin = self.public_coin(witness);
+        if public_coin.is_small_order() {
+            return Ok(false);
+        }
+
+        let challenge = ZKPProof::challenge(&witness.to_point(), &public_coin, message, context)?;
+
+        Ok(self.challenge == challenge)
+    }
+
+    /// Verifies the compact encoding of a legacy zero-knowledge proof against a witness.
+    pub fn verify_legacy(&self, witness: ZKPWitness) -> Result<bool> {
+        witness.validate()?;
+
+        let public_coin = self.public_coin(witness);
+        if public_coin.is_small_order() {
+            return Ok(false);
+        }
+
+        let challenge = ZKPProof::legacy_challenge(&witness.to_point(), &public_coin)?;
+
+        Ok(self.challenge == challenge)
+    }
+
+    /// Recomputes the public coin t = (g^r)*(w^c).
+    fn public_coin(&self, witness: ZKPWitness) -> Point {
+        let g = Point::default();
+
+        let gr = &g * &self.response;
+        let wc = &witness.to_point() * &self.challenge;
+
+        &gr + &wc
+    }
+}
+
+impl Validate for ZKPCompactProof {
+    fn validate(&self) -> Result<()> {
+        self.challenge.validate()?;
+        self.response.validate()?;
+
+        Ok(())
+    }
+}
+
+impl BinarySerialize for ZKPCompactProof {
+    fn to_bytes(&self) -> Result<Vec<u8>> {
+        let mut buf = Vec::new();
+
+        buf.write_all(&self.challenge.to_bytes()?)?;
+        buf.write_all(&self.response.to_bytes()?)?;
+
+        Ok(buf)
+    }
+
+    fn from_bytes(b: &[u8]) -> Result<ZKPCompactProof> {
+        if b.len() != 64 {
+            return Err(ErrorKind::InvalidLength.into());
+        }
+
+        let challenge = Scalar::from_bytes(&b[0..32])?;
+        let response = Scalar::from_bytes(&b[32..])?;
+
+        Ok(ZKPCompactProof {
+            challenge: challenge,
+            response: response,
+        })
+    }
+}
+
+impl HexSerialize for ZKPCompactProof {
+    fn to_hex(&self) -> Result<String> {
+        Ok(hex::encode(&self.to_bytes()?))
+    }
+
+    fn from_hex(s: &str) -> Result<ZKPCompactProof> {
+        Self::from_bytes(&hex::decode(s)?)
+    }
+}
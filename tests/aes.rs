@@ -0,0 +1,124 @@
+// Copyright 2018 Yobicash Ltd. See the COPYRIGHT file at the top-level directory
+// of this distribution.
+//
+// Licensed under the MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>
+// and the Apache 2.0 license <LICENSE-APACHE or https://opensource.org/licenses/Apache-2.0>.
+// This file may not be copied, modified, or distributed except according to those
+// terms.
+
+//! Yobicrypto `aes` module tests.
+
+extern crate yobicrypto;
+extern crate hex;
+
+use yobicrypto::{ErrorKind, Random};
+use yobicrypto::Key;
+use yobicrypto::HexSerialize;
+use yobicrypto::{BlockCipher, AES256, AES256Ctaes, has_aesni};
+#[cfg(target_arch = "x86_64")]
+use yobicrypto::AES256NI;
+
+// FIPS-197, appendix C.3, and NIST SP 800-38A, F.1.5
+fn aes256_test_vectors() -> Vec<(String, String, String)> {
+    vec![
+        (
+            "000102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f".to_string(),
+            "00112233445566778899aabbccddeeff".to_string(),
+            "8ea2b7ca516745bfeafc49904b496089".to_string(),
+        ),
+        (
+            "603deb1015ca71be2b73aef0857d77811f352c073b6108d72d9810a30914dff4".to_string(),
+            "6bc1bee22e409f96e93d7e117393172aae2d8a571e03ac9c9eb76fac45af8e51\
+             30c81c46a35ce411e5fbc1191a0a52eff69f2445df4f9b17ad2b417be66c3710".to_string(),
+            "f3eed1bdb5d2a03c064b5a7e3db181f8591ccb10d410ed26dc5ba74a31362870\
+             b6ed21b99ca6f4f9f153e7b1beafed1d23304b7a39f9f3ff067d8d8f9e24ecc7".to_string(),
+        ),
+    ]
+}
+
+fn check_test_vectors<C: BlockCipher>() {
+    for v in aes256_test_vectors() {
+        let key = Key::from_hex(&v.0).unwrap();
+        let plain = hex::decode(&v.1).unwrap();
+        let cyph = hex::decode(&v.2).unwrap();
+
+        let cipher = C::new(&key).unwrap();
+
+        let mut buf = plain.clone();
+        cipher.encrypt_blocks(&mut buf).unwrap();
+        assert_eq!(buf, cyph);
+
+        cipher.decrypt_blocks(&mut buf).unwrap();
+        assert_eq!(buf, plain)
+    }
+}
+
+#[test]
+fn aes256_test_vectors_succ() {
+    check_test_vectors::<AES256>()
+}
+
+#[test]
+fn aes256_ctaes_test_vectors_succ() {
+    check_test_vectors::<AES256Ctaes>()
+}
+
+#[test]
+#[cfg(target_arch = "x86_64")]
+fn aes256_ni_test_vectors_succ() {
+    if !has_aesni() {
+        return;
+    }
+
+    check_test_vectors::<AES256NI>()
+}
+
+#[test]
+#[cfg(target_arch = "x86_64")]
+fn aes256_ni_ctaes_cross_check_succ() {
+    if !has_aesni() {
+        return;
+    }
+
+    for i in 0..64 {
+        let key = Key::new();
+        let plain = Random::bytes(16 * i);
+
+        let ctaes = AES256Ctaes::new(&key).unwrap();
+        let ni = AES256NI::new(&key).unwrap();
+
+        let mut cyph_a = plain.clone();
+        ctaes.encrypt_blocks(&mut cyph_a).unwrap();
+        let mut cyph_b = plain.clone();
+        ni.encrypt_blocks(&mut cyph_b).unwrap();
+        assert_eq!(cyph_a, cyph_b);
+
+        ni.decrypt_blocks(&mut cyph_a).unwrap();
+        ctaes.decrypt_blocks(&mut cyph_b).unwrap();
+        assert_eq!(cyph_a, plain);
+        assert_eq!(cyph_b, plain)
+    }
+}
+
+#[test]
+fn aes256_backend_succ() {
+    let cipher = AES256::new(&Key::new()).unwrap();
+
+    match cipher {
+        AES256::Ctaes(_) => assert!(!has_aesni()),
+        #[cfg(target_arch = "x86_64")]
+        AES256::NI(_) => assert!(has_aesni()),
+    }
+}
+
+#[test]
+fn aes256_encrypt_blocks_fail() {
+    let cipher = AES256::new(&Key::new()).unwrap();
+    let mut buf = Random::bytes(17);
+    let res = cipher.encrypt_blocks(&mut buf);
+    assert_eq!(res.unwrap_err().kind(), ErrorKind::InvalidLength);
+
+    let cipher = AES256Ctaes::new(&Key::new()).unwrap();
+    let res = cipher.decrypt_blocks(&mut buf);
+    assert_eq!(res.unwrap_err().kind(), ErrorKind::InvalidLength)
+}
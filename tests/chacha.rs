@@ -0,0 +1,143 @@
+// Copyright 2018 Yobicash Ltd. See the COPYRIGHT file at the top-level directory
+// of this distribution.
+//
+// Licensed under the MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>
+// and the Apache 2.0 license <LICENSE-APACHE or https://opensource.org/licenses/Apache-2.0>.
+// This file may not be copied, modified, or distributed except according to those
+// terms.
+
+//! Yobicrypto `chacha` module tests.
+
+extern crate yobicrypto;
+extern crate hex;
+
+use yobicrypto::{ErrorKind, Random, Key};
+use yobicrypto::HexSerialize;
+use yobicrypto::AEADCipher;
+use yobicrypto::{ChaCha20Poly1305, XChaCha20Poly1305};
+use yobicrypto::{poly1305, hchacha20};
+
+fn aead_test_vectors() -> Vec<(String, String, String, String, String, String)> {
+    // RFC 8439, section 2.8.2.
+    vec![
+        (
+            "808182838485868788898a8b8c8d8e8f909192939495969798999a9b9c9d9e9f".to_string(),
+            "070000004041424344454647".to_string(),
+            "4c616469657320616e642047656e746c656d656e206f662074686520636c617373206f66202739393a204966204920636f756c64206f6666657220796f75206f6e6c79206f6e652074697020666f7220746865206675747572652c2073756e73637265656e20776f756c642062652069742e".to_string(),
+            "50515253c0c1c2c3c4c5c6c7".to_string(),
+            "d31a8d34648e60db7b86afbc53ef7ec2a4aded51296e08fea9e2b5a736ee62d63dbea45e8ca9671282fafb69da92728b1a71de0a9e060b2905d6a5b67ecd3b3692ddbd7f2d778b8c9803aee328091b58fab324e4fad675945585808b4831d7bc3ff4def08e4b7a9de576d26586cec64b6116".to_string(),
+            "1ae10b594f09e26a7e902ecbd0600691".to_string()
+        ),
+    ]
+}
+
+fn xaead_test_vectors() -> Vec<(String, String, String, String, String, String)> {
+    // draft-irtf-cfrg-xchacha-03, section A.3.1.
+    vec![
+        (
+            "808182838485868788898a8b8c8d8e8f909192939495969798999a9b9c9d9e9f".to_string(),
+            "404142434445464748494a4b4c4d4e4f5051525354555657".to_string(),
+            "4c616469657320616e642047656e746c656d656e206f662074686520636c617373206f66202739393a204966204920636f756c64206f6666657220796f75206f6e6c79206f6e652074697020666f7220746865206675747572652c2073756e73637265656e20776f756c642062652069742e".to_string(),
+            "50515253c0c1c2c3c4c5c6c7".to_string(),
+            "bd6d179d3e83d43b9576579493c0e939572a1700252bfaccbed2902c21396cbb731c7f1b0b4aa6440bf3a82f4eda7e39ae64c6708c54c216cb96b72e1213b4522f8c9ba40db5d945b11b69b982c1bb9e3f3fac2bc369488f76b2383565d3fff921f9664c97637da9768812f615c68b13b52e".to_string(),
+            "c0875924c1c7987947deafd8780acf49".to_string()
+        ),
+    ]
+}
+
+#[test]
+fn poly1305_test_vectors() {
+    // RFC 8439, section 2.5.2.
+    let mut key = [0u8; 32];
+    key.copy_from_slice(&hex::decode("85d6be7857556d337f4452fe42d506a80103808afb0db2fd4abff6af4149f51b").unwrap());
+    let msg = hex::decode("43727970746f6772617068696320466f72756d2052657365617263682047726f7570").unwrap();
+    let tag = hex::decode("a8061dc1305136c6c22b8baf0c0127a9").unwrap();
+    assert_eq!(&poly1305(&key, &msg)[..], &tag[..])
+}
+
+#[test]
+fn hchacha20_test_vectors() {
+    // draft-irtf-cfrg-xchacha-03, section 2.2.1.
+    let key = hex::decode("000102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f").unwrap();
+    let nonce = hex::decode("000000090000004a0000000031415927").unwrap();
+    let subkey = hex::decode("82413b4227b27bfed30e42508a877d73a0f9e4d58a74a853c12ec41326d3ecdc").unwrap();
+    assert_eq!(&hchacha20(&key, &nonce).unwrap()[..], &subkey[..])
+}
+
+#[test]
+fn chacha20_poly1305_encrypt_test_vectors() {
+    for v in aead_test_vectors() {
+        let key = Key::from_hex(&v.0).unwrap();
+        let nonce = hex::decode(v.1).unwrap();
+        let plain = hex::decode(v.2).unwrap();
+        let ad = hex::decode(v.3).unwrap();
+        let mut cyph = hex::decode(v.4).unwrap();
+        cyph.extend_from_slice(&hex::decode(v.5).unwrap());
+        let res = ChaCha20Poly1305::new(key).unwrap().encrypt(&nonce, &ad, &plain).unwrap();
+        assert_eq!(res, cyph)
+    }
+}
+
+#[test]
+fn chacha20_poly1305_decrypt_test_vectors() {
+    for v in aead_test_vectors() {
+        let key = Key::from_hex(&v.0).unwrap();
+        let nonce = hex::decode(v.1).unwrap();
+        let plain = hex::decode(v.2).unwrap();
+        let ad = hex::decode(v.3).unwrap();
+        let mut cyph = hex::decode(v.4).unwrap();
+        cyph.extend_from_slice(&hex::decode(v.5).unwrap());
+        let res = ChaCha20Poly1305::new(key).unwrap().decrypt(&nonce, &ad, &cyph).unwrap();
+        assert_eq!(res, plain)
+    }
+}
+
+#[test]
+fn xchacha20_poly1305_encrypt_test_vectors() {
+    for v in xaead_test_vectors() {
+        let key = Key::from_hex(&v.0).unwrap();
+        let nonce = hex::decode(v.1).unwrap();
+        let plain = hex::decode(v.2).unwrap();
+        let ad = hex::decode(v.3).unwrap();
+        let mut cyph = hex::decode(v.4).unwrap();
+        cyph.extend_from_slice(&hex::decode(v.5).unwrap());
+        let res = XChaCha20Poly1305::new(key).unwrap().encrypt(&nonce, &ad, &plain).unwrap();
+        assert_eq!(res, cyph)
+    }
+}
+
+#[test]
+fn xchacha20_poly1305_decrypt_test_vectors() {
+    for v in xaead_test_vectors() {
+        let key = Key::from_hex(&v.0).unwrap();
+        let nonce = hex::decode(v.1).unwrap();
+        let plain = hex::decode(v.2).unwrap();
+        let ad = hex::decode(v.3).unwrap();
+        let mut cyph = hex::decode(v.4).unwrap();
+        cyph.extend_from_slice(&hex::decode(v.5).unwrap());
+        let res = XChaCha20Poly1305::new(key).unwrap().decrypt(&nonce, &ad, &cyph).unwrap();
+        assert_eq!(res, plain)
+    }
+}
+
+#[test]
+fn chacha20_poly1305_decrypt_fail() {
+    let key = Key::new();
+    let nonce = Random::bytes(12);
+    let plain = Random::bytes(100);
+    let mut cyph = ChaCha20Poly1305::new(key.clone()).unwrap().encrypt(&nonce, &[], &plain).unwrap();
+    cyph[0] ^= 1;
+    let res = ChaCha20Poly1305::new(key).unwrap().decrypt(&nonce, &[], &cyph);
+    assert_eq!(res.unwrap_err().kind(), ErrorKind::InvalidTag)
+}
+
+#[test]
+fn xchacha20_poly1305_decrypt_fail() {
+    let key = Key::new();
+    let nonce = Random::bytes(24);
+    let plain = Random::bytes(100);
+    let cyph = XChaCha20Poly1305::new(key.clone()).unwrap().encrypt(&nonce, &[], &plain).unwrap();
+    let wrong_nonce = Random::bytes(24);
+    let res = XChaCha20Poly1305::new(key).unwrap().decrypt(&wrong_nonce, &[], &cyph);
+    assert_eq!(res.unwrap_err().kind(), ErrorKind::InvalidTag)
+}
@@ -0,0 +1,108 @@
+// Copyright 2018 Yobicash Ltd. See the COPYRIGHT file at the top-level directory
+// of this distribution.
+//
+// Licensed under the MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>
+// and the Apache 2.0 license <LICENSE-APACHE or https://opensource.org/licenses/Apache-2.0>.
+// This file may not be copied, modified, or distributed except according to those
+// terms.
+
+//! Yobicrypto `cipher` module tests.
+
+extern crate yobicrypto;
+
+use yobicrypto::{Random, SecretKey, Key};
+use yobicrypto::{CipherAlgorithm, Cipher, Envelope, DEFAULT_CIPHER_ALGORITHM};
+use yobicrypto::{sym_encrypt, sym_encrypt_with_cipher, sym_decrypt, sym_decrypt_with_ad};
+use yobicrypto::{assym_encrypt_with_cipher, assym_decrypt};
+use yobicrypto::{seal_with_cipher, open};
+use yobicrypto::BinarySerialize;
+
+fn algorithms() -> Vec<CipherAlgorithm> {
+    vec![
+        CipherAlgorithm::AES256GCM,
+        CipherAlgorithm::ChaCha20Poly1305,
+        CipherAlgorithm::XChaCha20Poly1305,
+        CipherAlgorithm::AES256GCMSIV,
+    ]
+}
+
+#[test]
+fn cipher_algorithm_from_u8_succ() {
+    for algorithm in algorithms() {
+        let res = CipherAlgorithm::from_u8(algorithm.to_u8()).unwrap();
+        assert_eq!(res, algorithm)
+    }
+}
+
+#[test]
+fn cipher_algorithm_from_u8_fail() {
+    let res = CipherAlgorithm::from_u8(0);
+    assert!(res.is_err())
+}
+
+#[test]
+fn cipher_decrypt_succ() {
+    for algorithm in algorithms() {
+        let key = Key::new();
+        let cipher = Cipher::new(algorithm, key).unwrap();
+        assert_eq!(cipher.algorithm(), algorithm);
+        let nonce = Random::bytes(algorithm.nonce_size() as u32);
+        let plain_a = Random::bytes(100);
+        let cyph = cipher.encrypt(&nonce, &[], &plain_a).unwrap();
+        assert_eq!(cyph.len(), plain_a.len() + algorithm.tag_size());
+        let plain_b = cipher.decrypt(&nonce, &[], &cyph).unwrap();
+        assert_eq!(plain_a, plain_b)
+    }
+}
+
+#[test]
+fn sym_decrypt_with_cipher_succ() {
+    for algorithm in algorithms() {
+        let key = Key::new();
+        let plain_a = Random::bytes(100);
+        let ad = Random::bytes(16);
+        let cyph = sym_encrypt_with_cipher(algorithm, key.clone(), &ad, &plain_a).unwrap();
+        let envelope = Envelope::from_bytes(&cyph).unwrap();
+        assert_eq!(envelope.algorithm, algorithm);
+        let plain_b = sym_decrypt_with_ad(key, &ad, &cyph).unwrap();
+        assert_eq!(plain_a, plain_b)
+    }
+}
+
+#[test]
+fn assym_decrypt_with_cipher_succ() {
+    for algorithm in algorithms() {
+        let sk_a = SecretKey::random();
+        let sk_b = SecretKey::random();
+        let pk_a = sk_a.to_public();
+        let pk_b = sk_b.to_public();
+        let plain_a = Random::bytes(100);
+        let cyph = assym_encrypt_with_cipher(algorithm, sk_a, pk_b, &plain_a).unwrap();
+        let plain_b = assym_decrypt(sk_b, pk_a, &cyph).unwrap();
+        assert_eq!(plain_a, plain_b)
+    }
+}
+
+#[test]
+fn open_with_cipher_succ() {
+    for algorithm in algorithms() {
+        let sk = SecretKey::random();
+        let pk = sk.to_public();
+        let plain_a = Random::bytes(100);
+        let cyph = seal_with_cipher(algorithm, pk, &plain_a).unwrap();
+        let plain_b = open(sk, &cyph).unwrap();
+        assert_eq!(plain_a, plain_b)
+    }
+}
+
+#[test]
+fn cipher_default_succ() {
+    assert_eq!(CipherAlgorithm::default(), DEFAULT_CIPHER_ALGORITHM);
+    let key = Key::new();
+    let plain_a = Random::bytes(100);
+    let cyph = sym_encrypt(key.clone(), &plain_a).unwrap();
+    let envelope = Envelope::from_bytes(&cyph).unwrap();
+    assert_eq!(envelope.algorithm, DEFAULT_CIPHER_ALGORITHM);
+    let plain_b = sym_decrypt(key, &cyph).unwrap();
+    assert_eq!(plain_a, plain_b)
+}
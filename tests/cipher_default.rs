@@ -0,0 +1,58 @@
+// Copyright 2018 Yobicash Ltd. See the COPYRIGHT file at the top-level directory
+// of this distribution.
+//
+// Licensed under the MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>
+// and the Apache 2.0 license <LICENSE-APACHE or https://opensource.org/licenses/Apache-2.0>.
+// This file may not be copied, modified, or distributed except according to those
+// terms.
+
+//! Yobicrypto `cipher` module tests of the process-wide default cipher.
+//!
+//! NB: the default is set once per process, so these tests have their own binary
+//! and a single test function.
+
+extern crate yobicrypto;
+
+use yobicrypto::{ErrorKind, Random, SecretKey, Key};
+use yobicrypto::{CipherAlgorithm, Envelope};
+use yobicrypto::{sym_encrypt, sym_decrypt, assym_encrypt, assym_decrypt, seal, open};
+use yobicrypto::{StreamEncryptor, StreamDecryptor};
+use yobicrypto::BinarySerialize;
+
+#[test]
+fn cipher_set_default_succ() {
+    CipherAlgorithm::set_default(CipherAlgorithm::ChaCha20Poly1305).unwrap();
+    CipherAlgorithm::set_default(CipherAlgorithm::ChaCha20Poly1305).unwrap();
+    assert_eq!(CipherAlgorithm::default(), CipherAlgorithm::ChaCha20Poly1305);
+
+    let res = CipherAlgorithm::set_default(CipherAlgorithm::AES256GCM);
+    assert_eq!(res.unwrap_err().kind(), ErrorKind::AlreadyFound);
+    assert_eq!(CipherAlgorithm::default(), CipherAlgorithm::ChaCha20Poly1305);
+
+    let plain_a = Random::bytes(100);
+
+    let key = Key::new();
+    let cyph = sym_encrypt(key.clone(), &plain_a).unwrap();
+    let envelope = Envelope::from_bytes(&cyph).unwrap();
+    assert_eq!(envelope.algorithm, CipherAlgorithm::ChaCha20Poly1305);
+    assert_eq!(sym_decrypt(key, &cyph).unwrap(), plain_a);
+
+    let sk_a = SecretKey::random();
+    let sk_b = SecretKey::random();
+    let cyph = assym_encrypt(sk_a.clone(), sk_b.to_public(), &plain_a).unwrap();
+    let envelope = Envelope::from_bytes(&cyph).unwrap();
+    assert_eq!(envelope.algorithm, CipherAlgorithm::ChaCha20Poly1305);
+    assert_eq!(assym_decrypt(sk_b.clone(), sk_a.to_public(), &cyph).unwrap(), plain_a);
+
+    let cyph = seal(sk_b.to_public(), &plain_a).unwrap();
+    let envelope = Envelope::from_bytes(&cyph[32..]).unwrap();
+    assert_eq!(envelope.algorithm, CipherAlgorithm::ChaCha20Poly1305);
+    assert_eq!(open(sk_b, &cyph).unwrap(), plain_a);
+
+    let key = Key::new();
+    let encryptor = StreamEncryptor::new(key.clone(), Vec::new()).unwrap();
+    assert_eq!(encryptor.algorithm(), CipherAlgorithm::ChaCha20Poly1305);
+    let cyph = encryptor.finish().unwrap();
+    let decryptor = StreamDecryptor::new(key, &cyph[..]).unwrap();
+    assert_eq!(decryptor.algorithm(), CipherAlgorithm::ChaCha20Poly1305);
+}
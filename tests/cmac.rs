@@ -0,0 +1,117 @@
+// Copyright 2018 Yobicash Ltd. See the COPYRIGHT file at the top-level directory
+// of this distribution.
+//
+// Licensed under the MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>
+// and the Apache 2.0 license <LICENSE-APACHE or https://opensource.org/licenses/Apache-2.0>.
+// This file may not be copied, modified, or distributed except according to those
+// terms.
+
+//! Yobicrypto `cmac` module tests.
+
+extern crate yobicrypto;
+extern crate hex;
+
+use yobicrypto::{ErrorKind, Random, Key};
+use yobicrypto::HexSerialize;
+use yobicrypto::{AES256CMAC, cmac_aes256};
+
+// NIST SP 800-38B, appendix D.3
+fn cmac_test_vectors() -> Vec<(String, String, String)> {
+    let key = "603deb1015ca71be2b73aef0857d77811f352c073b6108d72d9810a30914dff4";
+
+    vec![
+        (
+            key.to_string(),
+            "".to_string(),
+            "028962f61b7bf89efc6b551f4667d983".to_string(),
+        ),
+        (
+            key.to_string(),
+            "6bc1bee22e409f96e93d7e117393172a".to_string(),
+            "28a7023f452e8f82bd4bf28d8c37c35c".to_string(),
+        ),
+        (
+            key.to_string(),
+            "6bc1bee22e409f96e93d7e117393172aae2d8a571e03ac9c9eb76fac45af8e5130c81c46a35ce411".to_string(),
+            "aaf3d8f1de5640c232f5b169b9c911e6".to_string(),
+        ),
+        (
+            key.to_string(),
+            "6bc1bee22e409f96e93d7e117393172aae2d8a571e03ac9c9eb76fac45af8e5130c81c46a35ce411e5fbc1191a0a52eff69f2445df4f9b17ad2b417be66c3710".to_string(),
+            "e1992190549f6ed5696a2c056c315410".to_string(),
+        ),
+    ]
+}
+
+#[test]
+fn cmac_test_vectors_succ() {
+    for v in cmac_test_vectors() {
+        let key = Key::from_hex(&v.0).unwrap();
+        let msg = hex::decode(&v.1).unwrap();
+        let tag = cmac_aes256(&key, &msg).unwrap();
+        assert_eq!(hex::encode(&tag), v.2)
+    }
+}
+
+#[test]
+fn cmac_update_succ() {
+    for v in cmac_test_vectors() {
+        let key = Key::from_hex(&v.0).unwrap();
+        let msg = hex::decode(&v.1).unwrap();
+
+        for chunk_size in 1..33 {
+            let mut cmac = AES256CMAC::new(&key).unwrap();
+            for chunk in msg.chunks(chunk_size) {
+                cmac.update(chunk).unwrap();
+            }
+            assert_eq!(hex::encode(&cmac.finalize().unwrap()), v.2);
+        }
+    }
+}
+
+#[test]
+fn cmac_verify_succ() {
+    let key = Key::new();
+    let msg = Random::bytes(100);
+    let tag = cmac_aes256(&key, &msg).unwrap();
+
+    let mut cmac = AES256CMAC::new(&key).unwrap();
+    cmac.update(&msg).unwrap();
+    assert!(cmac.verify(&tag).is_ok())
+}
+
+#[test]
+fn cmac_verify_fail() {
+    let key = Key::new();
+    let msg = Random::bytes(100);
+    let mut tag = cmac_aes256(&key, &msg).unwrap();
+    tag[0] ^= 1;
+
+    let mut cmac = AES256CMAC::new(&key).unwrap();
+    cmac.update(&msg).unwrap();
+    let res = cmac.verify(&tag);
+    assert_eq!(res.unwrap_err().kind(), ErrorKind::InvalidTag);
+
+    tag[0] ^= 1;
+    let mut cmac = AES256CMAC::new(&key).unwrap();
+    cmac.update(&msg).unwrap();
+    let res = cmac.verify(&tag[..15]);
+    assert_eq!(res.unwrap_err().kind(), ErrorKind::InvalidTag);
+
+    let mut cmac = AES256CMAC::new(&Key::new()).unwrap();
+    cmac.update(&msg).unwrap();
+    let res = cmac.verify(&tag);
+    assert_eq!(res.unwrap_err().kind(), ErrorKind::InvalidTag)
+}
+
+#[test]
+fn cmac_padding_succ() {
+    let key = Key::new();
+
+    // NB: a padded message differs from the one with the padding appended
+    let tag_a = cmac_aes256(&key, &[1u8; 15]).unwrap();
+    let mut padded = vec![1u8; 15];
+    padded.push(0x80);
+    let tag_b = cmac_aes256(&key, &padded).unwrap();
+    assert_ne!(tag_a, tag_b)
+}
@@ -10,14 +10,21 @@
 
 extern crate yobicrypto;
 extern crate hex;
+extern crate ctaes_sys;
 
-use yobicrypto::Random;
+use yobicrypto::{ErrorKind, Random};
 use yobicrypto::{SecretKey, PublicKey, Key};
+use yobicrypto::{BinarySerialize, HexSerialize};
+use yobicrypto::{AEADCipher, AES256GCM, GCM_TAG_SIZE};
 use yobicrypto::{sym_encrypt, sym_decrypt};
+use yobicrypto::{sym_encrypt_with_ad, sym_decrypt_with_ad};
 use yobicrypto::{assym_encrypt, assym_decrypt};
-use yobicrypto::HexSerialize;
+use yobicrypto::{sym_decrypt_legacy, assym_decrypt_legacy};
+use yobicrypto::{seal, open};
 
-fn test_vectors() -> Vec<(String, String, String)> {
+use ctaes_sys::{AES256GCMKey, AESGCM256, AESGCMCipher};
+
+fn legacy_test_vectors() -> Vec<(String, String, String)> {
     vec![
         (
             "000102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f".to_string(),
@@ -47,29 +54,158 @@ fn test_vectors() -> Vec<(String, String, String)> {
     ]
 }
 
+fn gcm_test_vectors() -> Vec<(String, String, String, String, String, String)> {
+    // NIST GCM test cases 13-16 (AES-256).
+    vec![
+        (
+            "0000000000000000000000000000000000000000000000000000000000000000".to_string(),
+            "000000000000000000000000".to_string(),
+            "".to_string(),
+            "".to_string(),
+            "".to_string(),
+            "530f8afbc74536b9a963b4f1c4cb738b".to_string()
+        ),
+        (
+            "0000000000000000000000000000000000000000000000000000000000000000".to_string(),
+            "000000000000000000000000".to_string(),
+            "00000000000000000000000000000000".to_string(),
+            "".to_string(),
+            "cea7403d4d606b6e074ec5d3baf39d18".to_string(),
+            "d0d1c8a799996bf0265b98b5d48ab919".to_string()
+        ),
+        (
+            "feffe9928665731c6d6a8f9467308308feffe9928665731c6d6a8f9467308308".to_string(),
+            "cafebabefacedbaddecaf888".to_string(),
+            "d9313225f88406e5a55909c5aff5269a86a7a9531534f7da2e4c303d8a318a721c3c0c95956809532fcf0e2449a6b525b16aedf5aa0de657ba637b391aafd255".to_string(),
+            "".to_string(),
+            "522dc1f099567d07f47f37a32a84427d643a8cdcbfe5c0c97598a2bd2555d1aa8cb08e48590dbb3da7b08b1056828838c5f61e6393ba7a0abcc9f662898015ad".to_string(),
+            "b094dac5d93471bdec1a502270e3cc6c".to_string()
+        ),
+        (
+            "feffe9928665731c6d6a8f9467308308feffe9928665731c6d6a8f9467308308".to_string(),
+            "cafebabefacedbaddecaf888".to_string(),
+            "d9313225f88406e5a55909c5aff5269a86a7a9531534f7da2e4c303d8a318a721c3c0c95956809532fcf0e2449a6b525b16aedf5aa0de657ba637b39".to_string(),
+            "feedfacedeadbeeffeedfacedeadbeefabaddad2".to_string(),
+            "522dc1f099567d07f47f37a32a84427d643a8cdcbfe5c0c97598a2bd2555d1aa8cb08e48590dbb3da7b08b1056828838c5f61e6393ba7a0abcc9f662".to_string(),
+            "76fc6ece0f4e1768cddf8853bb2d551b".to_string()
+        ),
+    ]
+}
+
 #[test]
-fn sym_encrypt_test_vectors() {
-    for v in test_vectors() {
+fn aes_gcm_encrypt_test_vectors() {
+    for v in gcm_test_vectors() {
         let key = Key::from_hex(&v.0).unwrap();
-        let plain = hex::decode(v.1).unwrap();
-        let res = sym_encrypt(key, &plain).unwrap();
-        let cyph = hex::decode(v.2).unwrap();
+        let nonce = hex::decode(v.1).unwrap();
+        let plain = hex::decode(v.2).unwrap();
+        let ad = hex::decode(v.3).unwrap();
+        let mut cyph = hex::decode(v.4).unwrap();
+        cyph.extend_from_slice(&hex::decode(v.5).unwrap());
+        let res = AES256GCM::new(key).unwrap().encrypt(&nonce, &ad, &plain).unwrap();
         assert_eq!(res, cyph)
     }
 }
 
 #[test]
-fn sym_decrypt_test_vectors() {
-    for v in test_vectors() {
+fn aes_gcm_decrypt_test_vectors() {
+    for v in gcm_test_vectors() {
+        let key = Key::from_hex(&v.0).unwrap();
+        let nonce = hex::decode(v.1).unwrap();
+        let plain = hex::decode(v.2).unwrap();
+        let ad = hex::decode(v.3).unwrap();
+        let mut cyph = hex::decode(v.4).unwrap();
+        cyph.extend_from_slice(&hex::decode(v.5).unwrap());
+        let res = AES256GCM::new(key).unwrap().decrypt(&nonce, &ad, &cyph).unwrap();
+        assert_eq!(res, plain)
+    }
+}
+
+#[test]
+fn aes_gcm_decrypt_fail() {
+    for v in gcm_test_vectors() {
+        let key = Key::from_hex(&v.0).unwrap();
+        let nonce = hex::decode(v.1).unwrap();
+        let ad = hex::decode(v.3).unwrap();
+        let mut cyph = hex::decode(v.4).unwrap();
+        cyph.extend_from_slice(&hex::decode(v.5).unwrap());
+        let last = cyph.len() - 1;
+        cyph[last] ^= 1;
+        let res = AES256GCM::new(key).unwrap().decrypt(&nonce, &ad, &cyph);
+        assert_eq!(res.unwrap_err().kind(), ErrorKind::InvalidTag)
+    }
+}
+
+#[test]
+fn sym_decrypt_succ() {
+    let key = Key::new();
+    let size = Random::u32_range(1..100).unwrap();
+    let plain_a = Random::bytes(size);
+    let cyph = sym_encrypt(key.clone(), &plain_a).unwrap();
+    let plain_b = sym_decrypt(key, &cyph).unwrap();
+    assert_eq!(plain_a, plain_b)
+}
+
+#[test]
+fn sym_decrypt_fail() {
+    let key = Key::new();
+    let size = Random::u32_range(1..100).unwrap();
+    let plain = Random::bytes(size);
+    let mut cyph = sym_encrypt(key.clone(), &plain).unwrap();
+    let last = cyph.len() - 1;
+    cyph[last] ^= 1;
+    let res = sym_decrypt(key, &cyph);
+    assert_eq!(res.unwrap_err().kind(), ErrorKind::InvalidTag)
+}
+
+#[test]
+fn sym_decrypt_with_ad_fail() {
+    let key = Key::new();
+    let size = Random::u32_range(1..100).unwrap();
+    let plain = Random::bytes(size);
+    let ad = Random::bytes(32);
+    let cyph = sym_encrypt_with_ad(key.clone(), &ad, &plain).unwrap();
+    let wrong_ad = Random::bytes(32);
+    let res = sym_decrypt_with_ad(key, &wrong_ad, &cyph);
+    assert_eq!(res.unwrap_err().kind(), ErrorKind::InvalidTag)
+}
+
+#[test]
+fn sym_encrypt_distinct_blocks() {
+    let key = Key::new();
+    let plain = [0u8; 32];
+    let cyph = sym_encrypt(key, &plain).unwrap();
+    let start = cyph.len() - GCM_TAG_SIZE - 32;
+    assert_ne!(&cyph[start..start+16], &cyph[start+16..start+32])
+}
+
+#[test]
+fn sym_decrypt_legacy_test_vectors() {
+    for v in legacy_test_vectors() {
         let key = Key::from_hex(&v.0).unwrap();
         let cyph = hex::decode(v.2).unwrap();
         let plain = hex::decode(v.1).unwrap();
         let size = plain.len() as u32;
-        let res = sym_decrypt(key, &cyph, size).unwrap();
+        let res = sym_decrypt_legacy(key, &cyph, size).unwrap();
         assert_eq!(res, plain)
     }
 }
 
+#[test]
+fn assym_decrypt_legacy_succ() {
+    let sk_a = SecretKey::random();
+    let sk_b = SecretKey::random();
+    let pk_a = sk_a.to_public();
+    let pk_b = sk_b.to_public();
+    let key = Key::shared(sk_a, pk_b).unwrap();
+    let size = 40;
+    let mut plain_a = Random::bytes(size);
+    plain_a.extend_from_slice(&[0u8; 8]);
+    let aes_key = *AES256GCMKey::from_slice(key.expose_secret());
+    let cyph = AESGCM256::new(aes_key).encrypt(&plain_a).unwrap();
+    let plain_b = assym_decrypt_legacy(sk_b, pk_a, &cyph, size).unwrap();
+    assert_eq!(&plain_a[..size as usize], &plain_b[..])
+}
+
 #[test]
 fn shared_key_succ() {
     let sk_a = SecretKey::random();
@@ -113,7 +249,7 @@ fn assym_decrypt_succ() {
     let size = Random::u32_range(1..100).unwrap();
     let plain_a = Random::bytes(size);
     let cyph = assym_encrypt(sk_a, pk_b, &plain_a).unwrap();
-    let plain_b = assym_decrypt(sk_b, pk_a, &cyph, size).unwrap();
+    let plain_b = assym_decrypt(sk_b, pk_a, &cyph).unwrap();
     assert_eq!(plain_a, plain_b)
 }
 
@@ -126,7 +262,94 @@ fn assym_decrypt_fail() {
     let size = Random::u32_range(1..100).unwrap();
     let plain_a = Random::bytes(size);
     let mut cyph = assym_encrypt(sk_a, pk_b, &plain_a).unwrap();
-    cyph[0] ^= cyph[0];
-    let plain_b = assym_decrypt(sk_b, pk_a, &cyph, size).unwrap();
-    assert_ne!(plain_a, plain_b)
+    let last = cyph.len() - 1;
+    cyph[last] ^= 1;
+    let res = assym_decrypt(sk_b, pk_a, &cyph);
+    assert!(res.is_err())
+}
+
+#[test]
+fn open_succ() {
+    let sk = SecretKey::random();
+    let pk = sk.to_public();
+    let size = Random::u32_range(0..100).unwrap();
+    let plain_a = Random::bytes(size);
+    let cyph = seal(pk, &plain_a).unwrap();
+    let plain_b = open(sk, &cyph).unwrap();
+    assert_eq!(plain_a, plain_b)
+}
+
+#[test]
+fn open_fail() {
+    let sk = SecretKey::random();
+    let pk = sk.to_public();
+    let plain = Random::bytes(64);
+    let cyph = seal(pk, &plain).unwrap();
+    let wrong_sk = SecretKey::random();
+    let res = open(wrong_sk, &cyph);
+    assert_eq!(res.unwrap_err().kind(), ErrorKind::InvalidTag);
+    let mut tampered = cyph.clone();
+    tampered[40] ^= 1;
+    assert!(open(sk, &tampered).is_err())
+}
+
+#[test]
+fn seal_ephemeral_keys() {
+    let sk = SecretKey::random();
+    let pk = sk.to_public();
+    let plain = Random::bytes(64);
+    let cyph_a = seal(pk, &plain).unwrap();
+    let cyph_b = seal(pk, &plain).unwrap();
+    assert_ne!(&cyph_a[..32], &cyph_b[..32]);
+    assert_ne!(&cyph_a[32..], &cyph_b[32..])
+}
+
+#[test]
+fn secret_key_redacted() {
+    let sk = SecretKey::random();
+    let sk_hex = hex::encode(sk.expose_secret());
+    assert!(!format!("{:?}", sk).contains(&sk_hex));
+    assert!(!format!("{}", sk).contains(&sk_hex));
+}
+
+#[test]
+fn secret_key_expose_secret_succ() {
+    let sk_a = SecretKey::random();
+    let sk_b = SecretKey::from_bytes(sk_a.expose_secret()).unwrap();
+    assert_eq!(sk_a, sk_b);
+    assert_ne!(sk_a, SecretKey::random())
+}
+
+#[test]
+fn key_redacted() {
+    let key = Key::new();
+    let key_hex = hex::encode(key.expose_secret());
+    assert!(!format!("{:?}", key).contains(&key_hex));
+    assert!(!format!("{}", key).contains(&key_hex));
+}
+
+#[test]
+fn key_expose_secret_succ() {
+    let key_a = Key::new();
+    let key_b = Key::from_bytes(key_a.expose_secret()).unwrap();
+    assert_eq!(key_a, key_b);
+    assert_ne!(key_a, Key::new())
+}
+
+#[test]
+fn secret_key_to_bytes_succ() {
+    let sk_a = SecretKey::random();
+    let sk_b = SecretKey::from_bytes(&sk_a.to_bytes().unwrap()).unwrap();
+    assert_eq!(sk_a, sk_b);
+    let sk_c = SecretKey::from_hex(&sk_a.to_hex().unwrap()).unwrap();
+    assert_eq!(sk_a, sk_c)
+}
+
+#[test]
+fn key_to_bytes_succ() {
+    let key_a = Key::new();
+    let key_b = Key::from_bytes(&key_a.to_bytes().unwrap()).unwrap();
+    assert_eq!(key_a, key_b);
+    let key_c = Key::from_hex(&key_a.to_hex().unwrap()).unwrap();
+    assert_eq!(key_a, key_c)
 }
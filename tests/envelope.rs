@@ -0,0 +1,98 @@
+// Copyright 2018 Yobicash Ltd. See the COPYRIGHT file at the top-level directory
+// of this distribution.
+//
+// Licensed under the MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>
+// and the Apache 2.0 license <LICENSE-APACHE or https://opensource.org/licenses/Apache-2.0>.
+// This file may not be copied, modified, or distributed except according to those
+// terms.
+
+//! Yobicrypto `envelope` module tests.
+
+extern crate yobicrypto;
+
+use yobicrypto::{ErrorKind, Random, Key};
+use yobicrypto::{Envelope, ENVELOPE_VERSION};
+use yobicrypto::{Validate, BinarySerialize, HexSerialize};
+
+#[test]
+fn envelope_decrypt_succ() {
+    let key = Key::new();
+    let size = Random::u32_range(0..100).unwrap();
+    let plain_a = Random::bytes(size);
+    let ad = Random::bytes(16);
+    let envelope = Envelope::encrypt(key.clone(), &ad, &plain_a).unwrap();
+    assert_eq!(envelope.version, ENVELOPE_VERSION);
+    assert_eq!(envelope.length, size);
+    let plain_b = envelope.decrypt(key, &ad).unwrap();
+    assert_eq!(plain_a, plain_b)
+}
+
+#[test]
+fn envelope_decrypt_fail() {
+    let key = Key::new();
+    let plain = Random::bytes(64);
+    let envelope = Envelope::encrypt(key, &[], &plain).unwrap();
+    let wrong_key = Key::new();
+    let res = envelope.decrypt(wrong_key, &[]);
+    assert_eq!(res.unwrap_err().kind(), ErrorKind::InvalidTag)
+}
+
+#[test]
+fn envelope_header_tampered_fail() {
+    let key = Key::new();
+    let plain = Random::bytes(64);
+    let envelope = Envelope::encrypt(key.clone(), &[], &plain).unwrap();
+    let mut tampered = envelope.clone();
+    tampered.nonce[0] ^= 1;
+    assert!(tampered.decrypt(key.clone(), &[]).is_err());
+    let mut truncated = envelope.clone();
+    truncated.length -= 1;
+    truncated.cyphertext.remove(0);
+    assert!(truncated.decrypt(key, &[]).is_err())
+}
+
+#[test]
+fn envelope_max_length_fail() {
+    let key = Key::new();
+    let plain = Random::bytes(64);
+    let mut envelope = Envelope::encrypt(key.clone(), &[], &plain).unwrap();
+    envelope.length = u32::max_value();
+    let res = envelope.validate();
+    assert_eq!(res.unwrap_err().kind(), ErrorKind::InvalidLength);
+    let res = envelope.decrypt(key, &[]);
+    assert_eq!(res.unwrap_err().kind(), ErrorKind::InvalidLength)
+}
+
+#[test]
+fn envelope_to_bytes_succ() {
+    let key = Key::new();
+    let plain = Random::bytes(64);
+    let envelope_a = Envelope::encrypt(key, &[], &plain).unwrap();
+    let envelope_buf = envelope_a.to_bytes().unwrap();
+    let envelope_b = Envelope::from_bytes(&envelope_buf).unwrap();
+    assert_eq!(envelope_a, envelope_b)
+}
+
+#[test]
+fn envelope_from_bytes_fail() {
+    let key = Key::new();
+    let plain = Random::bytes(64);
+    let envelope = Envelope::encrypt(key, &[], &plain).unwrap();
+    let mut envelope_buf = envelope.to_bytes().unwrap();
+    envelope_buf[0] ^= 1;
+    let res = Envelope::from_bytes(&envelope_buf);
+    assert_eq!(res.unwrap_err().kind(), ErrorKind::InvalidFormat);
+    let envelope_buf = envelope.to_bytes().unwrap();
+    let res = Envelope::from_bytes(&envelope_buf[..envelope_buf.len()-1]);
+    assert_eq!(res.unwrap_err().kind(), ErrorKind::InvalidLength)
+}
+
+#[test]
+fn envelope_to_hex_succ() {
+    let key = Key::new();
+    let plain = Random::bytes(64);
+    let envelope_a = Envelope::encrypt(key, &[], &plain).unwrap();
+    let envelope_str = envelope_a.to_hex().unwrap();
+    let envelope_b = Envelope::from_hex(&envelope_str).unwrap();
+    assert_eq!(envelope_a, envelope_b)
+}
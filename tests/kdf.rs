@@ -0,0 +1,141 @@
+// Copyright 2018 Yobicash Ltd. See the COPYRIGHT file at the top-level directory
+// of this distribution.
+//
+// Licensed under the MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>
+// and the Apache 2.0 license <LICENSE-APACHE or https://opensource.org/licenses/Apache-2.0>.
+// This file may not be copied, modified, or distributed except according to those
+// terms.
+
+//! Yobicrypto `kdf` module tests.
+
+extern crate yobicrypto;
+extern crate hex;
+
+use yobicrypto::{SecretKey, X25519SecretKey};
+use yobicrypto::{hmac_sha512, hkdf_extract, hkdf_expand, hkdf_sha512};
+use yobicrypto::SharedSecret;
+
+// RFC 4231, test cases 1, 2 and 6
+fn hmac_test_vectors() -> Vec<(String, String, String)> {
+    vec![
+        (
+            "0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b".to_string(),
+            "4869205468657265".to_string(),
+            "87aa7cdea5ef619d4ff0b4241a1d6cb02379f4e2ce4ec2787ad0b30545e17cdedaa833b7d6b8a702038b274eaea3f4e4be9d914eeb61f1702e696c203a126854".to_string(),
+        ),
+        (
+            "4a656665".to_string(),
+            "7768617420646f2079612077616e7420666f72206e6f7468696e673f".to_string(),
+            "164b7a7bfcf819e2e395fbe73b56e0a387bd64222e831fd610270cd7ea2505549758bf75c05a994a6d034f65f8f0e6fdcaeab1a34d4a6b4b636e070a38bce737".to_string(),
+        ),
+        (
+            "aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa".to_string(),
+            "54657374205573696e67204c6172676572205468616e20426c6f636b2d53697a65204b6579202d2048617368204b6579204669727374".to_string(),
+            "80b24263c7c1a3ebb71493c1dd7be8b49b46d1f41b4aeec1121b013783f8f3526b56d037e05f2598bd0fd2215d6a1e5295e64f73f63f0aec8b915a985d786598".to_string(),
+        ),
+    ]
+}
+
+// RFC 5869 inputs, test cases 1, 2 and 3, with SHA512
+fn hkdf_test_vectors() -> Vec<(String, String, String, usize, String, String)> {
+    vec![
+        (
+            "000102030405060708090a0b0c".to_string(),
+            "0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b".to_string(),
+            "f0f1f2f3f4f5f6f7f8f9".to_string(),
+            42,
+            "665799823737ded04a88e47e54a5890bb2c3d247c7a4254a8e61350723590a26c36238127d8661b88cf80ef802d57e2f7cebcf1e00e083848be19929c61b4237".to_string(),
+            "832390086cda71fb47625bb5ceb168e4c8e26a1a16ed34d9fc7fe92c1481579338da362cb8d9f925d7cb".to_string(),
+        ),
+        (
+            "606162636465666768696a6b6c6d6e6f707172737475767778797a7b7c7d7e7f808182838485868788898a8b8c8d8e8f909192939495969798999a9b9c9d9e9fa0a1a2a3a4a5a6a7a8a9aaabacadaeaf".to_string(),
+            "000102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f202122232425262728292a2b2c2d2e2f303132333435363738393a3b3c3d3e3f404142434445464748494a4b4c4d4e4f".to_string(),
+            "b0b1b2b3b4b5b6b7b8b9babbbcbdbebfc0c1c2c3c4c5c6c7c8c9cacbcccdcecfd0d1d2d3d4d5d6d7d8d9dadbdcdddedfe0e1e2e3e4e5e6e7e8e9eaebecedeeeff0f1f2f3f4f5f6f7f8f9fafbfcfdfeff".to_string(),
+            82,
+            "35672542907d4e142c00e84499e74e1de08be86535f924e022804ad775dde27ec86cd1e5b7d178c74489bdbeb30712beb82d4f97416c5a94ea81ebdf3e629e4a".to_string(),
+            "ce6c97192805b346e6161e821ed165673b84f400a2b514b2fe23d84cd189ddf1b695b48cbd1c8388441137b3ce28f16aa64ba33ba466b24df6cfcb021ecff235f6a2056ce3af1de44d572097a8505d9e7a93".to_string(),
+        ),
+        (
+            "".to_string(),
+            "0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b".to_string(),
+            "".to_string(),
+            42,
+            "fd200c4987ac491313bd4a2a13287121247239e11c9ef82802044b66ef357e5b194498d0682611382348572a7b1611de54764094286320578a863f36562b0df6".to_string(),
+            "f5fa02b18298a72a8c23898a8703472c6eb179dc204c03425c970e3b164bf90fff22d04836d0e2343bac".to_string(),
+        ),
+    ]
+}
+
+#[test]
+fn hmac_sha512_test_vectors_succ() {
+    for v in hmac_test_vectors() {
+        let key = hex::decode(&v.0).unwrap();
+        let msg = hex::decode(&v.1).unwrap();
+        let mac = hmac_sha512(&key, &msg);
+        assert_eq!(hex::encode(&mac[..]), v.2)
+    }
+}
+
+#[test]
+fn hkdf_sha512_test_vectors_succ() {
+    for v in hkdf_test_vectors() {
+        let salt = hex::decode(&v.0).unwrap();
+        let ikm = hex::decode(&v.1).unwrap();
+        let info = hex::decode(&v.2).unwrap();
+        let prk = hkdf_extract(&salt, &ikm);
+        assert_eq!(hex::encode(&prk[..]), v.4);
+        let okm = hkdf_expand(&prk, &info, v.3).unwrap();
+        assert_eq!(hex::encode(&okm), v.5);
+        let okm = hkdf_sha512(&salt, &ikm, &info, v.3).unwrap();
+        assert_eq!(hex::encode(&okm), v.5)
+    }
+}
+
+#[test]
+fn hkdf_expand_fail() {
+    let res = hkdf_expand(&[0u8; 64], &[], 255 * 64 + 1);
+    assert!(res.is_err())
+}
+
+#[test]
+fn shared_secret_derive_succ() {
+    let sk_a = SecretKey::random();
+    let sk_b = SecretKey::random();
+    let secret_a = SharedSecret::new(sk_a.clone(), sk_b.to_public(), b"yobicash-test").unwrap();
+    let secret_b = SharedSecret::new(sk_b, sk_a.to_public(), b"yobicash-test").unwrap();
+    assert_eq!(secret_a.derive("encryption").unwrap(), secret_b.derive("encryption").unwrap());
+    assert_eq!(secret_a.derive_bytes("iv", 12).unwrap(), secret_b.derive_bytes("iv", 12).unwrap());
+    assert_ne!(secret_a.derive("encryption").unwrap(), secret_a.derive("mac").unwrap())
+}
+
+#[test]
+fn shared_secret_directions_succ() {
+    let sk_a = SecretKey::random();
+    let sk_b = SecretKey::random();
+    let secret_a = SharedSecret::new(sk_a.clone(), sk_b.to_public(), b"yobicash-test").unwrap();
+    let secret_b = SharedSecret::new(sk_b, sk_a.to_public(), b"yobicash-test").unwrap();
+    assert_eq!(secret_a.send_key("encryption").unwrap(), secret_b.recv_key("encryption").unwrap());
+    assert_eq!(secret_a.recv_key("encryption").unwrap(), secret_b.send_key("encryption").unwrap());
+    assert_ne!(secret_a.send_key("encryption").unwrap(), secret_a.recv_key("encryption").unwrap());
+    assert_ne!(secret_a.send_key("encryption").unwrap(), secret_a.derive("encryption").unwrap())
+}
+
+#[test]
+fn shared_secret_context_succ() {
+    let sk_a = SecretKey::random();
+    let sk_b = SecretKey::random();
+    let secret_a = SharedSecret::new(sk_a.clone(), sk_b.to_public(), b"yobicash-test-a").unwrap();
+    let secret_b = SharedSecret::new(sk_b, sk_a.to_public(), b"yobicash-test-b").unwrap();
+    assert_ne!(secret_a.derive("encryption").unwrap(), secret_b.derive("encryption").unwrap())
+}
+
+#[test]
+fn shared_secret_x25519_succ() {
+    let sk_a = X25519SecretKey::random();
+    let sk_b = X25519SecretKey::random();
+    let secret_a = SharedSecret::x25519(sk_a.clone(), sk_b.to_public(), b"yobicash-test").unwrap();
+    let secret_b = SharedSecret::x25519(sk_b, sk_a.to_public(), b"yobicash-test").unwrap();
+    assert_eq!(secret_a.derive("encryption").unwrap(), secret_b.derive("encryption").unwrap());
+    assert_eq!(secret_a.send_key("encryption").unwrap(), secret_b.recv_key("encryption").unwrap());
+    assert_ne!(secret_a.send_key("encryption").unwrap(), secret_b.send_key("encryption").unwrap())
+}
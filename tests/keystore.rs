@@ -0,0 +1,181 @@
+// Copyright 2018 Yobicash Ltd. See the COPYRIGHT file at the top-level directory
+// of this distribution.
+//
+// Licensed under the MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>
+// and the Apache 2.0 license <LICENSE-APACHE or https://opensource.org/licenses/Apache-2.0>.
+// This file may not be copied, modified, or distributed except according to those
+// terms.
+
+//! Yobicrypto `keystore` module tests.
+
+extern crate yobicrypto;
+
+use yobicrypto::{ErrorKind, Random, BalloonParams, SecretKey};
+use yobicrypto::{Keystore, KeystoreEntry};
+use yobicrypto::BinarySerialize;
+use yobicrypto::PASSPHRASE_MIN_S_COST;
+
+use std::env;
+use std::fs;
+
+fn params() -> BalloonParams {
+    BalloonParams::new(16, 2, 3).unwrap()
+}
+
+#[test]
+fn keystore_entry_decrypt_succ() {
+    let sk_a = SecretKey::random();
+    let entry = KeystoreEntry::new("main", sk_a.clone(), b"passphrase", params()).unwrap();
+    assert_eq!(entry.fingerprint, sk_a.to_public().fingerprint().unwrap());
+    let sk_b = entry.decrypt(b"passphrase").unwrap();
+    assert_eq!(sk_a, sk_b)
+}
+
+#[test]
+fn keystore_entry_decrypt_fail() {
+    let sk = SecretKey::random();
+    let entry = KeystoreEntry::new("main", sk, b"passphrase", params()).unwrap();
+    let res = entry.decrypt(b"wrong passphrase");
+    assert!(res.is_err());
+    assert_eq!(res.err().unwrap().kind(), ErrorKind::WrongPassphrase)
+}
+
+#[test]
+fn keystore_entry_tampered_fail() {
+    let sk = SecretKey::random();
+    let mut entry = KeystoreEntry::new("main", sk, b"passphrase", params()).unwrap();
+    entry.cyphertext[0] ^= 1;
+    let res = entry.decrypt(b"passphrase");
+    assert!(res.is_err())
+}
+
+#[test]
+fn keystore_add_succ() {
+    let sk_a = SecretKey::random();
+    let sk_b = SecretKey::random();
+    let mut keystore = Keystore::new();
+    keystore.add("a", sk_a.clone(), b"passphrase a", params()).unwrap();
+    keystore.add("b", sk_b.clone(), b"passphrase b", params()).unwrap();
+    assert_eq!(keystore.labels(), vec!["a".to_string(), "b".to_string()]);
+    assert_eq!(keystore.get("a", b"passphrase a").unwrap(), sk_a);
+    assert_eq!(keystore.get("b", b"passphrase b").unwrap(), sk_b)
+}
+
+#[test]
+fn keystore_add_fail() {
+    let mut keystore = Keystore::new();
+    keystore.add("a", SecretKey::random(), b"passphrase", params()).unwrap();
+    let res = keystore.add("a", SecretKey::random(), b"passphrase", params());
+    assert!(res.is_err());
+    assert_eq!(res.err().unwrap().kind(), ErrorKind::AlreadyFound)
+}
+
+#[test]
+fn keystore_remove_succ() {
+    let mut keystore = Keystore::new();
+    keystore.add("a", SecretKey::random(), b"passphrase", params()).unwrap();
+    keystore.remove("a").unwrap();
+    assert!(keystore.labels().is_empty());
+    let res = keystore.remove("a");
+    assert!(res.is_err());
+    assert_eq!(res.err().unwrap().kind(), ErrorKind::NotFound)
+}
+
+#[test]
+fn keystore_rename_succ() {
+    let sk = SecretKey::random();
+    let mut keystore = Keystore::new();
+    keystore.add("a", sk.clone(), b"passphrase", params()).unwrap();
+    keystore.rename("a", "b").unwrap();
+    assert_eq!(keystore.labels(), vec!["b".to_string()]);
+    assert_eq!(keystore.get("b", b"passphrase").unwrap(), sk)
+}
+
+#[test]
+fn keystore_rename_fail() {
+    let mut keystore = Keystore::new();
+    keystore.add("a", SecretKey::random(), b"passphrase", params()).unwrap();
+    keystore.add("b", SecretKey::random(), b"passphrase", params()).unwrap();
+    let res = keystore.rename("a", "b");
+    assert!(res.is_err());
+    let res = keystore.rename("c", "d");
+    assert!(res.is_err())
+}
+
+#[test]
+fn keystore_to_bytes_succ() {
+    let mut keystore_a = Keystore::new();
+    keystore_a.add("a", SecretKey::random(), b"passphrase", params()).unwrap();
+    let buf = keystore_a.to_bytes().unwrap();
+    let keystore_b = Keystore::from_bytes(&buf).unwrap();
+    assert_eq!(keystore_a, keystore_b)
+}
+
+#[test]
+fn keystore_save_succ() {
+    let sk = SecretKey::random();
+    let mut keystore_a = Keystore::new();
+    keystore_a.add("a", sk.clone(), b"passphrase", params()).unwrap();
+    let mut path = env::temp_dir();
+    path.push(format!("yobicrypto-keystore-{}", Random::u64()));
+    keystore_a.save(&path).unwrap();
+    let keystore_b = Keystore::load(&path).unwrap();
+    fs::remove_file(&path).unwrap();
+    assert_eq!(keystore_a, keystore_b);
+    assert_eq!(keystore_b.get("a", b"passphrase").unwrap(), sk)
+}
+
+#[test]
+fn keystore_save_replace_succ() {
+    let mut dir = env::temp_dir();
+    dir.push(format!("yobicrypto-keystore-dir-{}", Random::u64()));
+    fs::create_dir(&dir).unwrap();
+    let mut path = dir.clone();
+    path.push("keystore");
+
+    let mut keystore_a = Keystore::new();
+    keystore_a.add("a", SecretKey::random(), b"passphrase", params()).unwrap();
+    keystore_a.save(&path).unwrap();
+    keystore_a.add("b", SecretKey::random(), b"passphrase", params()).unwrap();
+    keystore_a.save(&path).unwrap();
+
+    let keystore_b = Keystore::load(&path).unwrap();
+    let files = fs::read_dir(&dir).unwrap().count();
+    let mode = permissions_mode(&path);
+    fs::remove_dir_all(&dir).unwrap();
+
+    assert_eq!(keystore_a, keystore_b);
+    assert_eq!(files, 1);
+    if let Some(mode) = mode {
+        assert_eq!(mode & 0o777, 0o600)
+    }
+}
+
+#[cfg(unix)]
+fn permissions_mode(path: &std::path::Path) -> Option<u32> {
+    use std::os::unix::fs::PermissionsExt;
+    Some(fs::metadata(path).unwrap().permissions().mode())
+}
+
+#[cfg(not(unix))]
+fn permissions_mode(_path: &std::path::Path) -> Option<u32> {
+    None
+}
+
+#[test]
+fn keystore_from_bytes_params_fail() {
+    let s_costs = vec![u32::max_value(), PASSPHRASE_MIN_S_COST - 1];
+
+    for s_cost in s_costs {
+        let mut keystore = Keystore::new();
+        keystore.add("a", SecretKey::random(), b"passphrase", params()).unwrap();
+        keystore.entries[0].params.s_cost = s_cost;
+        let buf = keystore.to_bytes().unwrap();
+        let res = Keystore::from_bytes(&buf);
+        assert!(res.is_err());
+        assert_eq!(res.unwrap_err().kind(), ErrorKind::OutOfBound);
+        let res = keystore.get("a", b"passphrase");
+        assert!(res.is_err());
+        assert_eq!(res.unwrap_err().kind(), ErrorKind::OutOfBound)
+    }
+}
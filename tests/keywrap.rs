@@ -0,0 +1,183 @@
+// Copyright 2018 Yobicash Ltd. See the COPYRIGHT file at the top-level directory
+// of this distribution.
+//
+// Licensed under the MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>
+// and the Apache 2.0 license <LICENSE-APACHE or https://opensource.org/licenses/Apache-2.0>.
+// This file may not be copied, modified, or distributed except according to those
+// terms.
+
+//! Yobicrypto `keywrap` module tests.
+
+extern crate yobicrypto;
+extern crate hex;
+
+use yobicrypto::{ErrorKind, Random, Key};
+use yobicrypto::HexSerialize;
+use yobicrypto::{aes_key_wrap, aes_key_unwrap, aes_key_wrap_pad, aes_key_unwrap_pad};
+use yobicrypto::{wrap_key, unwrap_key};
+
+// RFC 3394, sections 4.3, 4.5 and 4.6
+fn key_wrap_test_vectors() -> Vec<(String, String, String)> {
+    vec![
+        (
+            "000102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f".to_string(),
+            "00112233445566778899aabbccddeeff".to_string(),
+            "64e8c3f9ce0f5ba263e9777905818a2a93c8191e7d6e8ae7".to_string(),
+        ),
+        (
+            "000102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f".to_string(),
+            "00112233445566778899aabbccddeeff0001020304050607".to_string(),
+            "a8f9bc1612c68b3ff6e6f4fbe30e71e4769c8b80a32cb8958cd5d17d6b254da1".to_string(),
+        ),
+        (
+            "000102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f".to_string(),
+            "00112233445566778899aabbccddeeff000102030405060708090a0b0c0d0e0f".to_string(),
+            "28c9f404c4b810f4cbccb35cfb87f8263f5786e2d80ed326cbc7f0e71a99f43bfb988b9b7a02dd21".to_string(),
+        ),
+    ]
+}
+
+// NIST SP 800-38F KWP-AE vectors with a 256 bits key-encryption key, as the
+// RFC 5649 vectors use a 192 bits one
+fn key_wrap_pad_test_vectors() -> Vec<(String, String, String)> {
+    vec![
+        (
+            "6d60c0d0941cf3750b864c6f1fa580ae074c00edeb386f9fc299178a70fcccd1".to_string(),
+            "6b54a0".to_string(),
+            "24255140b4a9f8a9e35b9da2bfa0e0c3".to_string(),
+        ),
+        (
+            "eb950b844b97145a594b7f91aa81844045874aaa46db522cf91144f63a6fed37".to_string(),
+            "a4ce3f7d7c49b11a".to_string(),
+            "f5939d472407e28ee6d7269fa75dac88".to_string(),
+        ),
+        (
+            "314a549913256a71c6348eaab9b85efc755fe736568f0dbc9f6f8bc3ca3d12ee".to_string(),
+            "3b700e9682275d8dbe61ca7c1ec900e8".to_string(),
+            "70c684c49112ad8b8c3e13b99992127b58dcb9b59ce5c3fd".to_string(),
+        ),
+        (
+            "f2882a99e67fd1f0e024d2e973ee55bf2ae94d6798bc3b3a7ef94bfc9197a7f6".to_string(),
+            "13cdd6837c4c40fde0b9ec150093713771ac".to_string(),
+            "d096d3702ea4252da0d36666d01f1f450bcd26c87814a8041f8eefd229ec4828".to_string(),
+        ),
+    ]
+}
+
+#[test]
+fn aes_key_wrap_test_vectors_succ() {
+    for v in key_wrap_test_vectors() {
+        let kek = Key::from_hex(&v.0).unwrap();
+        let plain = hex::decode(&v.1).unwrap();
+        let cyph = aes_key_wrap(&kek, &plain).unwrap();
+        assert_eq!(hex::encode(&cyph), v.2);
+        assert_eq!(aes_key_unwrap(&kek, &cyph).unwrap(), plain)
+    }
+}
+
+#[test]
+fn aes_key_wrap_pad_test_vectors_succ() {
+    for v in key_wrap_pad_test_vectors() {
+        let kek = Key::from_hex(&v.0).unwrap();
+        let plain = hex::decode(&v.1).unwrap();
+        let cyph = aes_key_wrap_pad(&kek, &plain).unwrap();
+        assert_eq!(hex::encode(&cyph), v.2);
+        assert_eq!(aes_key_unwrap_pad(&kek, &cyph).unwrap(), plain)
+    }
+}
+
+#[test]
+fn aes_key_wrap_pad_succ() {
+    let kek = Key::new();
+
+    for len in 1..65 {
+        let plain = Random::bytes(len);
+        let cyph = aes_key_wrap_pad(&kek, &plain).unwrap();
+        assert_eq!(cyph.len(), 8 + (len as usize + 7) / 8 * 8);
+        assert_eq!(aes_key_unwrap_pad(&kek, &cyph).unwrap(), plain)
+    }
+}
+
+#[test]
+fn aes_key_wrap_length_fail() {
+    let kek = Key::new();
+
+    for len in vec![0, 8, 17, 31] {
+        let res = aes_key_wrap(&kek, &vec![0u8; len]);
+        assert_eq!(res.unwrap_err().kind(), ErrorKind::InvalidLength);
+    }
+
+    for len in vec![0, 16, 25] {
+        let res = aes_key_unwrap(&kek, &vec![0u8; len]);
+        assert_eq!(res.unwrap_err().kind(), ErrorKind::InvalidLength);
+    }
+
+    let res = aes_key_wrap_pad(&kek, &[]);
+    assert_eq!(res.unwrap_err().kind(), ErrorKind::InvalidLength);
+
+    for len in vec![0, 8, 17] {
+        let res = aes_key_unwrap_pad(&kek, &vec![0u8; len]);
+        assert_eq!(res.unwrap_err().kind(), ErrorKind::InvalidLength);
+    }
+}
+
+#[test]
+fn aes_key_unwrap_integrity_fail() {
+    for v in key_wrap_test_vectors() {
+        let kek = Key::from_hex(&v.0).unwrap();
+        let mut cyph = hex::decode(&v.2).unwrap();
+        cyph[10] ^= 1;
+        let res = aes_key_unwrap(&kek, &cyph);
+        assert_eq!(res.unwrap_err().kind(), ErrorKind::IntegrityFailure);
+
+        cyph[10] ^= 1;
+        let res = aes_key_unwrap(&Key::new(), &cyph);
+        assert_eq!(res.unwrap_err().kind(), ErrorKind::IntegrityFailure)
+    }
+}
+
+#[test]
+fn aes_key_unwrap_pad_integrity_fail() {
+    for v in key_wrap_pad_test_vectors() {
+        let kek = Key::from_hex(&v.0).unwrap();
+        let mut cyph = hex::decode(&v.2).unwrap();
+        cyph[3] ^= 1;
+        let res = aes_key_unwrap_pad(&kek, &cyph);
+        assert_eq!(res.unwrap_err().kind(), ErrorKind::IntegrityFailure);
+
+        cyph[3] ^= 1;
+        let res = aes_key_unwrap_pad(&Key::new(), &cyph);
+        assert_eq!(res.unwrap_err().kind(), ErrorKind::IntegrityFailure)
+    }
+}
+
+#[test]
+fn aes_key_unwrap_pad_wrong_format_fail() {
+    let kek = Key::new();
+
+    // NB: a RFC 3394 wrapping does not have the RFC 5649 initial value
+    let cyph = aes_key_wrap(&kek, &[7u8; 16]).unwrap();
+    let res = aes_key_unwrap_pad(&kek, &cyph);
+    assert_eq!(res.unwrap_err().kind(), ErrorKind::IntegrityFailure);
+
+    let cyph = aes_key_wrap_pad(&kek, &[7u8; 16]).unwrap();
+    let res = aes_key_unwrap(&kek, &cyph);
+    assert_eq!(res.unwrap_err().kind(), ErrorKind::IntegrityFailure)
+}
+
+#[test]
+fn wrap_key_succ() {
+    let kek = Key::new();
+    let key = Key::new();
+    let cyph = wrap_key(&kek, &key).unwrap();
+    assert_eq!(cyph.len(), 40);
+    assert_eq!(unwrap_key(&kek, &cyph).unwrap(), key)
+}
+
+#[test]
+fn unwrap_key_fail() {
+    let kek = Key::new();
+    let cyph = wrap_key(&kek, &Key::new()).unwrap();
+    let res = unwrap_key(&Key::new(), &cyph);
+    assert_eq!(res.unwrap_err().kind(), ErrorKind::IntegrityFailure)
+}
@@ -0,0 +1,48 @@
+// Copyright 2018 Yobicash Ltd. See the COPYRIGHT file at the top-level directory
+// of this distribution.
+//
+// Licensed under the MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>
+// and the Apache 2.0 license <LICENSE-APACHE or https://opensource.org/licenses/Apache-2.0>.
+// This file may not be copied, modified, or distributed except according to those
+// terms.
+
+//! Yobicrypto `locked` module tests.
+
+extern crate yobicrypto;
+
+use yobicrypto::Locked;
+use yobicrypto::{SecretKey, Key};
+use yobicrypto::{sym_encrypt, sym_decrypt};
+use yobicrypto::Random;
+
+#[test]
+fn locked_secret_key_succ() {
+    let sk = SecretKey::random();
+    let pk = sk.to_public();
+
+    let locked = Locked::new(sk).unwrap();
+    assert_eq!(locked.to_public(), pk);
+}
+
+#[test]
+fn locked_key_succ() {
+    let locked = Locked::new(Key::new()).unwrap();
+
+    let plain_a = Random::bytes(64);
+    let cyph = sym_encrypt(locked.clone(), &plain_a).unwrap();
+    let plain_b = sym_decrypt(locked.clone(), &cyph).unwrap();
+    assert_eq!(plain_a, plain_b);
+}
+
+#[test]
+fn locked_mut_succ() {
+    let mut locked = Locked::new([0u8; 32]).unwrap();
+    locked[0] = 1;
+    assert_eq!(locked[0], 1);
+}
+
+#[test]
+fn locked_multi_page_succ() {
+    let locked = Locked::new([7u8; 10000]).unwrap();
+    assert!(locked.iter().all(|b| *b == 7));
+}
@@ -0,0 +1,504 @@
+// Copyright 2018 Yobicash Ltd. See the COPYRIGHT file at the top-level directory
+// of this distribution.
+//
+// Licensed under the MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>
+// and the Apache 2.0 license <LICENSE-APACHE or https://opensource.org/licenses/Apache-2.0>.
+// This file may not be copied, modified, or distributed except according to those
+// terms.
+
+//! Yobicrypto `noise` module tests.
+
+extern crate yobicrypto;
+extern crate hex;
+
+use yobicrypto::ErrorKind;
+use yobicrypto::{X25519SecretKey, X25519PublicKey};
+use yobicrypto::CipherAlgorithm;
+use yobicrypto::{HandshakePattern, NoiseParams, HandshakeState, TransportState};
+use yobicrypto::{write_frame, read_frame};
+
+use std::io::{self, Read, Write};
+use std::sync::mpsc::{channel, Sender, Receiver};
+use std::thread;
+
+// Cacophony test vectors, with the SHA512 suites of the XX, IK and NK patterns:
+// protocol name, prologue, initiator static and ephemeral keys, responder static
+// and ephemeral keys, handshake hash, and the (payload, ciphertext) messages.
+fn noise_test_vectors() -> Vec<(String, String, String, String, String, String, String, Vec<(String, String)>)> {
+    vec![
+        (
+            "Noise_XX_25519_AESGCM_SHA512".to_string(),
+            "4a6f686e2047616c74".to_string(),
+            "e61ef9919cde45dd5f82166404bd08e38bceb5dfdfded0a34c8df7ed542214d1".to_string(),
+            "893e28b9dc6ca8d611ab664754b8ceb7bac5117349a4439a6b0569da977c464a".to_string(),
+            "4a3acbfdb163dec651dfa3194dece676d437029c62a408b4c5ea9114246e4893".to_string(),
+            "bbdb4cdbd309f1a1f2e1456967fe288cadd6f712d65dc7b7793d5e63da6b375b".to_string(),
+            "39f00604ced1d119476899b3cde9b7c970ef862897396f31d5df4340f2d65c58816a3e58a26f18f3d686e4c1d451129790977e56d857a86ce4b9db635b535334".to_string(),
+            vec![
+                ("4c756477696720766f6e204d69736573".to_string(), "ca35def5ae56cec33dc2036731ab14896bc4c75dbb07a61f879f8e3afa4c79444c756477696720766f6e204d69736573".to_string()),
+                ("4d757272617920526f746862617264".to_string(), "95ebc60d2b1fa672c1f46a8aa265ef51bfe38e7ccb39ec5be34069f144808843fd25f21a1797f62ac8960e3b8b37ba21dfd9b202859ad8f0011a179a0054b50205997c6746578d5bd7e8a2a1a9fa068b77f36ca8b1417ec18ad342d9734eb94eb89915ce5e9768358d5e29d7c624da".to_string()),
+                ("462e20412e20486179656b".to_string(), "5daf793909ca9cd970345c1bcd7d1612278fa941d8f62761bbbe278b7a3130c6c523872960931080fd3472c0499c214f45f03bb3389cc0a181176289251c30d67f5b7a59a5920f7d1b8aed".to_string()),
+                ("4361726c204d656e676572".to_string(), "11a6e4405bf638c751b57d0714c7b5138ab09e939c345d05a7fa6b".to_string()),
+                ("4a65616e2d426170746973746520536179".to_string(), "f2b926f127ba4ba6b40dfbc86101b20bb120f8d59a5babd665824d9dee31571af4".to_string()),
+                ("457567656e2042f6686d20766f6e2042617765726b".to_string(), "3e4b185189bb0bf4b535431344ee0726726f8632ba59b78a6b0cecffdf00b7dfe28320dc05".to_string()),
+            ],
+        ),
+        (
+            "Noise_XX_25519_ChaChaPoly_SHA512".to_string(),
+            "4a6f686e2047616c74".to_string(),
+            "e61ef9919cde45dd5f82166404bd08e38bceb5dfdfded0a34c8df7ed542214d1".to_string(),
+            "893e28b9dc6ca8d611ab664754b8ceb7bac5117349a4439a6b0569da977c464a".to_string(),
+            "4a3acbfdb163dec651dfa3194dece676d437029c62a408b4c5ea9114246e4893".to_string(),
+            "bbdb4cdbd309f1a1f2e1456967fe288cadd6f712d65dc7b7793d5e63da6b375b".to_string(),
+            "b98d52b12437f34cfec8312fe038c869b5c4882dfe45fb064e746d88783e56a3773ee191e726776467ec3b309f0093f7e712a87062c625e6c8d766bb172cea42".to_string(),
+            vec![
+                ("4c756477696720766f6e204d69736573".to_string(), "ca35def5ae56cec33dc2036731ab14896bc4c75dbb07a61f879f8e3afa4c79444c756477696720766f6e204d69736573".to_string()),
+                ("4d757272617920526f746862617264".to_string(), "95ebc60d2b1fa672c1f46a8aa265ef51bfe38e7ccb39ec5be34069f144808843d7c6169611117c6e843085d5ec1af406d58f75d17052f76fc87b7e624027b002be220520a7766451ec44fa8388d120354c0f8c8b8a83eb281d131cd231a5f3cc6a809c5dffb06cb8d792415336b4c0".to_string()),
+                ("462e20412e20486179656b".to_string(), "438696ce0ba3e21424cad39c48b89839fc102c64e3f3e81b6431c0c915d7983d0d7d87e611485ef5bf005c25a052289c949d3e1dd51b536bfda2eb3d14988f9c3291a1ac64b7b4cba0a019".to_string()),
+                ("4361726c204d656e676572".to_string(), "56430f48030039cfd44539edb61a3b87e1cd461a765cb539c3f4b6".to_string()),
+                ("4a65616e2d426170746973746520536179".to_string(), "0709391497714d94a8f62959fe15153996001daadbc1dec326a03ba8ff416b47f5".to_string()),
+                ("457567656e2042f6686d20766f6e2042617765726b".to_string(), "3d5f431ceee58c3ff1bdcdf874aaca9f564b743286a995ed03dffa8b65f33ac45c8c4b196d".to_string()),
+            ],
+        ),
+        (
+            "Noise_IK_25519_AESGCM_SHA512".to_string(),
+            "4a6f686e2047616c74".to_string(),
+            "e61ef9919cde45dd5f82166404bd08e38bceb5dfdfded0a34c8df7ed542214d1".to_string(),
+            "893e28b9dc6ca8d611ab664754b8ceb7bac5117349a4439a6b0569da977c464a".to_string(),
+            "4a3acbfdb163dec651dfa3194dece676d437029c62a408b4c5ea9114246e4893".to_string(),
+            "bbdb4cdbd309f1a1f2e1456967fe288cadd6f712d65dc7b7793d5e63da6b375b".to_string(),
+            "6eb7af04466fb3a1561f53ee65dc261ff26e01417fc1a2066ac0e8d4060775d6a76d002f3d769446ebba4d7fa2347e6692515f9b6bc8601067c53ae4b9615af0".to_string(),
+            vec![
+                ("4c756477696720766f6e204d69736573".to_string(), "ca35def5ae56cec33dc2036731ab14896bc4c75dbb07a61f879f8e3afa4c79441edc6a898ac79b09a5e21a391d717cc9fe6207726ca03a1ec47e7efa6ae61cba2c392f2f30d00850077641ed02d38c0f11bed6a3a668b33ecd3f324773f791921f8ee5b0d422bd6831686aef505dcd88".to_string()),
+                ("4d757272617920526f746862617264".to_string(), "95ebc60d2b1fa672c1f46a8aa265ef51bfe38e7ccb39ec5be34069f144808843c9993ca1fc214af8c6a4e228b2b5d66106b2bbc5e4537cc17655e44ace079a".to_string()),
+                ("462e20412e20486179656b".to_string(), "c9f752880da6468eb9bf272293d8d3d1bf88130372e4d26f12b921".to_string()),
+                ("4361726c204d656e676572".to_string(), "9bec50cd609e30cbc702417247b3854fbed537decc2b2366bf343a".to_string()),
+                ("4a65616e2d426170746973746520536179".to_string(), "c27c79d9d975652bdf091c566ccdf385d2f6f8ddffecfcafd80d9dad70b7f6ca5f".to_string()),
+                ("457567656e2042f6686d20766f6e2042617765726b".to_string(), "12f01efb31171bdfd3263e601784d51d51872897e169ac8a382388de223103f3f6c211186b".to_string()),
+            ],
+        ),
+        (
+            "Noise_IK_25519_ChaChaPoly_SHA512".to_string(),
+            "4a6f686e2047616c74".to_string(),
+            "e61ef9919cde45dd5f82166404bd08e38bceb5dfdfded0a34c8df7ed542214d1".to_string(),
+            "893e28b9dc6ca8d611ab664754b8ceb7bac5117349a4439a6b0569da977c464a".to_string(),
+            "4a3acbfdb163dec651dfa3194dece676d437029c62a408b4c5ea9114246e4893".to_string(),
+            "bbdb4cdbd309f1a1f2e1456967fe288cadd6f712d65dc7b7793d5e63da6b375b".to_string(),
+            "df5f46e7b80429fe9c587824b883d2c0a9e909d9be842e8d63797ca4815dd63bbbae8d2803a48ed79e3646103362e6de02921f138529389854c7701638d98c85".to_string(),
+            vec![
+                ("4c756477696720766f6e204d69736573".to_string(), "ca35def5ae56cec33dc2036731ab14896bc4c75dbb07a61f879f8e3afa4c79447a2281c0f1aee0c48c41333a1abbb349ee4bf12e09f8c4fd66635aabbb7dad346081a79f59e2cef812260cfe8c9e6a99d12f7c7ffc9fe5513818d9cf9b8778d1ebd1ce70c8f726d7869830258a788910".to_string()),
+                ("4d757272617920526f746862617264".to_string(), "95ebc60d2b1fa672c1f46a8aa265ef51bfe38e7ccb39ec5be34069f144808843f58050451a0edd2a40bb8b0f6b51ea8094a07e3ed31ebc516b584fef6eaaaf".to_string()),
+                ("462e20412e20486179656b".to_string(), "cae0b6af5460d026e80e22c27572a92048176872538f91a056a8df".to_string()),
+                ("4361726c204d656e676572".to_string(), "ab1440d2b5892c638a11a7fa6412beaea5cee62342147f02d75a68".to_string()),
+                ("4a65616e2d426170746973746520536179".to_string(), "0263ed778a193155c9947202e0b9d35eb46581a902449d091e1b6575a9a59fbeff".to_string()),
+                ("457567656e2042f6686d20766f6e2042617765726b".to_string(), "95aedd9192351379cb063c8d5827d5529c7f2c8929552dd64c57029734737ea2a405255dcf".to_string()),
+            ],
+        ),
+        (
+            "Noise_NK_25519_AESGCM_SHA512".to_string(),
+            "4a6f686e2047616c74".to_string(),
+            "".to_string(),
+            "893e28b9dc6ca8d611ab664754b8ceb7bac5117349a4439a6b0569da977c464a".to_string(),
+            "4a3acbfdb163dec651dfa3194dece676d437029c62a408b4c5ea9114246e4893".to_string(),
+            "bbdb4cdbd309f1a1f2e1456967fe288cadd6f712d65dc7b7793d5e63da6b375b".to_string(),
+            "4a029ed7881c96fd2e32ec38263bb49f0eac61810b258a61671eb486ef119c47799720f6920c2eba38b9435300851744a835cdd799ec0d0832873769b5bfd5d5".to_string(),
+            vec![
+                ("4c756477696720766f6e204d69736573".to_string(), "ca35def5ae56cec33dc2036731ab14896bc4c75dbb07a61f879f8e3afa4c7944648f51aa930fd7d9d64c13e0d94f6b0e72227dbc98dfccecfb2c474e05ce5c82".to_string()),
+                ("4d757272617920526f746862617264".to_string(), "95ebc60d2b1fa672c1f46a8aa265ef51bfe38e7ccb39ec5be34069f14480884313f7cea78d381a0b98243bdc46040115147c0a7caa6faa0ef49f3b0a4e8258".to_string()),
+                ("462e20412e20486179656b".to_string(), "0a0cecde12117879a0aaa10b67404e1329f2edaef2d849b892659b".to_string()),
+                ("4361726c204d656e676572".to_string(), "1ef1c166c5ca068f10677a3385397e708642ecbdc4963adac6a5df".to_string()),
+                ("4a65616e2d426170746973746520536179".to_string(), "db97688a0eb78d7b0ea89ebbf28840a721363d1c83409484f5cbfc3360d474dd83".to_string()),
+                ("457567656e2042f6686d20766f6e2042617765726b".to_string(), "2ccdd3e605f17652c0c5bce7d2d858b033a31c323ca5d81f0bbca534c865276513059a8fbe".to_string()),
+            ],
+        ),
+        (
+            "Noise_NK_25519_ChaChaPoly_SHA512".to_string(),
+            "4a6f686e2047616c74".to_string(),
+            "".to_string(),
+            "893e28b9dc6ca8d611ab664754b8ceb7bac5117349a4439a6b0569da977c464a".to_string(),
+            "4a3acbfdb163dec651dfa3194dece676d437029c62a408b4c5ea9114246e4893".to_string(),
+            "bbdb4cdbd309f1a1f2e1456967fe288cadd6f712d65dc7b7793d5e63da6b375b".to_string(),
+            "eae5f014a9a3ea7ff24a9adf24720fe7809bcb173c878fcd86df1345766626e4a4850ca01c6fd8195cc5faf7aa48476fa4522d0166d7e9103921f60792492584".to_string(),
+            vec![
+                ("4c756477696720766f6e204d69736573".to_string(), "ca35def5ae56cec33dc2036731ab14896bc4c75dbb07a61f879f8e3afa4c79444bc2296c8eea30b5482161d29ace420ef8b63c1e6f026b61150c535870d604d9".to_string()),
+                ("4d757272617920526f746862617264".to_string(), "95ebc60d2b1fa672c1f46a8aa265ef51bfe38e7ccb39ec5be34069f14480884315059cc8b9a76e12fd9b33b9e07f3c66e8732a6bf06b6bc1b2c6fb40b0782d".to_string()),
+                ("462e20412e20486179656b".to_string(), "18ecb8118b223145bae7829f9c8d91be8221175d0bf585f2e99e60".to_string()),
+                ("4361726c204d656e676572".to_string(), "6a19c0843276fd4c37a1b0053d0ce7c3724a4ece8f7cfed15a3a2a".to_string()),
+                ("4a65616e2d426170746973746520536179".to_string(), "47afae3fd6d853c3be2835fcb249e7a31821782635112f4828e6edba09fe9334d5".to_string()),
+                ("457567656e2042f6686d20766f6e2042617765726b".to_string(), "4ebc1f72fca0525982f97530426bd3cff9eaa8a84f4b3fbb8ad420079cd27b367c77594a44".to_string()),
+            ],
+        ),
+    ]
+}
+
+/// One end of an in-memory duplex pipe.
+struct Pipe {
+    tx: Sender<Vec<u8>>,
+    rx: Receiver<Vec<u8>>,
+    buf: Vec<u8>,
+}
+
+impl Read for Pipe {
+    fn read(&mut self, out: &mut [u8]) -> io::Result<usize> {
+        if self.buf.is_empty() {
+            match self.rx.recv() {
+                Ok(data) => self.buf = data,
+                Err(_) => return Ok(0),
+            }
+        }
+
+        let len = std::cmp::min(out.len(), self.buf.len());
+        out[..len].copy_from_slice(&self.buf[..len]);
+        self.buf.drain(..len);
+
+        Ok(len)
+    }
+}
+
+impl Write for Pipe {
+    fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+        self.tx.send(data.to_vec())
+            .map_err(|_| io::Error::new(io::ErrorKind::BrokenPipe, "closed pipe"))?;
+
+        Ok(data.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+fn pipe() -> (Pipe, Pipe) {
+    let (tx_a, rx_a) = channel();
+    let (tx_b, rx_b) = channel();
+
+    let a = Pipe { tx: tx_a, rx: rx_b, buf: Vec::new() };
+    let b = Pipe { tx: tx_b, rx: rx_a, buf: Vec::new() };
+
+    (a, b)
+}
+
+fn secret_from_hex(s: &str) -> Option<X25519SecretKey> {
+    if s.is_empty() {
+        None
+    } else {
+        Some(X25519SecretKey::from_hex(s).unwrap())
+    }
+}
+
+/// Runs a handshake over a stream, returning the `TransportState`.
+fn handshake<S: Read + Write>(mut hs: HandshakeState, stream: &mut S) -> TransportState {
+    while !hs.is_finished() {
+        if hs.is_my_turn() {
+            let message = hs.write_message(&[]).unwrap();
+            write_frame(stream, &message).unwrap();
+        } else {
+            let message = read_frame(stream).unwrap();
+            hs.read_message(&message).unwrap();
+        }
+    }
+
+    hs.into_transport().unwrap()
+}
+
+/// Runs a handshake and an echo of `count` messages over a pipe.
+fn run_pipe(params: NoiseParams, rekey_interval: u64, count: usize) {
+    let init_sk = X25519SecretKey::random();
+    let resp_sk = X25519SecretKey::random();
+    let resp_pk = resp_sk.to_public();
+
+    let init_s = if params.pattern == HandshakePattern::NK { None } else { Some(init_sk.clone()) };
+    let init_rs = if params.pattern == HandshakePattern::XX { None } else { Some(resp_pk) };
+
+    let (mut init_stream, mut resp_stream) = pipe();
+
+    let responder = thread::spawn(move || {
+        let hs = HandshakeState::responder(params, b"prologue", resp_sk).unwrap();
+        let mut transport = handshake(hs, &mut resp_stream);
+        transport.set_rekey_interval(rekey_interval).unwrap();
+
+        for _ in 0..count {
+            let message = read_frame(&mut resp_stream).unwrap();
+            let payload = transport.read_message(&message).unwrap();
+            let reply = transport.write_message(&payload).unwrap();
+            write_frame(&mut resp_stream, &reply).unwrap();
+        }
+
+        (transport.handshake_hash(), transport.remote_static())
+    });
+
+    let hs = HandshakeState::initiator(params, b"prologue", init_s, init_rs).unwrap();
+    let mut transport = handshake(hs, &mut init_stream);
+    transport.set_rekey_interval(rekey_interval).unwrap();
+
+    let mut messages = Vec::new();
+
+    for i in 0..count {
+        let payload = format!("message {}", i).into_bytes();
+        let message = transport.write_message(&payload).unwrap();
+        write_frame(&mut init_stream, &message).unwrap();
+        messages.push(message);
+
+        let reply = read_frame(&mut init_stream).unwrap();
+        assert_eq!(transport.read_message(&reply).unwrap(), payload);
+    }
+
+    // NB: rekeying changes the ciphertexts of equal plaintexts
+    if count > rekey_interval as usize {
+        assert_ne!(messages[0], messages[rekey_interval as usize]);
+    }
+
+    let (resp_hash, resp_remote) = responder.join().unwrap();
+
+    assert_eq!(transport.handshake_hash(), resp_hash);
+    assert_eq!(transport.remote_static(), Some(resp_pk));
+
+    if params.pattern == HandshakePattern::NK {
+        assert_eq!(resp_remote, None);
+    } else {
+        assert_eq!(resp_remote, Some(init_sk.to_public()));
+    }
+}
+
+#[test]
+fn noise_params_name_succ() {
+    for v in noise_test_vectors() {
+        let params = NoiseParams::from_name(&v.0).unwrap();
+        assert_eq!(params.name(), v.0);
+    }
+}
+
+#[test]
+fn noise_params_unsupported_fail() {
+    let res = NoiseParams::from_name("Noise_XX_25519_AESGCM_SHA256");
+    assert!(res.is_err());
+
+    let res = NoiseParams::from_name("Noise_KK_25519_AESGCM_SHA512");
+    assert!(res.is_err());
+
+    let res = NoiseParams::new(HandshakePattern::XX, CipherAlgorithm::AES256GCMSIV);
+    assert!(res.is_err());
+}
+
+#[test]
+fn noise_test_vectors_succ() {
+    for v in noise_test_vectors() {
+        let params = NoiseParams::from_name(&v.0).unwrap();
+        let prologue = hex::decode(&v.1).unwrap();
+        let init_s = secret_from_hex(&v.2);
+        let resp_s = X25519SecretKey::from_hex(&v.4).unwrap();
+        let init_rs = if params.pattern == HandshakePattern::XX {
+            None
+        } else {
+            Some(resp_s.to_public())
+        };
+
+        let mut init = HandshakeState::initiator(params, &prologue, init_s, init_rs).unwrap();
+        init.set_ephemeral(X25519SecretKey::from_hex(&v.3).unwrap());
+
+        let mut resp = HandshakeState::responder(params, &prologue, resp_s).unwrap();
+        resp.set_ephemeral(X25519SecretKey::from_hex(&v.5).unwrap());
+
+        let mut messages = v.7.iter();
+        let mut initiator_turn = true;
+
+        while !init.is_finished() {
+            let (payload, cyph) = messages.next().unwrap();
+            let payload = hex::decode(payload).unwrap();
+            let cyph = hex::decode(cyph).unwrap();
+
+            let (writer, reader) = if initiator_turn {
+                (&mut init, &mut resp)
+            } else {
+                (&mut resp, &mut init)
+            };
+
+            assert_eq!(writer.write_message(&payload).unwrap(), cyph);
+            assert_eq!(reader.read_message(&cyph).unwrap(), payload);
+
+            initiator_turn = !initiator_turn;
+        }
+
+        assert!(resp.is_finished());
+        assert_eq!(hex::encode(init.handshake_hash()), v.6);
+        assert_eq!(hex::encode(resp.handshake_hash()), v.6);
+
+        let mut init = init.into_transport().unwrap();
+        let mut resp = resp.into_transport().unwrap();
+
+        for (payload, cyph) in messages {
+            let payload = hex::decode(payload).unwrap();
+            let cyph = hex::decode(cyph).unwrap();
+
+            let (writer, reader) = if initiator_turn {
+                (&mut init, &mut resp)
+            } else {
+                (&mut resp, &mut init)
+            };
+
+            assert_eq!(writer.write_message(&payload).unwrap(), cyph);
+            assert_eq!(reader.read_message(&cyph).unwrap(), payload);
+
+            initiator_turn = !initiator_turn;
+        }
+    }
+}
+
+#[test]
+fn noise_pipe_succ() {
+    for pattern in vec![HandshakePattern::XX, HandshakePattern::IK, HandshakePattern::NK] {
+        for algorithm in vec![CipherAlgorithm::AES256GCM, CipherAlgorithm::ChaCha20Poly1305] {
+            let params = NoiseParams::new(pattern, algorithm).unwrap();
+            run_pipe(params, 1 << 20, 10);
+        }
+    }
+}
+
+#[test]
+fn noise_pipe_rekey_succ() {
+    let params = NoiseParams::new(HandshakePattern::XX, CipherAlgorithm::ChaCha20Poly1305).unwrap();
+    run_pipe(params, 3, 10);
+}
+
+#[test]
+fn noise_rekey_mismatch_fail() {
+    let params = NoiseParams::new(HandshakePattern::NK, CipherAlgorithm::AES256GCM).unwrap();
+    let resp_sk = X25519SecretKey::random();
+    let resp_pk = resp_sk.to_public();
+
+    let mut init = HandshakeState::initiator(params, &[], None, Some(resp_pk)).unwrap();
+    let mut resp = HandshakeState::responder(params, &[], resp_sk).unwrap();
+
+    let message = init.write_message(&[]).unwrap();
+    resp.read_message(&message).unwrap();
+    let message = resp.write_message(&[]).unwrap();
+    init.read_message(&message).unwrap();
+
+    let mut init = init.into_transport().unwrap();
+    let mut resp = resp.into_transport().unwrap();
+    init.set_rekey_interval(2).unwrap();
+
+    for _ in 0..2 {
+        let message = init.write_message(b"data").unwrap();
+        resp.read_message(&message).unwrap();
+    }
+
+    let message = init.write_message(b"data").unwrap();
+    let res = resp.read_message(&message);
+    assert!(res.is_err());
+}
+
+#[test]
+fn noise_tampered_message_fail() {
+    let params = NoiseParams::new(HandshakePattern::XX, CipherAlgorithm::AES256GCM).unwrap();
+
+    let mut init = HandshakeState::initiator(params, &[], Some(X25519SecretKey::random()), None).unwrap();
+    let mut resp = HandshakeState::responder(params, &[], X25519SecretKey::random()).unwrap();
+
+    let message = init.write_message(&[]).unwrap();
+    resp.read_message(&message).unwrap();
+
+    let mut message = resp.write_message(b"payload").unwrap();
+    let last = message.len() - 1;
+    message[last] ^= 1;
+
+    let res = init.read_message(&message);
+    assert!(res.is_err());
+
+    // NB: a failed read leaves the state unchanged
+    message[last] ^= 1;
+    assert_eq!(init.read_message(&message).unwrap(), b"payload".to_vec());
+}
+
+#[test]
+fn noise_rekey_tampered_message_fail() {
+    let params = NoiseParams::new(HandshakePattern::XX, CipherAlgorithm::ChaCha20Poly1305).unwrap();
+
+    let mut init = HandshakeState::initiator(params, &[], Some(X25519SecretKey::random()), None).unwrap();
+    let mut resp = HandshakeState::responder(params, &[], X25519SecretKey::random()).unwrap();
+
+    let message = init.write_message(&[]).unwrap();
+    resp.read_message(&message).unwrap();
+    let message = resp.write_message(&[]).unwrap();
+    init.read_message(&message).unwrap();
+    let message = init.write_message(&[]).unwrap();
+    resp.read_message(&message).unwrap();
+
+    let mut init = init.into_transport().unwrap();
+    let mut resp = resp.into_transport().unwrap();
+    init.set_rekey_interval(3).unwrap();
+    resp.set_rekey_interval(3).unwrap();
+
+    for _ in 0..3 {
+        let message = init.write_message(b"data").unwrap();
+        resp.read_message(&message).unwrap();
+    }
+
+    // NB: a tampered message at the rekey boundary must not rekey the receiver
+    let mut message = init.write_message(b"payload").unwrap();
+    message[0] ^= 1;
+    assert!(resp.read_message(&message).is_err());
+    assert!(resp.read_message(&message).is_err());
+
+    message[0] ^= 1;
+    assert_eq!(resp.read_message(&message).unwrap(), b"payload".to_vec());
+
+    for _ in 0..4 {
+        let message = init.write_message(b"data").unwrap();
+        assert_eq!(resp.read_message(&message).unwrap(), b"data".to_vec());
+    }
+}
+
+#[test]
+fn noise_prologue_mismatch_fail() {
+    let params = NoiseParams::new(HandshakePattern::NK, CipherAlgorithm::ChaCha20Poly1305).unwrap();
+    let resp_sk = X25519SecretKey::random();
+    let resp_pk = resp_sk.to_public();
+
+    let mut init = HandshakeState::initiator(params, b"one", None, Some(resp_pk)).unwrap();
+    let mut resp = HandshakeState::responder(params, b"two", resp_sk).unwrap();
+
+    let message = init.write_message(&[]).unwrap();
+    let res = resp.read_message(&message);
+    assert!(res.is_err());
+}
+
+#[test]
+fn noise_out_of_turn_fail() {
+    let params = NoiseParams::new(HandshakePattern::XX, CipherAlgorithm::AES256GCM).unwrap();
+
+    let mut init = HandshakeState::initiator(params, &[], Some(X25519SecretKey::random()), None).unwrap();
+    let mut resp = HandshakeState::responder(params, &[], X25519SecretKey::random()).unwrap();
+
+    let res = resp.write_message(&[]);
+    assert_eq!(res.unwrap_err().kind(), ErrorKind::InvalidHandshake);
+
+    let message = init.write_message(&[]).unwrap();
+    let res = init.read_message(&message);
+    assert!(res.is_err());
+
+    let res = init.into_transport();
+    assert!(res.is_err());
+}
+
+#[test]
+fn noise_missing_keys_fail() {
+    let params = NoiseParams::new(HandshakePattern::IK, CipherAlgorithm::AES256GCM).unwrap();
+
+    let res = HandshakeState::initiator(params, &[], Some(X25519SecretKey::random()), None);
+    assert!(res.is_err());
+
+    let res = HandshakeState::initiator(params, &[], None, Some(X25519PublicKey::default()));
+    assert!(res.is_err());
+}
+
+#[test]
+fn noise_frame_succ() {
+    let (mut a, mut b) = pipe();
+
+    write_frame(&mut a, b"frame").unwrap();
+    assert_eq!(read_frame(&mut b).unwrap(), b"frame".to_vec());
+
+    let res = write_frame(&mut a, &vec![0u8; 65536]);
+    assert!(res.is_err());
+}
@@ -0,0 +1,162 @@
+// Copyright 2018 Yobicash Ltd. See the COPYRIGHT file at the top-level directory
+// of this distribution.
+//
+// Licensed under the MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>
+// and the Apache 2.0 license <LICENSE-APACHE or https://opensource.org/licenses/Apache-2.0>.
+// This file may not be copied, modified, or distributed except according to those
+// terms.
+
+//! Yobicrypto `passphrase` module tests.
+
+extern crate byteorder;
+extern crate yobicrypto;
+
+use byteorder::{BigEndian, ByteOrder};
+
+use yobicrypto::{ErrorKind, Random, Digest, BalloonParams, Key};
+use yobicrypto::{encrypt_with_passphrase, decrypt_with_passphrase};
+use yobicrypto::{PASSPHRASE_HEADER_SIZE, PASSPHRASE_MIN_S_COST, PASSPHRASE_MAX_S_COST};
+use yobicrypto::{PASSPHRASE_MIN_T_COST, PASSPHRASE_MAX_T_COST, PASSPHRASE_MAX_DELTA};
+use yobicrypto::check_passphrase_params;
+
+use std::time::{Duration, Instant};
+
+#[test]
+fn key_from_passphrase_succ() {
+    let salt = Digest::hash(&Random::bytes(64));
+    let params = BalloonParams::new(16, 2, 3).unwrap();
+    let key_a = Key::from_passphrase(b"passphrase", salt, params).unwrap();
+    let key_b = Key::from_passphrase(b"passphrase", salt, params).unwrap();
+    assert_eq!(key_a, key_b)
+}
+
+#[test]
+fn key_from_passphrase_fail() {
+    let salt_a = Digest::hash(&Random::bytes(64));
+    let salt_b = Digest::hash(&Random::bytes(64));
+    let params = BalloonParams::new(16, 2, 3).unwrap();
+    let key_a = Key::from_passphrase(b"passphrase", salt_a, params).unwrap();
+    let key_b = Key::from_passphrase(b"passphrase", salt_b, params).unwrap();
+    let key_c = Key::from_passphrase(b"Passphrase", salt_a, params).unwrap();
+    assert_ne!(key_a, key_b);
+    assert_ne!(key_a, key_c)
+}
+
+#[test]
+fn decrypt_with_passphrase_succ() {
+    let params = BalloonParams::new(16, 2, 3).unwrap();
+    let plain_a = Random::bytes(100);
+    let cyph = encrypt_with_passphrase(b"passphrase", params, &plain_a).unwrap();
+    let plain_b = decrypt_with_passphrase(b"passphrase", &cyph).unwrap();
+    assert_eq!(plain_a, plain_b)
+}
+
+#[test]
+fn decrypt_with_passphrase_fail() {
+    let params = BalloonParams::new(16, 2, 3).unwrap();
+    let plain = Random::bytes(100);
+    let cyph = encrypt_with_passphrase(b"passphrase", params, &plain).unwrap();
+    let res = decrypt_with_passphrase(b"wrong passphrase", &cyph);
+    assert!(res.is_err());
+    assert_eq!(res.err().unwrap().kind(), ErrorKind::InvalidTag)
+}
+
+#[test]
+fn decrypt_with_passphrase_tampered_params_fail() {
+    let params = BalloonParams::new(16, 2, 3).unwrap();
+    let plain = Random::bytes(100);
+    let mut cyph = encrypt_with_passphrase(b"passphrase", params, &plain).unwrap();
+    // NB: t_cost from 2 to 1
+    cyph[PASSPHRASE_HEADER_SIZE - 5] = 1;
+    let res = decrypt_with_passphrase(b"passphrase", &cyph);
+    assert!(res.is_err())
+}
+
+#[test]
+fn decrypt_with_passphrase_truncated_fail() {
+    let params = BalloonParams::new(16, 2, 3).unwrap();
+    let plain = Random::bytes(100);
+    let cyph = encrypt_with_passphrase(b"passphrase", params, &plain).unwrap();
+    let res = decrypt_with_passphrase(b"passphrase", &cyph[..PASSPHRASE_HEADER_SIZE - 1]);
+    assert!(res.is_err())
+}
+
+#[test]
+fn check_passphrase_params_succ() {
+    let params = BalloonParams::new(PASSPHRASE_MIN_S_COST, PASSPHRASE_MIN_T_COST, 3).unwrap();
+    check_passphrase_params(params).unwrap();
+    let params = BalloonParams::new(PASSPHRASE_MAX_S_COST, PASSPHRASE_MAX_T_COST, PASSPHRASE_MAX_DELTA).unwrap();
+    check_passphrase_params(params).unwrap()
+}
+
+#[test]
+fn check_passphrase_params_fail() {
+    let params = vec![
+        (PASSPHRASE_MIN_S_COST - 1, PASSPHRASE_MIN_T_COST, 3),
+        (PASSPHRASE_MAX_S_COST + 1, PASSPHRASE_MIN_T_COST, 3),
+        (PASSPHRASE_MIN_S_COST, PASSPHRASE_MIN_T_COST - 1, 3),
+        (PASSPHRASE_MIN_S_COST, PASSPHRASE_MAX_T_COST + 1, 3),
+        (PASSPHRASE_MIN_S_COST, PASSPHRASE_MIN_T_COST, PASSPHRASE_MAX_DELTA + 1),
+    ];
+
+    for (s_cost, t_cost, delta) in params {
+        let params = BalloonParams::new(s_cost, t_cost, delta).unwrap();
+        let res = check_passphrase_params(params);
+        assert!(res.is_err());
+        assert_eq!(res.unwrap_err().kind(), ErrorKind::OutOfBound);
+        let res = encrypt_with_passphrase(b"passphrase", params, &Random::bytes(100));
+        assert!(res.is_err())
+    }
+}
+
+#[test]
+fn decrypt_with_passphrase_max_s_cost_fail() {
+    let params = BalloonParams::new(16, 2, 3).unwrap();
+    let plain = Random::bytes(100);
+    let mut cyph = encrypt_with_passphrase(b"passphrase", params, &plain).unwrap();
+    // NB: s_cost to u32::MAX, which would exhaust the memory if hashed
+    for b in cyph[64..68].iter_mut() {
+        *b = 0xff;
+    }
+    let res = decrypt_with_passphrase(b"passphrase", &cyph);
+    assert!(res.is_err());
+    assert_eq!(res.unwrap_err().kind(), ErrorKind::OutOfBound)
+}
+
+#[test]
+fn decrypt_with_passphrase_max_work_fail() {
+    let params = BalloonParams::new(16, 2, 3).unwrap();
+    let plain = Random::bytes(100);
+    let cyph = encrypt_with_passphrase(b"passphrase", params, &plain).unwrap();
+    // NB: just above the maxima, and the previous maxima (about 2^30 hash calls)
+    let forged = vec![
+        (PASSPHRASE_MAX_S_COST + 1, PASSPHRASE_MAX_T_COST, PASSPHRASE_MAX_DELTA),
+        (PASSPHRASE_MAX_S_COST, PASSPHRASE_MAX_T_COST + 1, PASSPHRASE_MAX_DELTA),
+        (PASSPHRASE_MAX_S_COST, PASSPHRASE_MAX_T_COST, PASSPHRASE_MAX_DELTA + 1),
+        (1 << 20, 32, 32),
+    ];
+
+    for (s_cost, t_cost, delta) in forged {
+        let mut _cyph = cyph.clone();
+        BigEndian::write_u32(&mut _cyph[64..68], s_cost);
+        BigEndian::write_u32(&mut _cyph[68..72], t_cost);
+        BigEndian::write_u32(&mut _cyph[72..76], delta);
+        let start = Instant::now();
+        let res = decrypt_with_passphrase(b"passphrase", &_cyph);
+        assert!(start.elapsed() < Duration::from_secs(1));
+        assert!(res.is_err());
+        assert_eq!(res.unwrap_err().kind(), ErrorKind::OutOfBound)
+    }
+}
+
+#[test]
+fn decrypt_with_passphrase_min_s_cost_fail() {
+    let params = BalloonParams::new(16, 2, 3).unwrap();
+    let plain = Random::bytes(100);
+    let mut cyph = encrypt_with_passphrase(b"passphrase", params, &plain).unwrap();
+    // NB: s_cost to 2, which would skip the Balloon mixing
+    cyph[64..68].copy_from_slice(&[0, 0, 0, 2]);
+    let res = decrypt_with_passphrase(b"passphrase", &cyph);
+    assert!(res.is_err());
+    assert_eq!(res.unwrap_err().kind(), ErrorKind::OutOfBound)
+}
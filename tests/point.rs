@@ -57,3 +57,19 @@ fn point_to_hex_succ() {
     let point_b = Point::from_hex(point_a_hex.as_str()).unwrap();
     assert_eq!(point_a, point_b)
 }
+
+#[test]
+fn point_torsion_succ() {
+    // NB: a point of order 8
+    let torsion = Point::from_hex("c7176a703d4dd84fba3c0b760d10670f2a2053fa2c39ccc64ec7fd7792ac037a").unwrap();
+    assert!(torsion.is_small_order());
+    assert!(!torsion.is_torsion_free());
+
+    let point = Point::random().unwrap();
+    assert!(!point.is_small_order());
+    assert!(point.is_torsion_free());
+
+    let mixed = &point + &torsion;
+    assert!(!mixed.is_small_order());
+    assert!(!mixed.is_torsion_free())
+}
@@ -0,0 +1,207 @@
+// Copyright 2018 Yobicash Ltd. See the COPYRIGHT file at the top-level directory
+// of this distribution.
+//
+// Licensed under the MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>
+// and the Apache 2.0 license <LICENSE-APACHE or https://opensource.org/licenses/Apache-2.0>.
+// This file may not be copied, modified, or distributed except according to those
+// terms.
+
+//! Yobicrypto `ratchet` module tests.
+
+extern crate yobicrypto;
+
+use yobicrypto::{ErrorKind, Key};
+use yobicrypto::{ChainKey, SymmetricRatchet};
+use yobicrypto::{hmac_sha512, sym_encrypt_with_ad, sym_decrypt_with_ad};
+use yobicrypto::{BinarySerialize, HexSerialize};
+
+fn ratchets(max_skip: usize) -> (SymmetricRatchet, SymmetricRatchet) {
+    let key = Key::new();
+    let sender = SymmetricRatchet::with_max_skip(key.clone(), max_skip);
+    let receiver = SymmetricRatchet::with_max_skip(key, max_skip);
+
+    (sender, receiver)
+}
+
+#[test]
+fn chain_key_next_key_succ() {
+    let key = Key::new();
+    let mut chain = ChainKey::new(key.clone());
+
+    let (index, message_key) = chain.next_key().unwrap();
+    assert_eq!(index, 0);
+    assert_eq!(chain.index(), 1);
+
+    let expected = hmac_sha512(key.expose_secret(), &[0x01]);
+    assert_eq!(message_key.expose_secret(), &expected[..32]);
+
+    let (index, next_message_key) = chain.next_key().unwrap();
+    assert_eq!(index, 1);
+    assert_ne!(message_key, next_message_key);
+    assert_ne!(message_key, key);
+}
+
+#[test]
+fn chain_key_deterministic_succ() {
+    let key = Key::new();
+    let mut chain_a = ChainKey::new(key.clone());
+    let mut chain_b = ChainKey::new(key);
+
+    for _ in 0..10 {
+        assert_eq!(chain_a.next_key().unwrap(), chain_b.next_key().unwrap());
+    }
+
+    assert_eq!(chain_a, chain_b)
+}
+
+#[test]
+fn ratchet_in_order_succ() {
+    let (mut sender, mut receiver) = ratchets(10);
+
+    for i in 0..20 {
+        let plain = format!("message {}", i).into_bytes();
+        let cyph = sender.encrypt(b"ad", &plain).unwrap();
+        assert_eq!(receiver.decrypt(b"ad", &cyph).unwrap(), plain);
+    }
+
+    assert_eq!(receiver.index(), 20);
+    assert_eq!(receiver.skipped_len(), 0)
+}
+
+#[test]
+fn ratchet_out_of_order_succ() {
+    let (mut sender, mut receiver) = ratchets(10);
+
+    let cyphs: Vec<Vec<u8>> = (0..5u8)
+        .map(|i| sender.encrypt(&[], &[i]).unwrap())
+        .collect();
+
+    assert_eq!(receiver.decrypt(&[], &cyphs[3]).unwrap(), vec![3]);
+    assert_eq!(receiver.skipped_len(), 3);
+
+    assert_eq!(receiver.decrypt(&[], &cyphs[0]).unwrap(), vec![0]);
+    assert_eq!(receiver.decrypt(&[], &cyphs[4]).unwrap(), vec![4]);
+    assert_eq!(receiver.decrypt(&[], &cyphs[2]).unwrap(), vec![2]);
+    assert_eq!(receiver.decrypt(&[], &cyphs[1]).unwrap(), vec![1]);
+    assert_eq!(receiver.skipped_len(), 0)
+}
+
+#[test]
+fn ratchet_replay_fail() {
+    let (mut sender, mut receiver) = ratchets(10);
+
+    let cyph_a = sender.encrypt(&[], b"a").unwrap();
+    let cyph_b = sender.encrypt(&[], b"b").unwrap();
+
+    receiver.decrypt(&[], &cyph_b).unwrap();
+    receiver.decrypt(&[], &cyph_a).unwrap();
+
+    let res = receiver.decrypt(&[], &cyph_a);
+    assert_eq!(res.unwrap_err().kind(), ErrorKind::NotFound);
+
+    let res = receiver.decrypt(&[], &cyph_b);
+    assert_eq!(res.unwrap_err().kind(), ErrorKind::NotFound)
+}
+
+#[test]
+fn ratchet_max_skip_fail() {
+    let (mut sender, mut receiver) = ratchets(3);
+
+    for _ in 0..4 {
+        sender.encrypt(&[], b"lost").unwrap();
+    }
+
+    let cyph = sender.encrypt(&[], b"too far").unwrap();
+    let res = receiver.decrypt(&[], &cyph);
+    assert_eq!(res.unwrap_err().kind(), ErrorKind::OutOfBound);
+    assert_eq!(receiver.index(), 0)
+}
+
+#[test]
+fn ratchet_skipped_eviction_succ() {
+    let (mut sender, mut receiver) = ratchets(3);
+
+    let cyphs: Vec<Vec<u8>> = (0..8u8)
+        .map(|i| sender.encrypt(&[], &[i]).unwrap())
+        .collect();
+
+    receiver.decrypt(&[], &cyphs[3]).unwrap();
+    receiver.decrypt(&[], &cyphs[7]).unwrap();
+    assert_eq!(receiver.skipped_len(), 3);
+
+    // NB: the oldest skipped keys are evicted first
+    let res = receiver.decrypt(&[], &cyphs[0]);
+    assert_eq!(res.unwrap_err().kind(), ErrorKind::NotFound);
+    assert_eq!(receiver.decrypt(&[], &cyphs[6]).unwrap(), vec![6])
+}
+
+#[test]
+fn ratchet_tampered_fail() {
+    let (mut sender, mut receiver) = ratchets(10);
+
+    let mut cyph = sender.encrypt(&[], b"message").unwrap();
+    let last = cyph.len() - 1;
+    cyph[last] ^= 1;
+
+    let res = receiver.decrypt(&[], &cyph);
+    assert!(res.is_err());
+    assert_eq!(receiver.index(), 0);
+
+    cyph[last] ^= 1;
+    let res = receiver.decrypt(b"other ad", &cyph);
+    assert!(res.is_err());
+
+    // NB: the index is authenticated
+    let mut moved = cyph.clone();
+    moved[7] = 1;
+    let res = receiver.decrypt(&[], &moved);
+    assert!(res.is_err());
+    assert_eq!(receiver.skipped_len(), 0);
+
+    assert_eq!(receiver.decrypt(&[], &cyph).unwrap(), b"message".to_vec())
+}
+
+#[test]
+fn chain_key_forward_secrecy_succ() {
+    let mut chain = ChainKey::new(Key::new());
+    let (_, past_key) = chain.next_key().unwrap();
+
+    let cyph = sym_encrypt_with_ad(past_key.clone(), &[], b"past").unwrap();
+
+    // NB: a compromised chain only derives the keys of the later messages
+    let mut compromised = chain.clone();
+    for _ in 0..10 {
+        let (_, key) = compromised.next_key().unwrap();
+        assert_ne!(key, past_key);
+        assert!(sym_decrypt_with_ad(key, &[], &cyph).is_err());
+    }
+}
+
+#[test]
+fn ratchet_to_bytes_succ() {
+    let (mut sender, mut receiver) = ratchets(10);
+
+    let cyphs: Vec<Vec<u8>> = (0..4u8)
+        .map(|i| sender.encrypt(&[], &[i]).unwrap())
+        .collect();
+
+    receiver.decrypt(&[], &cyphs[2]).unwrap();
+
+    let b = receiver.to_bytes().unwrap();
+    let mut resumed = SymmetricRatchet::from_bytes(&b).unwrap();
+    assert_eq!(resumed, receiver);
+    assert_eq!(resumed.skipped_len(), 2);
+
+    assert_eq!(resumed.decrypt(&[], &cyphs[0]).unwrap(), vec![0]);
+    assert_eq!(resumed.decrypt(&[], &cyphs[3]).unwrap(), vec![3]);
+}
+
+#[test]
+fn ratchet_to_hex_succ() {
+    let (mut sender, _) = ratchets(10);
+    sender.encrypt(&[], b"message").unwrap();
+
+    let s = sender.to_hex().unwrap();
+    let resumed = SymmetricRatchet::from_hex(&s).unwrap();
+    assert_eq!(resumed, sender)
+}
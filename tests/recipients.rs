@@ -0,0 +1,93 @@
+// Copyright 2018 Yobicash Ltd. See the COPYRIGHT file at the top-level directory
+// of this distribution.
+//
+// Licensed under the MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>
+// and the Apache 2.0 license <LICENSE-APACHE or https://opensource.org/licenses/Apache-2.0>.
+// This file may not be copied, modified, or distributed except according to those
+// terms.
+
+//! Yobicrypto `recipients` module tests.
+
+extern crate yobicrypto;
+
+use yobicrypto::{ErrorKind, Random, SecretKey, PublicKey};
+use yobicrypto::CipherAlgorithm;
+use yobicrypto::{encrypt_to_many, encrypt_to_many_with_hints, encrypt_to_many_with};
+use yobicrypto::decrypt_from_many;
+
+fn recipients(n: usize) -> (Vec<SecretKey>, Vec<PublicKey>) {
+    let sks: Vec<SecretKey> = (0..n).map(|_| SecretKey::random()).collect();
+    let pks = sks.iter().map(|sk| sk.to_public()).collect();
+    (sks, pks)
+}
+
+#[test]
+fn decrypt_from_many_succ() {
+    let (sks, pks) = recipients(5);
+    let plain_a = Random::bytes(100);
+    let cyph = encrypt_to_many(&pks, &plain_a).unwrap();
+    for sk in sks {
+        let plain_b = decrypt_from_many(sk, &cyph).unwrap();
+        assert_eq!(plain_a, plain_b)
+    }
+}
+
+#[test]
+fn decrypt_from_many_with_hints_succ() {
+    let (sks, pks) = recipients(5);
+    let plain_a = Random::bytes(100);
+    let cyph = encrypt_to_many_with_hints(&pks, &plain_a).unwrap();
+    for sk in sks {
+        let plain_b = decrypt_from_many(sk, &cyph).unwrap();
+        assert_eq!(plain_a, plain_b)
+    }
+}
+
+#[test]
+fn decrypt_from_many_with_cipher_succ() {
+    let (sks, pks) = recipients(3);
+    let plain_a = Random::bytes(100);
+    let cyph = encrypt_to_many_with(CipherAlgorithm::XChaCha20Poly1305, &pks, false, &plain_a).unwrap();
+    for sk in sks {
+        let plain_b = decrypt_from_many(sk, &cyph).unwrap();
+        assert_eq!(plain_a, plain_b)
+    }
+}
+
+#[test]
+fn decrypt_from_many_not_recipient_fail() {
+    let (_, pks) = recipients(3);
+    let plain = Random::bytes(100);
+    let cyph = encrypt_to_many(&pks, &plain).unwrap();
+    let res = decrypt_from_many(SecretKey::random(), &cyph);
+    assert!(res.is_err());
+    assert_eq!(res.err().unwrap().kind(), ErrorKind::NotFound)
+}
+
+#[test]
+fn decrypt_from_many_tampered_slot_fail() {
+    let (sks, pks) = recipients(1);
+    let plain = Random::bytes(100);
+    let mut cyph = encrypt_to_many(&pks, &plain).unwrap();
+    cyph[38] ^= 1;
+    let res = decrypt_from_many(sks[0].clone(), &cyph);
+    assert!(res.is_err())
+}
+
+#[test]
+fn decrypt_from_many_tampered_fail() {
+    let (sks, pks) = recipients(3);
+    let plain = Random::bytes(100);
+    let mut cyph = encrypt_to_many(&pks, &plain).unwrap();
+    let len = cyph.len();
+    cyph[len - 1] ^= 1;
+    let res = decrypt_from_many(sks[0].clone(), &cyph);
+    assert!(res.is_err())
+}
+
+#[test]
+fn encrypt_to_many_fail() {
+    let plain = Random::bytes(100);
+    let res = encrypt_to_many(&[], &plain);
+    assert!(res.is_err())
+}
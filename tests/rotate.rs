@@ -0,0 +1,225 @@
+// Copyright 2018 Yobicash Ltd. See the COPYRIGHT file at the top-level directory
+// of this distribution.
+//
+// Licensed under the MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>
+// and the Apache 2.0 license <LICENSE-APACHE or https://opensource.org/licenses/Apache-2.0>.
+// This file may not be copied, modified, or distributed except according to those
+// terms.
+
+//! Yobicrypto `rotate` module tests.
+
+extern crate yobicrypto;
+
+use yobicrypto::{ErrorKind, Random, CipherAlgorithm};
+use yobicrypto::{SecretKey, Key};
+use yobicrypto::{sym_encrypt, sym_decrypt, sym_encrypt_with_ad, sym_decrypt_with_ad};
+use yobicrypto::{assym_encrypt, assym_decrypt};
+use yobicrypto::{StreamEncryptor, StreamDecryptor, STREAM_CHUNK_SIZE};
+use yobicrypto::{KeyRotation, RotationStatus, StreamCheckpoint};
+use yobicrypto::BinarySerialize;
+
+use std::io::{Read, Write};
+
+#[test]
+fn key_rotation_new_fail() {
+    let key = Key::new();
+    let res = KeyRotation::new(key.clone(), key);
+    assert!(res.is_err())
+}
+
+#[test]
+fn key_rotation_rotate_succ() {
+    let old_key = Key::new();
+    let new_key = Key::new();
+    let rotation = KeyRotation::new(old_key.clone(), new_key.clone()).unwrap();
+    let plain_a = Random::bytes(100);
+    let cyph = sym_encrypt(old_key.clone(), &plain_a).unwrap();
+    let rotated = rotation.rotate(&cyph).unwrap();
+    assert!(sym_decrypt(old_key, &rotated).is_err());
+    let plain_b = sym_decrypt(new_key, &rotated).unwrap();
+    assert_eq!(plain_a, plain_b)
+}
+
+#[test]
+fn key_rotation_rotate_with_ad_succ() {
+    let old_key = Key::new();
+    let new_key = Key::new();
+    let rotation = KeyRotation::new(old_key.clone(), new_key.clone()).unwrap();
+    let plain_a = Random::bytes(100);
+    let ad = Random::bytes(16);
+    let cyph = sym_encrypt_with_ad(old_key, &ad, &plain_a).unwrap();
+    let (status, rotated) = rotation.rotate_with_ad(&ad, &cyph).unwrap();
+    assert_eq!(status, RotationStatus::Rotated);
+    let plain_b = sym_decrypt_with_ad(new_key, &ad, &rotated).unwrap();
+    assert_eq!(plain_a, plain_b)
+}
+
+#[test]
+fn key_rotation_rotate_idempotent_succ() {
+    let old_key = Key::new();
+    let new_key = Key::new();
+    let rotation = KeyRotation::new(old_key.clone(), new_key).unwrap();
+    let plain = Random::bytes(100);
+    let cyph = sym_encrypt(old_key, &plain).unwrap();
+    let (_, rotated_a) = rotation.rotate_with_ad(&[], &cyph).unwrap();
+    let (status, rotated_b) = rotation.rotate_with_ad(&[], &rotated_a).unwrap();
+    assert_eq!(status, RotationStatus::AlreadyRotated);
+    assert_eq!(rotated_a, rotated_b)
+}
+
+#[test]
+fn key_rotation_rotate_fail() {
+    let rotation = KeyRotation::new(Key::new(), Key::new()).unwrap();
+    let plain = Random::bytes(100);
+    let cyph = sym_encrypt(Key::new(), &plain).unwrap();
+    let res = rotation.rotate(&cyph);
+    assert!(res.is_err());
+    assert_eq!(res.err().unwrap().kind(), ErrorKind::InvalidTag)
+}
+
+#[test]
+fn key_rotation_assym_succ() {
+    let old_sk = SecretKey::random();
+    let new_sk = SecretKey::random();
+    let sk = SecretKey::random();
+    let pk = sk.to_public();
+    let rotation = KeyRotation::assym(old_sk.clone(), new_sk.clone(), pk).unwrap();
+    let plain_a = Random::bytes(100);
+    let cyph = assym_encrypt(sk, old_sk.to_public(), &plain_a).unwrap();
+    let rotated = rotation.rotate(&cyph).unwrap();
+    let plain_b = assym_decrypt(new_sk, pk, &rotated).unwrap();
+    assert_eq!(plain_a, plain_b)
+}
+
+#[test]
+fn key_rotation_rotate_batch_succ() {
+    let old_key = Key::new();
+    let new_key = Key::new();
+    let rotation = KeyRotation::new(old_key.clone(), new_key.clone()).unwrap();
+    let plains: Vec<Vec<u8>> = (0..4).map(|_| Random::bytes(100)).collect();
+    let mut cyphs = vec![
+        sym_encrypt(old_key.clone(), &plains[0]).unwrap(),
+        sym_encrypt(new_key.clone(), &plains[1]).unwrap(),
+        sym_encrypt(Key::new(), &plains[2]).unwrap(),
+        sym_encrypt(old_key, &plains[3]).unwrap(),
+    ];
+    let failed = cyphs[2].clone();
+    let report = rotation.rotate_batch(&mut cyphs);
+    assert!(!report.is_complete());
+    assert_eq!(report.rotated, vec![0, 3]);
+    assert_eq!(report.already_rotated, vec![1]);
+    assert_eq!(report.failed, vec![(2, ErrorKind::InvalidTag)]);
+    assert_eq!(cyphs[2], failed);
+    for i in [0, 1, 3].iter() {
+        let plain = sym_decrypt(new_key.clone(), &cyphs[*i]).unwrap();
+        assert_eq!(plain, plains[*i])
+    }
+
+    // NB: resuming the rotation does not change the rotated cyphertexts
+    let rotated = cyphs.clone();
+    let report = rotation.rotate_batch(&mut cyphs);
+    assert_eq!(report.already_rotated, vec![0, 1, 3]);
+    assert_eq!(rotated, cyphs)
+}
+
+#[test]
+fn key_rotation_rotate_stream_succ() {
+    let old_key = Key::new();
+    let new_key = Key::new();
+    let rotation = KeyRotation::new(old_key.clone(), new_key.clone()).unwrap();
+    let plain_a = Random::bytes(2*STREAM_CHUNK_SIZE as u32 + 17);
+    let mut encryptor = StreamEncryptor::new(old_key, Vec::new()).unwrap();
+    encryptor.write_all(&plain_a).unwrap();
+    let cyph = encryptor.finish().unwrap();
+    let rotated = rotation.rotate_stream(&cyph[..], Vec::new()).unwrap();
+    let mut decryptor = StreamDecryptor::new(new_key, &rotated[..]).unwrap();
+    let mut plain_b = Vec::new();
+    decryptor.read_to_end(&mut plain_b).unwrap();
+    assert_eq!(plain_a, plain_b)
+}
+
+#[test]
+fn key_rotation_rotate_stream_fail() {
+    let rotation = KeyRotation::new(Key::new(), Key::new()).unwrap();
+    let plain = Random::bytes(100);
+    let mut encryptor = StreamEncryptor::new(Key::new(), Vec::new()).unwrap();
+    encryptor.write_all(&plain).unwrap();
+    let cyph = encryptor.finish().unwrap();
+    let res = rotation.rotate_stream(&cyph[..], Vec::new());
+    assert!(res.is_err())
+}
+
+#[test]
+fn key_rotation_resume_stream_succ() {
+    let old_key = Key::new();
+    let new_key = Key::new();
+    let rotation = KeyRotation::new(old_key.clone(), new_key.clone()).unwrap();
+    let plain_a = Random::bytes(4*STREAM_CHUNK_SIZE as u32 + 17);
+    let mut encryptor = StreamEncryptor::with_cipher(CipherAlgorithm::XChaCha20Poly1305, old_key, Vec::new()).unwrap();
+    encryptor.write_all(&plain_a).unwrap();
+    let cyph = encryptor.finish().unwrap();
+
+    // NB: the rotation is interrupted after 2 chunks, while writing the third one
+    let mut rotated = Vec::new();
+    let mut stored = Vec::new();
+    let res = rotation.rotate_stream_with_checkpoints(&cyph[..], &mut rotated, |_, checkpoint| {
+        stored = checkpoint.to_bytes()?;
+        if checkpoint.chunks == 2 {
+            return Err(ErrorKind::IOFailure.into());
+        }
+        Ok(())
+    });
+    assert!(res.is_err());
+    rotated.extend_from_slice(&Random::bytes(100));
+
+    let checkpoint = StreamCheckpoint::from_bytes(&stored).unwrap();
+    assert_eq!(checkpoint.chunks, 2);
+    let reader_offset = checkpoint.reader_offset().unwrap() as usize;
+    let writer_offset = checkpoint.writer_offset().unwrap() as usize;
+    rotated.truncate(writer_offset);
+
+    let mut checkpoints = Vec::new();
+    rotation.resume_stream(&checkpoint, &cyph[reader_offset..], &mut rotated, |_, checkpoint| {
+        checkpoints.push(checkpoint.chunks);
+        Ok(())
+    }).unwrap();
+    assert_eq!(checkpoints, vec![2, 3, 4]);
+
+    let mut decryptor = StreamDecryptor::new(new_key, &rotated[..]).unwrap();
+    assert_eq!(decryptor.algorithm(), CipherAlgorithm::XChaCha20Poly1305);
+    let mut plain_b = Vec::new();
+    decryptor.read_to_end(&mut plain_b).unwrap();
+    assert_eq!(plain_a, plain_b)
+}
+
+#[test]
+fn key_rotation_resume_stream_fail() {
+    let old_key = Key::new();
+    let rotation = KeyRotation::new(old_key.clone(), Key::new()).unwrap();
+    let plain = Random::bytes(3*STREAM_CHUNK_SIZE as u32);
+    let mut encryptor = StreamEncryptor::new(old_key, Vec::new()).unwrap();
+    encryptor.write_all(&plain).unwrap();
+    let cyph = encryptor.finish().unwrap();
+
+    let mut stored = None;
+    let res = rotation.rotate_stream_with_checkpoints(&cyph[..], Vec::new(), |_, checkpoint| {
+        stored = Some(checkpoint.clone());
+        if checkpoint.chunks == 1 {
+            return Err(ErrorKind::IOFailure.into());
+        }
+        Ok(())
+    });
+    assert!(res.is_err());
+
+    // NB: resuming from the wrong offset of the input fails the authentication
+    let checkpoint = stored.unwrap();
+    let res = rotation.resume_stream(&checkpoint, &cyph[..], Vec::new(), |_, _| Ok(()));
+    assert!(res.is_err());
+
+    let mut checkpoint = checkpoint;
+    checkpoint.new_header.pop();
+    let reader_offset = checkpoint.reader_offset().unwrap() as usize;
+    let res = rotation.resume_stream(&checkpoint, &cyph[reader_offset..], Vec::new(), |_, _| Ok(()));
+    assert!(res.is_err());
+    assert_eq!(res.unwrap_err().kind(), ErrorKind::InvalidLength)
+}
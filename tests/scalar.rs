@@ -11,7 +11,7 @@
 extern crate yobicrypto;
 extern crate hex;
 
-use yobicrypto::{Random, Scalar};
+use yobicrypto::{Random, Scalar, SecretScalar};
 use yobicrypto::{BinarySerialize, HexSerialize};
 
 #[test]
@@ -83,3 +83,66 @@ fn scalar_mul_succ() {
     let c = Scalar::from_u64(6).unwrap();
     assert_eq!(c, (&a*&b))
 }
+
+#[test]
+fn scalar_invert_succ() {
+    let scalar = Scalar::random();
+    let inverse = scalar.invert().unwrap();
+    assert_eq!(&scalar * &inverse, Scalar::from_u64(1).unwrap())
+}
+
+#[test]
+fn scalar_invert_fail() {
+    let res = Scalar::default().invert();
+    assert!(res.is_err())
+}
+
+#[test]
+fn scalar_from_bytes_wide_succ() {
+    let mut b = [0u8; 64];
+    b[0] = 7;
+    assert_eq!(Scalar::from_bytes_wide(&b), Scalar::from_u64(7).unwrap());
+
+    Random::bytes_mut(&mut b);
+    let scalar = Scalar::from_bytes_wide(&b);
+    assert!(Scalar::from_bytes(&scalar.to_bytes().unwrap()).is_ok())
+}
+
+#[test]
+fn scalar_eq_succ() {
+    let scalar_a = Scalar::random();
+    let scalar_b = Scalar::from_bytes(&scalar_a.to_bytes().unwrap()).unwrap();
+    assert_eq!(scalar_a, scalar_b);
+    assert_ne!(scalar_a, Scalar::random())
+}
+
+#[test]
+fn scalar_display_succ() {
+    let scalar = Scalar::random();
+    let scalar_hex = scalar.to_hex().unwrap();
+    assert!(format!("{}", scalar).contains(&scalar_hex));
+}
+
+#[test]
+fn secret_scalar_redacted() {
+    let secret = SecretScalar::random();
+    let secret_hex = secret.to_hex().unwrap();
+    assert!(!format!("{:?}", secret).contains(&secret_hex));
+    assert!(!format!("{}", secret).contains(&secret_hex));
+}
+
+#[test]
+fn secret_scalar_eq_succ() {
+    let secret_a = SecretScalar::random();
+    let secret_b = SecretScalar::from_hex(&secret_a.to_hex().unwrap()).unwrap();
+    assert_eq!(secret_a, secret_b);
+    assert_eq!(secret_a.expose_secret(), secret_b.expose_secret());
+    assert_ne!(secret_a, SecretScalar::random())
+}
+
+#[test]
+fn secret_scalar_from_bytes_fail() {
+    let b = [255u8; 32];
+    let res = SecretScalar::from_bytes(&b);
+    assert!(res.is_err())
+}
@@ -0,0 +1,273 @@
+// Copyright 2018 Yobicash Ltd. See the COPYRIGHT file at the top-level directory
+// of this distribution.
+//
+// Licensed under the MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>
+// and the Apache 2.0 license <LICENSE-APACHE or https://opensource.org/licenses/Apache-2.0>.
+// This file may not be copied, modified, or distributed except according to those
+// terms.
+
+//! Yobicrypto `signature` module tests.
+
+extern crate yobicrypto;
+extern crate hex;
+
+use yobicrypto::{ErrorKind, Random, SecretKey, PublicKey};
+use yobicrypto::{Signature, SigningKey};
+use yobicrypto::{BinarySerialize, HexSerialize};
+
+// RFC 8032, section 7.1: secret key, public key, message, signature
+fn ed25519_test_vectors() -> Vec<(String, String, String, String)> {
+    vec![
+        (
+            "9d61b19deffd5a60ba844af492ec2cc44449c5697b326919703bac031cae7f60".to_string(),
+            "d75a980182b10ab7d54bfed3c964073a0ee172f3daa62325af021a68f707511a".to_string(),
+            "".to_string(),
+            "e5564300c360ac729086e2cc806e828a84877f1eb8e5d974d873e065224901555fb8821590a33bacc61e39701cf9b46bd25bf5f0595bbe24655141438e7a100b".to_string(),
+        ),
+        (
+            "4ccd089b28ff96da9db6c346ec114e0f5b8a319f35aba624da8cf6ed4fb8a6fb".to_string(),
+            "3d4017c3e843895a92b70aa74d1b7ebc9c982ccf2ec4968cc0cd55f12af4660c".to_string(),
+            "72".to_string(),
+            "92a009a9f0d4cab8720e820b5f642540a2b27b5416503f8fb3762223ebdb69da085ac1e43e15996e458f3613d0f11d8c387b2eaeb4302aeeb00d291612bb0c00".to_string(),
+        ),
+        (
+            "c5aa8df43f9f837bedb7442f31dcb7b166d38535076f094b85ce3a2e0b4458f7".to_string(),
+            "fc51cd8e6218a1a38da47ed00230f0580816ed13ba3303ac5deb911548908025".to_string(),
+            "af82".to_string(),
+            "6291d657deec24024827e69c3abe01a30ce548a284743a445e3680d7db5ac3ac18ff9b538d16f290ae67f760984dc6594a7c15e9716ed28dc027beceea1ec40a".to_string(),
+        ),
+        (
+            "833fe62409237b9d62ec77587520911e9a759cec1d19755b7da901b96dca3d42".to_string(),
+            "ec172b93ad5e563bf4932c70e1245034c35467ef2efd4d64ebf819683467e2bf".to_string(),
+            "ddaf35a193617abacc417349ae20413112e6fa4e89a97ea20a9eeee64b55d39a2192992a274fc1a836ba3c23a3feebbd454d4423643ce80e2a9ac94fa54ca49f".to_string(),
+            "dc2a4459e7369633a52b1bf277839a00201009a3efbf3ecb69bea2186c26b58909351fc9ac90b3ecfdfbc7c66431e0303dca179c138ac17ad9bef1177331a704".to_string(),
+        ),
+    ]
+}
+
+// RFC 8032, section 7.2: secret key, public key, message, context, signature
+fn ed25519ctx_test_vectors() -> Vec<(String, String, String, String, String)> {
+    vec![
+        (
+            "0305334e381af78f141cb666f6199f57bc3495335a256a95bd2a55bf546663f6".to_string(),
+            "dfc9425e4f968f7f0c29f0259cf5f9aed6851c2bb4ad8bfb860cfee0ab248292".to_string(),
+            "f726936d19c800494e3fdaff20b276a8".to_string(),
+            "666f6f".to_string(),
+            "55a4cc2f70a54e04288c5f4cd1e45a7bb520b36292911876cada7323198dd87a8b36950b95130022907a7fb7c4e9b2d5f6cca685a587b4b21f4b888e4e7edb0d".to_string(),
+        ),
+        (
+            "0305334e381af78f141cb666f6199f57bc3495335a256a95bd2a55bf546663f6".to_string(),
+            "dfc9425e4f968f7f0c29f0259cf5f9aed6851c2bb4ad8bfb860cfee0ab248292".to_string(),
+            "f726936d19c800494e3fdaff20b276a8".to_string(),
+            "626172".to_string(),
+            "fc60d5872fc46b3aa69f8b5b4351d5808f92bcc044606db097abab6dbcb1aee3216c48e8b3b66431b5b186d1d28f8ee15a5ca2df6668346291c2043d4eb3e90d".to_string(),
+        ),
+        (
+            "0305334e381af78f141cb666f6199f57bc3495335a256a95bd2a55bf546663f6".to_string(),
+            "dfc9425e4f968f7f0c29f0259cf5f9aed6851c2bb4ad8bfb860cfee0ab248292".to_string(),
+            "508e9e6882b979fea900f62adceaca35".to_string(),
+            "666f6f".to_string(),
+            "8b70c1cc8310e1de20ac53ce28ae6e7207f33c3295e03bb5c0732a1d20dc64908922a8b052cf99b7c4fe107a5abb5b2c4085ae75890d02df26269d8945f84b0b".to_string(),
+        ),
+        (
+            "ab9c2853ce297ddab85c993b3ae14bcad39b2c682beabc27d6d4eb20711d6560".to_string(),
+            "0f1d1274943b91415889152e893d80e93275a1fc0b65fd71b4b0dda10ad7d772".to_string(),
+            "f726936d19c800494e3fdaff20b276a8".to_string(),
+            "666f6f".to_string(),
+            "21655b5f1aa965996b3f97b3c849eafba922a0a62992f73b3d1b73106a84ad85e9b86a7b6005ea868337ff2d20a7f5fbd4cd10b0be49a68da2b2e0dc0ad8960f".to_string(),
+        ),
+    ]
+}
+
+// RFC 8032, section 7.3: secret key, public key, message, signature
+fn ed25519ph_test_vectors() -> Vec<(String, String, String, String)> {
+    vec![
+        (
+            "833fe62409237b9d62ec77587520911e9a759cec1d19755b7da901b96dca3d42".to_string(),
+            "ec172b93ad5e563bf4932c70e1245034c35467ef2efd4d64ebf819683467e2bf".to_string(),
+            "616263".to_string(),
+            "98a70222f0b8121aa9d30f813d683f809e462b469c7ff87639499bb94e6dae4131f85042463c2a355a2003d062adf5aaa10b8c61e636062aaad11c2a26083406".to_string(),
+        ),
+    ]
+}
+
+// The order of the basepoint, in little-endian.
+const BASEPOINT_ORDER: [u8; 32] = [
+    0xed, 0xd3, 0xf5, 0x5c, 0x1a, 0x63, 0x12, 0x58, 0xd6, 0x9c, 0xf7, 0xa2, 0xde, 0xf9, 0xde, 0x14,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x10,
+];
+
+#[test]
+fn ed25519_test_vectors_succ() {
+    for v in ed25519_test_vectors() {
+        let key = SigningKey::from_seed(&hex::decode(&v.0).unwrap()).unwrap();
+        assert_eq!(key.public_key().to_hex().unwrap(), v.1);
+
+        let msg = hex::decode(&v.2).unwrap();
+        let sig = key.sign(&msg).unwrap();
+        assert_eq!(sig.to_hex().unwrap(), v.3);
+
+        let pk = PublicKey::from_hex(&v.1).unwrap();
+        assert!(pk.verify(&msg, &sig).is_ok())
+    }
+}
+
+#[test]
+fn ed25519ctx_test_vectors_succ() {
+    for v in ed25519ctx_test_vectors() {
+        let key = SigningKey::from_seed(&hex::decode(&v.0).unwrap()).unwrap();
+        assert_eq!(key.public_key().to_hex().unwrap(), v.1);
+
+        let msg = hex::decode(&v.2).unwrap();
+        let ctx = hex::decode(&v.3).unwrap();
+        let sig = key.sign_ctx(&ctx, &msg).unwrap();
+        assert_eq!(sig.to_hex().unwrap(), v.4);
+
+        let pk = PublicKey::from_hex(&v.1).unwrap();
+        assert!(pk.verify_ctx(&ctx, &msg, &sig).is_ok());
+        assert!(pk.verify_ctx(b"other", &msg, &sig).is_err());
+        assert!(pk.verify(&msg, &sig).is_err())
+    }
+}
+
+#[test]
+fn ed25519ph_test_vectors_succ() {
+    for v in ed25519ph_test_vectors() {
+        let key = SigningKey::from_seed(&hex::decode(&v.0).unwrap()).unwrap();
+        assert_eq!(key.public_key().to_hex().unwrap(), v.1);
+
+        let msg = hex::decode(&v.2).unwrap();
+        let sig = key.sign_ph(&[], &msg).unwrap();
+        assert_eq!(sig.to_hex().unwrap(), v.3);
+
+        let pk = PublicKey::from_hex(&v.1).unwrap();
+        assert!(pk.verify_ph(&[], &msg, &sig).is_ok());
+        assert!(pk.verify(&msg, &sig).is_err())
+    }
+}
+
+#[test]
+fn secret_key_sign_succ() {
+    let sk = SecretKey::random();
+    let pk = sk.to_public();
+    let msg = Random::bytes(64);
+
+    let sig = sk.sign(&msg).unwrap();
+    assert!(pk.verify(&msg, &sig).is_ok());
+
+    // NB: the signatures are deterministic
+    assert_eq!(sk.sign(&msg).unwrap(), sig);
+
+    let sig = sk.sign_ctx(b"context", &msg).unwrap();
+    assert!(pk.verify_ctx(b"context", &msg, &sig).is_ok());
+
+    let sig = sk.sign_ph(b"context", &msg).unwrap();
+    assert!(pk.verify_ph(b"context", &msg, &sig).is_ok())
+}
+
+#[test]
+fn signing_key_from_secret_key_succ() {
+    let sk = SecretKey::random();
+    let key = SigningKey::from_secret_key(sk.clone());
+    assert_eq!(key.secret_key(), &sk);
+    assert_eq!(key.public_key(), sk.to_public());
+    assert_eq!(key.sign(b"message").unwrap(), sk.sign(b"message").unwrap());
+    assert!(!format!("{:?}", key).contains(&hex::encode(sk.expose_secret())))
+}
+
+#[test]
+fn verify_fail() {
+    let sk = SecretKey::random();
+    let pk = sk.to_public();
+    let sig = sk.sign(b"message").unwrap();
+
+    let res = pk.verify(b"other message", &sig);
+    assert_eq!(res.unwrap_err().kind(), ErrorKind::InvalidSignature);
+
+    let res = SecretKey::random().to_public().verify(b"message", &sig);
+    assert_eq!(res.unwrap_err().kind(), ErrorKind::InvalidSignature);
+
+    let mut tampered = sig;
+    tampered.r[0] ^= 1;
+    assert!(pk.verify(b"message", &tampered).is_err());
+
+    let mut tampered = sig;
+    tampered.s[0] ^= 1;
+    assert!(pk.verify(b"message", &tampered).is_err())
+}
+
+#[test]
+fn verify_malleated_fail() {
+    let sk = SecretKey::random();
+    let pk = sk.to_public();
+    let sig = sk.sign(b"message").unwrap();
+
+    // NB: S + l is a valid signature for a non-strict verifier
+    let mut malleated = sig;
+    let mut carry = 0u16;
+    for i in 0..32 {
+        let sum = malleated.s[i] as u16 + BASEPOINT_ORDER[i] as u16 + carry;
+        malleated.s[i] = sum as u8;
+        carry = sum >> 8;
+    }
+    assert_eq!(carry, 0);
+
+    let res = pk.verify(b"message", &malleated);
+    assert_eq!(res.unwrap_err().kind(), ErrorKind::InvalidSignature)
+}
+
+#[test]
+fn verify_small_order_fail() {
+    // NB: the identity, with S = 0, verifies any message with a non-strict verifier
+    let mut identity = [0u8; 32];
+    identity[0] = 1;
+
+    let sig = Signature {
+        r: identity,
+        s: [0u8; 32],
+    };
+
+    let pk = PublicKey::from_bytes(&identity).unwrap();
+    let res = pk.verify(b"message", &sig);
+    assert_eq!(res.unwrap_err().kind(), ErrorKind::InvalidSignature);
+
+    let res = SecretKey::random().to_public().verify(b"message", &sig);
+    assert_eq!(res.unwrap_err().kind(), ErrorKind::InvalidSignature)
+}
+
+#[test]
+fn sign_context_fail() {
+    let sk = SecretKey::random();
+
+    let res = sk.sign_ctx(&[], b"message");
+    assert_eq!(res.unwrap_err().kind(), ErrorKind::InvalidArgument);
+
+    let res = sk.sign_ctx(&[0u8; 256], b"message");
+    assert_eq!(res.unwrap_err().kind(), ErrorKind::InvalidLength);
+
+    let res = sk.sign_ph(&[0u8; 256], b"message");
+    assert_eq!(res.unwrap_err().kind(), ErrorKind::InvalidLength)
+}
+
+#[test]
+fn signing_key_from_seed_fail() {
+    let res = SigningKey::from_seed(&[0u8; 31]);
+    assert_eq!(res.unwrap_err().kind(), ErrorKind::InvalidLength)
+}
+
+#[test]
+fn signature_to_bytes_succ() {
+    let sig_a = SecretKey::random().sign(b"message").unwrap();
+    let b = sig_a.to_bytes().unwrap();
+    assert_eq!(b.len(), 64);
+    let sig_b = Signature::from_bytes(&b).unwrap();
+    assert_eq!(sig_a, sig_b)
+}
+
+#[test]
+fn signature_to_hex_succ() {
+    let sig_a = SecretKey::random().sign(b"message").unwrap();
+    let s = sig_a.to_hex().unwrap();
+    let sig_b = Signature::from_hex(&s).unwrap();
+    assert_eq!(sig_a, sig_b)
+}
@@ -0,0 +1,158 @@
+// Copyright 2018 Yobicash Ltd. See the COPYRIGHT file at the top-level directory
+// of this distribution.
+//
+// Licensed under the MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>
+// and the Apache 2.0 license <LICENSE-APACHE or https://opensource.org/licenses/Apache-2.0>.
+// This file may not be copied, modified, or distributed except according to those
+// terms.
+
+//! Yobicrypto `siv` module tests.
+
+extern crate yobicrypto;
+extern crate hex;
+
+use yobicrypto::{ErrorKind, Random, Key};
+use yobicrypto::HexSerialize;
+use yobicrypto::AEADCipher;
+use yobicrypto::AES256GCMSIV;
+use yobicrypto::{encrypt_deterministic, decrypt_deterministic};
+
+// RFC 8452, appendix C.2 and C.3
+fn gcm_siv_test_vectors() -> Vec<(String, String, String, String, String)> {
+    vec![
+        (
+            "0100000000000000000000000000000000000000000000000000000000000000".to_string(),
+            "030000000000000000000000".to_string(),
+            "".to_string(),
+            "".to_string(),
+            "07f5f4169bbf55a8400cd47ea6fd400f".to_string(),
+        ),
+        (
+            "0100000000000000000000000000000000000000000000000000000000000000".to_string(),
+            "030000000000000000000000".to_string(),
+            "0100000000000000".to_string(),
+            "".to_string(),
+            "c2ef328e5c71c83b843122130f7364b761e0b97427e3df28".to_string(),
+        ),
+        (
+            "0100000000000000000000000000000000000000000000000000000000000000".to_string(),
+            "030000000000000000000000".to_string(),
+            "010000000000000000000000".to_string(),
+            "".to_string(),
+            "9aab2aeb3faa0a34aea8e2b18ca50da9ae6559e48fd10f6e5c9ca17e".to_string(),
+        ),
+        (
+            "0100000000000000000000000000000000000000000000000000000000000000".to_string(),
+            "030000000000000000000000".to_string(),
+            "0100000000000000000000000000000002000000000000000000000000000000".to_string(),
+            "".to_string(),
+            "4a6a9db4c8c6549201b9edb53006cba821ec9cf850948a7c86c68ac7539d027fe819e63abcd020b006a976397632eb5d".to_string(),
+        ),
+        (
+            "0100000000000000000000000000000000000000000000000000000000000000".to_string(),
+            "030000000000000000000000".to_string(),
+            "0200000000000000".to_string(),
+            "01".to_string(),
+            "1de22967237a813291213f267e3b452f02d01ae33e4ec854".to_string(),
+        ),
+        (
+            "0100000000000000000000000000000000000000000000000000000000000000".to_string(),
+            "030000000000000000000000".to_string(),
+            "02000000000000000000000000000000".to_string(),
+            "010000000000000000000000".to_string(),
+            "a463fcad737f8a3069d2b7575e79c6b7feae1d0f24321df9ccf01f22033a0b30".to_string(),
+        ),
+        (
+            "e66021d5eb8e4f4066d4adb9c33560e4f46e44bb3da0015c94f7088736864200".to_string(),
+            "e0eaf5284d884a0e77d31646".to_string(),
+            "".to_string(),
+            "".to_string(),
+            "169fbb2fbf389a995f6390af22228a62".to_string(),
+        ),
+        (
+            "0000000000000000000000000000000000000000000000000000000000000000".to_string(),
+            "000000000000000000000000".to_string(),
+            "000000000000000000000000000000004db923dc793ee6497c76dcc03a98e108".to_string(),
+            "".to_string(),
+            "f3f80f2cf0cb2dd9c5984fcda908456cc537703b5ba70324a6793a7bf218d3eaffffffff000000000000000000000000".to_string(),
+        ),
+        (
+            "0000000000000000000000000000000000000000000000000000000000000000".to_string(),
+            "000000000000000000000000".to_string(),
+            "eb3640277c7ffd1303c7a542d02d3e4c0000000000000000".to_string(),
+            "".to_string(),
+            "18ce4f0b8cb4d0cac65fea8f79257b20888e53e72299e56dffffffff000000000000000000000000".to_string(),
+        ),
+    ]
+}
+
+#[test]
+fn gcm_siv_encrypt_test_vectors() {
+    for v in gcm_siv_test_vectors() {
+        let key = Key::from_hex(&v.0).unwrap();
+        let nonce = hex::decode(v.1).unwrap();
+        let plain = hex::decode(v.2).unwrap();
+        let ad = hex::decode(v.3).unwrap();
+        let cyph = hex::decode(v.4).unwrap();
+        let res = AES256GCMSIV::new(key).unwrap().encrypt(&nonce, &ad, &plain).unwrap();
+        assert_eq!(res, cyph)
+    }
+}
+
+#[test]
+fn gcm_siv_decrypt_test_vectors() {
+    for v in gcm_siv_test_vectors() {
+        let key = Key::from_hex(&v.0).unwrap();
+        let nonce = hex::decode(v.1).unwrap();
+        let plain = hex::decode(v.2).unwrap();
+        let ad = hex::decode(v.3).unwrap();
+        let cyph = hex::decode(v.4).unwrap();
+        let res = AES256GCMSIV::new(key).unwrap().decrypt(&nonce, &ad, &cyph).unwrap();
+        assert_eq!(res, plain)
+    }
+}
+
+#[test]
+fn gcm_siv_decrypt_fail() {
+    let key = Key::new();
+    let nonce = Random::bytes(12);
+    let plain = Random::bytes(100);
+    let mut cyph = AES256GCMSIV::new(key.clone()).unwrap().encrypt(&nonce, &[], &plain).unwrap();
+    cyph[0] ^= 1;
+    let res = AES256GCMSIV::new(key).unwrap().decrypt(&nonce, &[], &cyph);
+    assert_eq!(res.unwrap_err().kind(), ErrorKind::InvalidTag)
+}
+
+#[test]
+fn decrypt_deterministic_succ() {
+    let key = Key::new();
+    let plain_a = Random::bytes(100);
+    let ad = Random::bytes(16);
+    let cyph = encrypt_deterministic(key.clone(), &ad, &plain_a).unwrap();
+    let plain_b = decrypt_deterministic(key, &ad, &cyph).unwrap();
+    assert_eq!(plain_a, plain_b)
+}
+
+#[test]
+fn decrypt_deterministic_fail() {
+    let key = Key::new();
+    let plain = Random::bytes(100);
+    let cyph = encrypt_deterministic(key.clone(), b"ad", &plain).unwrap();
+    let res = decrypt_deterministic(key, b"other ad", &cyph);
+    assert_eq!(res.unwrap_err().kind(), ErrorKind::InvalidTag)
+}
+
+#[test]
+fn encrypt_deterministic_succ() {
+    let key = Key::new();
+    let plain_a = Random::bytes(100);
+    let mut plain_b = plain_a.clone();
+    plain_b[99] ^= 1;
+    let cyph_a = encrypt_deterministic(key.clone(), &[], &plain_a).unwrap();
+    let cyph_b = encrypt_deterministic(key.clone(), &[], &plain_a).unwrap();
+    let cyph_c = encrypt_deterministic(key.clone(), &[], &plain_b).unwrap();
+    let cyph_d = encrypt_deterministic(key, b"ad", &plain_a).unwrap();
+    assert_eq!(cyph_a, cyph_b);
+    assert_ne!(cyph_a, cyph_c);
+    assert_ne!(cyph_a, cyph_d)
+}
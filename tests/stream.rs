@@ -0,0 +1,135 @@
+// Copyright 2018 Yobicash Ltd. See the COPYRIGHT file at the top-level directory
+// of this distribution.
+//
+// Licensed under the MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>
+// and the Apache 2.0 license <LICENSE-APACHE or https://opensource.org/licenses/Apache-2.0>.
+// This file may not be copied, modified, or distributed except according to those
+// terms.
+
+//! Yobicrypto `stream` module tests.
+
+extern crate yobicrypto;
+
+use yobicrypto::Random;
+use yobicrypto::{SecretKey, Key, CipherAlgorithm};
+use yobicrypto::{StreamEncryptor, StreamDecryptor};
+use yobicrypto::{STREAM_CHUNK_SIZE, GCM_TAG_SIZE, stream_header_size};
+
+use std::io::{Read, Write};
+
+fn encrypt(key: Key, plain: &[u8]) -> Vec<u8> {
+    let mut encryptor = StreamEncryptor::new(key, Vec::new()).unwrap();
+    for chunk in plain.chunks(1000) {
+        encryptor.write_all(chunk).unwrap();
+    }
+    encryptor.finish().unwrap()
+}
+
+fn decrypt(key: Key, cyph: &[u8]) -> std::io::Result<Vec<u8>> {
+    let mut decryptor = StreamDecryptor::new(key, cyph).unwrap();
+    let mut plain = Vec::new();
+    decryptor.read_to_end(&mut plain)?;
+    Ok(plain)
+}
+
+#[test]
+fn stream_decrypt_succ() {
+    let sizes = [0, 1, STREAM_CHUNK_SIZE - 1, STREAM_CHUNK_SIZE, STREAM_CHUNK_SIZE + 1, 3*STREAM_CHUNK_SIZE + 17];
+    for size in sizes.iter() {
+        let key = Key::new();
+        let plain_a = Random::bytes(*size as u32);
+        let cyph = encrypt(key.clone(), &plain_a);
+        let plain_b = decrypt(key, &cyph).unwrap();
+        assert_eq!(plain_a, plain_b)
+    }
+}
+
+#[test]
+fn stream_decrypt_with_cipher_succ() {
+    let algorithms = vec![
+        CipherAlgorithm::AES256GCM,
+        CipherAlgorithm::ChaCha20Poly1305,
+        CipherAlgorithm::XChaCha20Poly1305,
+        CipherAlgorithm::AES256GCMSIV,
+    ];
+
+    for algorithm in algorithms {
+        let key = Key::new();
+        let plain_a = Random::bytes(2*STREAM_CHUNK_SIZE as u32 + 5);
+        let mut encryptor = StreamEncryptor::with_cipher(algorithm, key.clone(), Vec::new()).unwrap();
+        assert_eq!(encryptor.algorithm(), algorithm);
+        encryptor.write_all(&plain_a).unwrap();
+        let cyph = encryptor.finish().unwrap();
+        assert_eq!(cyph[0], algorithm.to_u8());
+        let mut decryptor = StreamDecryptor::new(key, &cyph[..]).unwrap();
+        assert_eq!(decryptor.algorithm(), algorithm);
+        let mut plain_b = Vec::new();
+        decryptor.read_to_end(&mut plain_b).unwrap();
+        assert_eq!(plain_a, plain_b)
+    }
+}
+
+#[test]
+fn stream_assym_decrypt_succ() {
+    let sk_a = SecretKey::random();
+    let sk_b = SecretKey::random();
+    let pk_a = sk_a.to_public();
+    let pk_b = sk_b.to_public();
+    let plain_a = Random::bytes(2*STREAM_CHUNK_SIZE as u32 + 5);
+    let mut encryptor = StreamEncryptor::assym(sk_a, pk_b, Vec::new()).unwrap();
+    encryptor.write_all(&plain_a).unwrap();
+    let cyph = encryptor.finish().unwrap();
+    let mut decryptor = StreamDecryptor::assym(sk_b, pk_a, &cyph[..]).unwrap();
+    let mut plain_b = Vec::new();
+    decryptor.read_to_end(&mut plain_b).unwrap();
+    assert_eq!(plain_a, plain_b)
+}
+
+#[test]
+fn stream_decrypt_tampered_fail() {
+    let key = Key::new();
+    let plain = Random::bytes(STREAM_CHUNK_SIZE as u32 + 5);
+    let mut cyph = encrypt(key.clone(), &plain);
+    cyph[stream_header_size(CipherAlgorithm::default()) + 3] ^= 1;
+    assert!(decrypt(key, &cyph).is_err())
+}
+
+#[test]
+fn stream_decrypt_truncated_fail() {
+    let key = Key::new();
+    let plain = Random::bytes(3*STREAM_CHUNK_SIZE as u32);
+    let cyph = encrypt(key.clone(), &plain);
+    let chunk_len = STREAM_CHUNK_SIZE + GCM_TAG_SIZE;
+    let truncated = &cyph[..stream_header_size(CipherAlgorithm::default()) + 2*chunk_len];
+    assert!(decrypt(key.clone(), truncated).is_err());
+    let header_only = &cyph[..stream_header_size(CipherAlgorithm::default())];
+    assert!(decrypt(key, header_only).is_err())
+}
+
+#[test]
+fn stream_decrypt_reordered_fail() {
+    let key = Key::new();
+    let plain = Random::bytes(3*STREAM_CHUNK_SIZE as u32);
+    let cyph = encrypt(key.clone(), &plain);
+    let chunk_len = STREAM_CHUNK_SIZE + GCM_TAG_SIZE;
+    let start = stream_header_size(CipherAlgorithm::default());
+    let mut reordered = Vec::new();
+    reordered.extend_from_slice(&cyph[..start]);
+    reordered.extend_from_slice(&cyph[start + chunk_len..start + 2*chunk_len]);
+    reordered.extend_from_slice(&cyph[start..start + chunk_len]);
+    reordered.extend_from_slice(&cyph[start + 2*chunk_len..]);
+    assert!(decrypt(key, &reordered).is_err())
+}
+
+#[test]
+fn stream_decrypt_duplicated_fail() {
+    let key = Key::new();
+    let plain = Random::bytes(2*STREAM_CHUNK_SIZE as u32);
+    let cyph = encrypt(key.clone(), &plain);
+    let chunk_len = STREAM_CHUNK_SIZE + GCM_TAG_SIZE;
+    let start = stream_header_size(CipherAlgorithm::default());
+    let mut duplicated = Vec::new();
+    duplicated.extend_from_slice(&cyph[..start + chunk_len]);
+    duplicated.extend_from_slice(&cyph[start..]);
+    assert!(decrypt(key, &duplicated).is_err())
+}
@@ -0,0 +1,43 @@
+// Copyright 2018 Yobicash Ltd. See the COPYRIGHT file at the top-level directory
+// of this distribution.
+//
+// Licensed under the MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>
+// and the Apache 2.0 license <LICENSE-APACHE or https://opensource.org/licenses/Apache-2.0>.
+// This file may not be copied, modified, or distributed except according to those
+// terms.
+
+//! Yobicrypto `wipe` module tests.
+
+extern crate yobicrypto;
+
+use yobicrypto::Random;
+use yobicrypto::{Wipe, wipe_value};
+
+#[test]
+fn wipe_slice_succ() {
+    let mut buf = Random::bytes(64);
+    buf[..].wipe();
+    assert_eq!(buf, vec![0u8; 64]);
+}
+
+#[test]
+fn wipe_vec_succ() {
+    let mut buf = Random::bytes(64);
+    buf.truncate(32);
+    buf.wipe();
+    assert!(buf.is_empty());
+}
+
+#[test]
+fn wipe_words_succ() {
+    let mut words = [u32::max_value(); 8];
+    words.wipe();
+    assert_eq!(words, [0u32; 8]);
+}
+
+#[test]
+fn wipe_value_succ() {
+    let mut value = [u64::max_value(); 2];
+    wipe_value(&mut value, [0u64; 2]);
+    assert_eq!(value, [0u64; 2]);
+}
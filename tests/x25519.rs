@@ -0,0 +1,167 @@
+// Copyright 2018 Yobicash Ltd. See the COPYRIGHT file at the top-level directory
+// of this distribution.
+//
+// Licensed under the MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>
+// and the Apache 2.0 license <LICENSE-APACHE or https://opensource.org/licenses/Apache-2.0>.
+// This file may not be copied, modified, or distributed except according to those
+// terms.
+
+//! Yobicrypto `x25519` module tests.
+
+extern crate yobicrypto;
+extern crate hex;
+
+use yobicrypto::ErrorKind;
+use yobicrypto::{X25519SecretKey, X25519PublicKey, X25519_BASEPOINT};
+use yobicrypto::{x25519, x25519_clamp};
+use yobicrypto::Key;
+use yobicrypto::{BinarySerialize, HexSerialize};
+
+// RFC 7748, section 5.2
+fn x25519_test_vectors() -> Vec<(String, String, String)> {
+    vec![
+        (
+            "a546e36bf0527c9d3b16154b82465edd62144c0ac1fc5a18506a2244ba449ac4".to_string(),
+            "e6db6867583030db3594c1a424b15f7c726624ec26b3353b10a903a6d0ab1c4c".to_string(),
+            "c3da55379de9c6908e94ea4df28d084f32eccf03491c71f754b4075577a28552".to_string(),
+        ),
+        (
+            "4b66e9d4d1b4673c5ad22691957d6af5c11b6421e0ea01d42ca4169e7918ba0d".to_string(),
+            "e5210f12786811d3f4b7959d0538ae2c31dbe7106fc03c3efc4cd549c715a493".to_string(),
+            "95cbde9476e8907d7aade45cb4b873f88b595a68799fa152e6f8f7647aac7957".to_string(),
+        ),
+    ]
+}
+
+// RFC 7748, section 6.1
+fn x25519_dh_test_vectors() -> Vec<(String, String, String, String, String)> {
+    vec![
+        (
+            "77076d0a7318a57d3c16c17251b26645df4c2f87ebc0992ab177fba51db92c2a".to_string(),
+            "8520f0098930a754748b7ddcb43ef75a0dbf3a0d26381af4eba4a98eaa9b4e6a".to_string(),
+            "5dab087e624a8a4b79e17f8b83800ee66f3bb1292618b6fd1c2f8b27ff88e0eb".to_string(),
+            "de9edb7d7b7dc1b4d35b61c2ece435373f8343c85b78674dadfc7e146f882b4f".to_string(),
+            "4a5d9d5ba4ce2de1728e3bf480350f25e07e21c947d19e3376f09b3c1e161742".to_string(),
+        ),
+    ]
+}
+
+// The point with u = 1, of order 4.
+const LOW_ORDER_POINT: [u8; 32] = [
+    1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+    0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+];
+
+fn to_array(s: &str) -> [u8; 32] {
+    let mut b = [0u8; 32];
+    b.copy_from_slice(&hex::decode(s).unwrap());
+    b
+}
+
+#[test]
+fn x25519_test_vectors_succ() {
+    for v in x25519_test_vectors() {
+        let k = to_array(&v.0);
+        let u = to_array(&v.1);
+        let res = x25519(k, u);
+        assert_eq!(hex::encode(&res), v.2)
+    }
+}
+
+#[test]
+fn x25519_iterations_succ() {
+    let mut k = X25519_BASEPOINT;
+    let mut u = X25519_BASEPOINT;
+
+    for i in 0..1000 {
+        let res = x25519(k, u);
+        u = k;
+        k = res;
+
+        if i == 0 {
+            assert_eq!(hex::encode(&k),
+                "422c8e7a6227d7bca1350b3e2bb7279f7897b87bb6854b783c60e80311ae3079");
+        }
+    }
+
+    assert_eq!(hex::encode(&k),
+        "684cf59ba83309552800ef566f2f4d3c1c3887c49360e3875f2eb94d99532c51");
+}
+
+#[test]
+fn x25519_clamp_succ() {
+    let k = x25519_clamp([0xff; 32]);
+    assert_eq!(k[0], 0xf8);
+    assert_eq!(k[31], 0x7f);
+    let k = x25519_clamp([0; 32]);
+    assert_eq!(k[31], 0x40);
+}
+
+#[test]
+fn x25519_diffie_hellman_test_vectors_succ() {
+    for v in x25519_dh_test_vectors() {
+        let sk_a = X25519SecretKey::from_hex(&v.0).unwrap();
+        let pk_a = X25519PublicKey::from_hex(&v.1).unwrap();
+        let sk_b = X25519SecretKey::from_hex(&v.2).unwrap();
+        let pk_b = X25519PublicKey::from_hex(&v.3).unwrap();
+        assert_eq!(sk_a.to_public(), pk_a);
+        assert_eq!(sk_b.to_public(), pk_b);
+        let shared_a = sk_a.diffie_hellman(&pk_b).unwrap();
+        let shared_b = sk_b.diffie_hellman(&pk_a).unwrap();
+        assert_eq!(hex::encode(&shared_a), v.4);
+        assert_eq!(hex::encode(&shared_b), v.4)
+    }
+}
+
+#[test]
+fn x25519_diffie_hellman_succ() {
+    let sk_a = X25519SecretKey::random();
+    let sk_b = X25519SecretKey::random();
+    let shared_a = sk_a.diffie_hellman(&sk_b.to_public()).unwrap();
+    let shared_b = sk_b.diffie_hellman(&sk_a.to_public()).unwrap();
+    assert_eq!(shared_a, shared_b)
+}
+
+#[test]
+fn x25519_diffie_hellman_low_order_fail() {
+    let sk = X25519SecretKey::random();
+    let low_order = vec![
+        X25519PublicKey::new([0u8; 32]),
+        X25519PublicKey::new(LOW_ORDER_POINT),
+    ];
+    for pk in low_order {
+        let res = sk.diffie_hellman(&pk);
+        assert!(res.is_err());
+        assert_eq!(res.err().unwrap().kind(), ErrorKind::InvalidSharedSecret)
+    }
+}
+
+#[test]
+fn x25519_shared_key_succ() {
+    let sk_a = X25519SecretKey::random();
+    let sk_b = X25519SecretKey::random();
+    let key_a = Key::shared_x25519(sk_a.clone(), sk_b.to_public()).unwrap();
+    let key_b = Key::shared_x25519(sk_b, sk_a.to_public()).unwrap();
+    assert_eq!(key_a, key_b)
+}
+
+#[test]
+fn x25519_public_key_from_bytes_fail() {
+    let res = X25519PublicKey::from_bytes(&[0u8; 31]);
+    assert!(res.is_err())
+}
+
+#[test]
+fn x25519_secret_key_expose_secret_succ() {
+    let sk_a = X25519SecretKey::random();
+    let sk_b = X25519SecretKey::from_bytes(sk_a.expose_secret()).unwrap();
+    assert_eq!(sk_a, sk_b)
+}
+
+#[test]
+fn x25519_secret_key_redacted() {
+    let sk = X25519SecretKey::random();
+    let sk_hex = hex::encode(sk.expose_secret());
+    assert!(!format!("{:?}", sk).contains(&sk_hex));
+    assert!(!format!("{}", sk).contains(&sk_hex));
+}
@@ -10,25 +10,467 @@
 
 extern crate yobicrypto;
 
-use yobicrypto::{Random, Scalar, ZKPWitness, ZKPProof};
+use yobicrypto::{ErrorKind, Random, Scalar, Point, ZKPWitness, ZKPProof, ZKPCompactProof};
+use yobicrypto::{BinarySerialize, HexSerialize};
 
 #[test]
 fn schnorr_protocol_verify_succ() {
     let instance = Scalar::random();
-    let witness = ZKPWitness::new(instance).unwrap();
+    let witness = ZKPWitness::new(instance.clone()).unwrap();
     let message = Random::bytes(64);
-    let proof = ZKPProof::new(instance, &message).unwrap();
-    let verified = proof.verify(witness).unwrap();
+    let proof = ZKPProof::prove(instance, &message, b"context").unwrap();
+    let verified = proof.verify(witness, &message, b"context").unwrap();
     assert!(verified)
 }
 
 #[test]
 fn schnorr_protocol_verify_fail() {
+    let instance = Scalar::random();
+    let message = Random::bytes(64);
+    let proof = ZKPProof::prove(instance, &message, b"context").unwrap();
+    let faulty_instance = Scalar::random();
+    let faulty_witness = ZKPWitness::new(faulty_instance).unwrap();
+    let verified = proof.verify(faulty_witness, &message, b"context").unwrap();
+    assert!(!verified)
+}
+
+#[test]
+fn schnorr_protocol_verify_message_fail() {
+    let instance = Scalar::random();
+    let witness = ZKPWitness::new(instance.clone()).unwrap();
+    let message = Random::bytes(64);
+    let proof = ZKPProof::prove(instance, &message, b"context").unwrap();
+    let faulty_message = Random::bytes(64);
+    let verified = proof.verify(witness, &faulty_message, b"context").unwrap();
+    assert!(!verified)
+}
+
+#[test]
+fn schnorr_protocol_verify_context_fail() {
+    let instance = Scalar::random();
+    let witness = ZKPWitness::new(instance.clone()).unwrap();
+    let message = Random::bytes(64);
+    let proof = ZKPProof::prove(instance, &message, b"context").unwrap();
+    let verified = proof.verify(witness, &message, b"other context").unwrap();
+    assert!(!verified)
+}
+
+#[test]
+fn schnorr_protocol_verify_legacy_succ() {
+    let instance = Scalar::random();
+    let witness = ZKPWitness::new(instance.clone()).unwrap();
+    let message = Random::bytes(64);
+    let proof = ZKPProof::new(instance, &message).unwrap();
+    let verified = proof.verify_legacy(witness).unwrap();
+    assert!(verified)
+}
+
+#[test]
+fn schnorr_protocol_verify_legacy_fail() {
     let instance = Scalar::random();
     let message = Random::bytes(64);
     let proof = ZKPProof::new(instance, &message).unwrap();
     let faulty_instance = Scalar::random();
     let faulty_witness = ZKPWitness::new(faulty_instance).unwrap();
-    let verified = proof.verify(faulty_witness).unwrap();
+    let verified = proof.verify_legacy(faulty_witness).unwrap();
+    assert!(!verified)
+}
+
+#[test]
+fn schnorr_protocol_legacy_not_bound_fail() {
+    // NB: a legacy proof does not verify as a message-bound proof, and vice versa
+    let instance = Scalar::random();
+    let witness = ZKPWitness::new(instance.clone()).unwrap();
+    let message = Random::bytes(64);
+
+    let proof = ZKPProof::new(instance.clone(), &message).unwrap();
+    let verified = proof.verify(witness, &message, &[]).unwrap();
+    assert!(!verified);
+
+    let proof = ZKPProof::prove(instance, &message, &[]).unwrap();
+    let verified = proof.verify_legacy(witness).unwrap();
     assert!(!verified)
 }
+
+#[test]
+fn schnorr_protocol_forged_fail() {
+    // NB: t = (g^r)*(w^c) holds for any r and c with a chosen public coin, so the
+    // challenge must be checked
+    let witness = ZKPWitness::new(Scalar::random()).unwrap();
+    let response = Scalar::random();
+    let challenge = Scalar::random();
+    let public_coin = &(&Point::default() * &response) + &(&witness.to_point() * &challenge);
+
+    let proof = ZKPProof {
+        public_coin: public_coin,
+        challenge: challenge,
+        response: response,
+    };
+
+    assert!(!proof.verify(witness, b"message", &[]).unwrap());
+    assert!(!proof.verify_legacy(witness).unwrap())
+}
+
+#[test]
+fn schnorr_protocol_deterministic_nonce_succ() {
+    let instance = Scalar::random();
+    let message = Random::bytes(64);
+
+    let proof_a = ZKPProof::prove(instance.clone(), &message, b"context").unwrap();
+    let proof_b = ZKPProof::prove(instance.clone(), &message, b"context").unwrap();
+    assert_eq!(proof_a, proof_b);
+
+    // NB: the nonce depends on the secret instance
+    let proof_c = ZKPProof::prove(Scalar::random(), &message, b"context").unwrap();
+    assert_ne!(proof_a.public_coin, proof_c.public_coin);
+
+    // NB: a legacy proof of the same message uses another nonce
+    let proof_d = ZKPProof::new(instance, &message).unwrap();
+    assert_ne!(proof_a.public_coin, proof_d.public_coin)
+}
+
+#[test]
+fn schnorr_protocol_hedged_nonce_succ() {
+    let instance = Scalar::random();
+    let witness = ZKPWitness::new(instance.clone()).unwrap();
+    let message = Random::bytes(64);
+
+    let proof_a = ZKPProof::prove_hedged(instance.clone(), &message, b"context").unwrap();
+    let proof_b = ZKPProof::prove_hedged(instance.clone(), &message, b"context").unwrap();
+    assert_ne!(proof_a.public_coin, proof_b.public_coin);
+    assert!(proof_a.verify(witness, &message, b"context").unwrap());
+    assert!(proof_b.verify(witness, &message, b"context").unwrap());
+
+    let proof = ZKPProof::new_hedged(instance, &message).unwrap();
+    assert!(proof.verify_legacy(witness).unwrap())
+}
+
+/// Recovers the instance of a proof, given another proof with the same public coin
+/// and its instance: v = r1 + c1*x1, and x2 = (v - r2)/c2.
+fn recover_instance(proof_a: &ZKPProof, instance_a: &Scalar, proof_b: &ZKPProof) -> Scalar {
+    let v = &proof_a.response + &(&proof_a.challenge * instance_a);
+    &(&v - &proof_b.response) * &proof_b.challenge.invert().unwrap()
+}
+
+#[test]
+fn schnorr_protocol_shared_nonce_attack_fail() {
+    let message = Random::bytes(64);
+    let instance_a = Scalar::random();
+    let instance_b = Scalar::random();
+
+    // NB: the nonce used to be the hash of the message only, shared by all the
+    // proofs of a message, which leaks any instance given another one
+    let legacy_proof = |instance: &Scalar| {
+        let g = Point::default();
+        let witness = &g * instance;
+        let nonce = Scalar::from_hash(&message);
+        let public_coin = &g * &nonce;
+
+        let mut buf = g.to_bytes().unwrap();
+        buf.extend(witness.to_bytes().unwrap());
+        buf.extend(public_coin.to_bytes().unwrap());
+        let challenge = Scalar::from_hash(&buf);
+
+        ZKPProof {
+            public_coin: public_coin,
+            response: &nonce - &(&challenge * instance),
+            challenge: challenge,
+        }
+    };
+
+    let proof_a = legacy_proof(&instance_a);
+    let proof_b = legacy_proof(&instance_b);
+    assert!(proof_b.verify_legacy(ZKPWitness::new(instance_b.clone()).unwrap()).unwrap());
+    assert_eq!(recover_instance(&proof_a, &instance_a, &proof_b), instance_b);
+
+    for context in vec![None, Some(&b"context"[..])] {
+        let (proof_a, proof_b) = match context {
+            None => (
+                ZKPProof::new(instance_a.clone(), &message).unwrap(),
+                ZKPProof::new(instance_b.clone(), &message).unwrap(),
+            ),
+            Some(context) => (
+                ZKPProof::prove(instance_a.clone(), &message, context).unwrap(),
+                ZKPProof::prove(instance_b.clone(), &message, context).unwrap(),
+            ),
+        };
+
+        assert_ne!(proof_a.public_coin, proof_b.public_coin);
+        assert_ne!(recover_instance(&proof_a, &instance_a, &proof_b), instance_b);
+    }
+}
+
+fn batch(len: usize) -> Vec<(ZKPProof, ZKPWitness, Vec<u8>)> {
+    (0..len).map(|_| {
+        let instance = Scalar::random();
+        let witness = ZKPWitness::new(instance.clone()).unwrap();
+        let message = Random::bytes(32);
+        let proof = ZKPProof::prove(instance, &message, b"context").unwrap();
+
+        (proof, witness, message)
+    }).collect()
+}
+
+fn batch_items(batch: &[(ZKPProof, ZKPWitness, Vec<u8>)]) -> Vec<(ZKPProof, ZKPWitness, &[u8])> {
+    batch.iter()
+        .map(|&(ref proof, witness, ref message)| (proof.clone(), witness, &message[..]))
+        .collect()
+}
+
+#[test]
+fn schnorr_protocol_verify_batch_succ() {
+    let batch = batch(64);
+    let report = ZKPProof::verify_batch(&batch_items(&batch), b"context").unwrap();
+    assert!(report.is_valid());
+
+    let report = ZKPProof::verify_batch(&[], b"context").unwrap();
+    assert!(report.is_valid())
+}
+
+#[test]
+fn schnorr_protocol_verify_batch_fail() {
+    let mut batch = batch(64);
+
+    // NB: a wrong witness, a wrong message and a forged proof
+    batch[3].1 = ZKPWitness::new(Scalar::random()).unwrap();
+    batch[17].2 = Random::bytes(32);
+    let response = Scalar::random();
+    let challenge = Scalar::random();
+    let public_coin = &(&Point::default() * &response) + &(&batch[42].1.to_point() * &challenge);
+    batch[42].0 = ZKPProof {
+        public_coin: public_coin,
+        challenge: challenge,
+        response: response,
+    };
+
+    let report = ZKPProof::verify_batch(&batch_items(&batch), b"context").unwrap();
+    assert!(!report.is_valid());
+    assert_eq!(report.invalid, vec![3, 17, 42]);
+
+    let report = ZKPProof::verify_batch(&batch_items(&batch), b"other context").unwrap();
+    assert_eq!(report.invalid, (0..64).collect::<Vec<usize>>())
+}
+
+#[test]
+fn schnorr_protocol_verify_batch_relation_fail() {
+    let mut batch = batch(16);
+
+    // NB: a valid challenge with a wrong response is only caught by the relation
+    batch[5].0.response = &batch[5].0.response + &Scalar::from_u64(1).unwrap();
+
+    let report = ZKPProof::verify_batch(&batch_items(&batch), b"context").unwrap();
+    assert_eq!(report.invalid, vec![5])
+}
+
+#[test]
+fn schnorr_protocol_verify_batch_legacy_succ() {
+    let mut items: Vec<(ZKPProof, ZKPWitness)> = (0..32).map(|_| {
+        let instance = Scalar::random();
+        let witness = ZKPWitness::new(instance.clone()).unwrap();
+        let proof = ZKPProof::new(instance, &Random::bytes(32)).unwrap();
+
+        (proof, witness)
+    }).collect();
+
+    let report = ZKPProof::verify_batch_legacy(&items).unwrap();
+    assert!(report.is_valid());
+
+    items[9].1 = ZKPWitness::new(Scalar::random()).unwrap();
+    let report = ZKPProof::verify_batch_legacy(&items).unwrap();
+    assert_eq!(report.invalid, vec![9])
+}
+
+// NB: a point of order 8
+const SMALL_ORDER_POINT: &str = "c7176a703d4dd84fba3c0b760d10670f2a2053fa2c39ccc64ec7fd7792ac037a";
+
+const IDENTITY_POINT: &str = "0100000000000000000000000000000000000000000000000000000000000000";
+
+fn legacy_proof_with_coin(instance: &Scalar, nonce: &Scalar, torsion: &Point) -> ZKPProof {
+    let g = Point::default();
+    let witness = &g * instance;
+    let public_coin = &(&g * nonce) + torsion;
+
+    let mut buf = g.to_bytes().unwrap();
+    buf.extend(witness.to_bytes().unwrap());
+    buf.extend(public_coin.to_bytes().unwrap());
+    let challenge = Scalar::from_hash(&buf);
+
+    ZKPProof {
+        public_coin: public_coin,
+        response: nonce - &(&challenge * instance),
+        challenge: challenge,
+    }
+}
+
+#[test]
+fn schnorr_protocol_small_order_public_coin_fail() {
+    let torsion = Point::from_hex(SMALL_ORDER_POINT).unwrap();
+    assert!(torsion.is_small_order());
+
+    // NB: with a zero nonce the public coin is the small-order point, and the
+    // relation only holds up to the cofactor
+    let instance = Scalar::random();
+    let witness = ZKPWitness::new(instance.clone()).unwrap();
+    let proof = legacy_proof_with_coin(&instance, &Scalar::default(), &torsion);
+    assert!(proof.public_coin.is_small_order());
+
+    assert!(!proof.verify_legacy(witness).unwrap());
+    let report = ZKPProof::verify_batch_legacy(&[(proof, witness)]).unwrap();
+    assert_eq!(report.invalid, vec![0])
+}
+
+#[test]
+fn schnorr_protocol_torsion_public_coin_fail() {
+    let torsion = Point::from_hex(SMALL_ORDER_POINT).unwrap();
+
+    // NB: a public coin with a small-order component passes the relation only up to
+    // the cofactor, so the single, the batch and the compact verifications must all
+    // reject it
+    let instance = Scalar::random();
+    let witness = ZKPWitness::new(instance.clone()).unwrap();
+    let proof = legacy_proof_with_coin(&instance, &Scalar::random(), &torsion);
+    assert!(!proof.public_coin.is_small_order());
+    assert!(!proof.public_coin.is_torsion_free());
+
+    assert!(!proof.verify_legacy(witness).unwrap());
+    let report = ZKPProof::verify_batch_legacy(&[(proof.clone(), witness)]).unwrap();
+    assert_eq!(report.invalid, vec![0]);
+    assert!(!proof.to_compact().verify_legacy(witness).unwrap())
+}
+
+#[test]
+fn schnorr_protocol_torsion_compact_conversion_fail() {
+    let torsion = Point::from_hex(SMALL_ORDER_POINT).unwrap();
+
+    let instance = Scalar::random();
+    let witness = ZKPWitness::new(instance.clone()).unwrap();
+    let proof = legacy_proof_with_coin(&instance, &Scalar::random(), &torsion);
+
+    // NB: the restored proof drops the small-order component of the public coin,
+    // and both formats give the same answer
+    let compact = proof.to_compact();
+    let restored = ZKPProof::from_compact(&compact, witness).unwrap();
+    assert_ne!(restored.public_coin, proof.public_coin);
+
+    let valid = proof.verify_legacy(witness).unwrap();
+    assert_eq!(compact.verify_legacy(witness).unwrap(), valid);
+    assert_eq!(restored.verify_legacy(witness).unwrap(), valid);
+    assert_eq!(restored.to_compact().verify_legacy(witness).unwrap(), valid);
+    assert!(!valid);
+
+    // NB: without the small-order component the conversion keeps the proof valid
+    let identity = Point::from_hex(IDENTITY_POINT).unwrap();
+    let proof = legacy_proof_with_coin(&instance, &Scalar::random(), &identity);
+    let restored = ZKPProof::from_compact(&proof.to_compact(), witness).unwrap();
+    assert_eq!(restored, proof);
+    assert!(proof.verify_legacy(witness).unwrap());
+    assert!(proof.to_compact().verify_legacy(witness).unwrap());
+    assert!(restored.verify_legacy(witness).unwrap())
+}
+
+#[test]
+fn schnorr_protocol_small_order_witness_fail() {
+    let torsion = Point::from_hex(SMALL_ORDER_POINT).unwrap();
+
+    let res = ZKPWitness::from_point(torsion);
+    assert_eq!(res.unwrap_err().kind(), ErrorKind::InvalidArgument);
+
+    let res = ZKPWitness::from_hex(SMALL_ORDER_POINT);
+    assert_eq!(res.unwrap_err().kind(), ErrorKind::InvalidArgument);
+
+    let point = &(&Point::default() * &Scalar::random()) + &torsion;
+    let res = ZKPWitness::from_point(point);
+    assert_eq!(res.unwrap_err().kind(), ErrorKind::InvalidArgument);
+
+    let res = ZKPWitness::from_bytes(&Point::default().to_bytes().unwrap());
+    assert!(res.is_ok());
+
+    let proof = ZKPProof::new(Scalar::random(), b"message").unwrap();
+    let res = proof.verify_legacy(ZKPWitness(torsion));
+    assert_eq!(res.unwrap_err().kind(), ErrorKind::InvalidArgument);
+
+    let report = ZKPProof::verify_batch_legacy(&[(proof, ZKPWitness(torsion))]).unwrap();
+    assert_eq!(report.invalid, vec![0])
+}
+
+#[test]
+fn schnorr_protocol_compact_verify_succ() {
+    let instance = Scalar::random();
+    let witness = ZKPWitness::new(instance.clone()).unwrap();
+    let message = Random::bytes(64);
+    let proof = ZKPProof::prove(instance, &message, b"context").unwrap();
+    let compact = proof.to_compact();
+    assert!(compact.verify(witness, &message, b"context").unwrap())
+}
+
+#[test]
+fn schnorr_protocol_compact_verify_fail() {
+    let instance = Scalar::random();
+    let witness = ZKPWitness::new(instance.clone()).unwrap();
+    let message = Random::bytes(64);
+    let compact = ZKPProof::prove(instance, &message, b"context").unwrap().to_compact();
+
+    let faulty_witness = ZKPWitness::new(Scalar::random()).unwrap();
+    assert!(!compact.verify(faulty_witness, &message, b"context").unwrap());
+    assert!(!compact.verify(witness, &Random::bytes(64), b"context").unwrap());
+    assert!(!compact.verify(witness, &message, b"other context").unwrap());
+
+    let mut tampered = compact.clone();
+    tampered.response = Scalar::random();
+    assert!(!tampered.verify(witness, &message, b"context").unwrap())
+}
+
+#[test]
+fn schnorr_protocol_compact_verify_legacy_succ() {
+    let instance = Scalar::random();
+    let witness = ZKPWitness::new(instance.clone()).unwrap();
+    let compact = ZKPProof::new(instance, &Random::bytes(64)).unwrap().to_compact();
+    assert!(compact.verify_legacy(witness).unwrap());
+
+    let faulty_witness = ZKPWitness::new(Scalar::random()).unwrap();
+    assert!(!compact.verify_legacy(faulty_witness).unwrap())
+}
+
+#[test]
+fn schnorr_protocol_compact_conversion_succ() {
+    let instance = Scalar::random();
+    let witness = ZKPWitness::new(instance.clone()).unwrap();
+    let message = Random::bytes(64);
+    let proof = ZKPProof::prove(instance, &message, b"context").unwrap();
+
+    let compact = proof.to_compact();
+    assert_eq!(compact.challenge, proof.challenge);
+    assert_eq!(compact.response, proof.response);
+
+    let restored = ZKPProof::from_compact(&compact, witness).unwrap();
+    assert_eq!(restored, proof);
+    assert!(restored.verify(witness, &message, b"context").unwrap());
+
+    // NB: a compact proof restored with a wrong witness does not verify
+    let faulty_witness = ZKPWitness::new(Scalar::random()).unwrap();
+    let restored = ZKPProof::from_compact(&compact, faulty_witness).unwrap();
+    assert!(!restored.verify(faulty_witness, &message, b"context").unwrap())
+}
+
+#[test]
+fn schnorr_protocol_compact_to_bytes_succ() {
+    let proof = ZKPProof::prove(Scalar::random(), &Random::bytes(64), b"context").unwrap();
+    assert_eq!(proof.to_bytes().unwrap().len(), 96);
+
+    let compact_a = proof.to_compact();
+    let b = compact_a.to_bytes().unwrap();
+    assert_eq!(b.len(), 64);
+    let compact_b = ZKPCompactProof::from_bytes(&b).unwrap();
+    assert_eq!(compact_a, compact_b);
+
+    let res = ZKPCompactProof::from_bytes(&b[..63]);
+    assert!(res.is_err())
+}
+
+#[test]
+fn schnorr_protocol_compact_to_hex_succ() {
+    let compact_a = ZKPProof::prove(Scalar::random(), &Random::bytes(64), b"context").unwrap().to_compact();
+    let s = compact_a.to_hex().unwrap();
+    let compact_b = ZKPCompactProof::from_hex(&s).unwrap();
+    assert_eq!(compact_a, compact_b)
+}